@@ -386,7 +386,7 @@ export class ProgramService implements OnModuleInit {
 
       // Use the user program (with proper provider) instead of this.program
       const tx = await userProgram.methods
-        .swap(new BN(rawAmountIn), new BN(rawMinAmountOut), aToB)
+        .swap(new BN(rawAmountIn), new BN(rawMinAmountOut), aToB, null, null, 0, null, null)
         .accounts({
           poolState: poolConfig.poolState,
           userTokenA: userTokenA,
@@ -524,7 +524,7 @@ export class ProgramService implements OnModuleInit {
 
       // Use the user program (with proper provider) instead of this.program
       const tx = await userProgram.methods
-        .addLiquidity(new BN(rawAmountA), new BN(rawAmountB))
+        .addLiquidity(new BN(rawAmountA), new BN(rawAmountB), null)
         .accounts({
           poolState: poolConfig.poolState,
           userTokenA: userTokenA,
@@ -654,6 +654,7 @@ export class ProgramService implements OnModuleInit {
           new BN(rawLpAmount),
           new BN(rawMinAmountA),
           new BN(rawMinAmountB),
+          null,
         )
         .accounts({
           poolState: poolConfig.poolState,
@@ -0,0 +1,68 @@
+// Conversion between token base units and decimal amounts as people write them.
+
+use anyhow::{bail, Result};
+
+// Parses "1.5" into base units of a token with `decimals` decimals
+pub fn parse_amount(amount: &str, decimals: u8) -> Result<u64> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if whole.is_empty() && fraction.is_empty() || !(whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())) {
+        bail!("invalid amount {amount:?}");
+    }
+    if fraction.len() > decimals as usize {
+        bail!("amount {amount} has more than {decimals} decimal places");
+    }
+
+    let scale = 10u128.pow(decimals as u32);
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let fraction: u128 = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u128>()? * 10u128.pow((decimals as usize - fraction.len()) as u32)
+    };
+    let units = whole.checked_mul(scale).and_then(|units| units.checked_add(fraction));
+    match units.and_then(|units| u64::try_from(units).ok()) {
+        Some(units) => Ok(units),
+        None => bail!("amount {amount} is too large"),
+    }
+}
+
+// Formats base units as a decimal amount, e.g. 1500000 with 6 decimals as "1.5"
+pub fn format_amount(units: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return units.to_string();
+    }
+    let scale = 10u64.pow(decimals as u32);
+    let fraction = format!("{:0width$}", units % scale, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (units / scale).to_string()
+    } else {
+        format!("{}.{}", units / scale, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_decimal_amounts() {
+        assert_eq!(parse_amount("1.5", 6).unwrap(), 1_500_000);
+        assert_eq!(parse_amount("2", 9).unwrap(), 2_000_000_000);
+        assert_eq!(parse_amount(".25", 2).unwrap(), 25);
+        assert_eq!(parse_amount("7", 0).unwrap(), 7);
+        assert!(parse_amount("1.2345678", 6).is_err());
+        assert!(parse_amount("abc", 6).is_err());
+        assert!(parse_amount("-1", 6).is_err());
+        assert!(parse_amount(".", 6).is_err());
+        assert!(parse_amount("18446744073709551616", 0).is_err());
+    }
+
+    #[test]
+    fn formats_without_trailing_zeros() {
+        assert_eq!(format_amount(1_500_000, 6), "1.5");
+        assert_eq!(format_amount(2_000_000_000, 9), "2");
+        assert_eq!(format_amount(1, 6), "0.000001");
+        assert_eq!(format_amount(42, 0), "42");
+    }
+}
@@ -0,0 +1,583 @@
+// amm-cli: creates, inspects and trades on AMM pools from the command line.
+//
+//   amm-cli --url http://127.0.0.1:8899 --keypair ~/.config/solana/id.json list-pools
+//
+// Every command prints a human-readable summary, or JSON with --json.
+
+mod amount;
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, program_pack::Pack};
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use anchor_spl::token::spl_token::{self, state::{Account as TokenAccount, Mint}};
+use anyhow::{anyhow, bail, Context as _, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use solana_amm_client::instructions;
+use solana_amm_client::rpc::RpcClient;
+use solana_amm_client::state::decode_pool_state;
+use solana_amm_client::{
+    ClientError, PoolAddresses, PoolState, SwapQuote, UserAccounts, POOL_TYPE_CONCENTRATED, POOL_TYPE_STABLE,
+    POOL_TYPE_STANDARD,
+};
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use amount::{format_amount, parse_amount};
+
+const BPS_DENOMINATOR: u128 = 10_000;
+// Pool fee rates are out of 100_000 (300 = 0.3%)
+const FEE_RATE_DENOMINATOR: f64 = 100_000.0;
+
+#[derive(Parser)]
+#[command(name = "amm-cli", version, about = "Create, inspect and trade on AMM pools")]
+struct Cli {
+    /// RPC endpoint, e.g. a local solana-test-validator
+    #[arg(long, short, global = true, env = "AMM_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    /// Keypair that signs and pays; defaults to the Solana CLI's
+    #[arg(long, short, global = true, env = "AMM_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// Print JSON instead of a summary
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a pool for two mints
+    CreatePool {
+        #[arg(long)]
+        mint_a: Pubkey,
+        #[arg(long)]
+        mint_b: Pubkey,
+        #[arg(long, value_enum, default_value_t = PoolType::Standard)]
+        pool_type: PoolType,
+    },
+    /// Deposit both tokens for LP tokens
+    AddLiquidity {
+        #[arg(long)]
+        pool: Pubkey,
+        /// Decimal amounts, e.g. 1.5
+        #[arg(long)]
+        amount_a: String,
+        #[arg(long)]
+        amount_b: String,
+        /// Pay and receive the pool's wrapped SOL side as native SOL
+        #[arg(long)]
+        native_sol: bool,
+        /// Reject the transaction if it lands more than this many seconds from now
+        #[arg(long)]
+        deadline_secs: Option<i64>,
+    },
+    /// Burn LP tokens for a share of both reserves
+    RemoveLiquidity {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long, required_unless_present = "all", conflicts_with = "all")]
+        lp_amount: Option<String>,
+        /// Burn the whole LP balance
+        #[arg(long)]
+        all: bool,
+        /// Accepted shortfall against the expected output, in basis points
+        #[arg(long, default_value_t = 50)]
+        slippage_bps: u16,
+        /// Pay and receive the pool's wrapped SOL side as native SOL
+        #[arg(long)]
+        native_sol: bool,
+        #[arg(long)]
+        deadline_secs: Option<i64>,
+    },
+    /// Sell one of the pool's tokens for the other
+    Swap {
+        #[arg(long)]
+        pool: Pubkey,
+        /// Mint of the token being sold
+        #[arg(long)]
+        sell: Pubkey,
+        #[arg(long)]
+        amount: String,
+        #[arg(long, default_value_t = 50)]
+        slippage_bps: u16,
+        /// Fail if the swap moves the pool's price by more than this
+        #[arg(long)]
+        max_price_impact_bps: Option<u16>,
+        /// Pay and receive the pool's wrapped SOL side as native SOL
+        #[arg(long)]
+        native_sol: bool,
+        #[arg(long)]
+        deadline_secs: Option<i64>,
+    },
+    /// Price a swap without sending it
+    Quote {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        sell: Pubkey,
+        #[arg(long)]
+        amount: String,
+    },
+    /// Show a pool's state
+    PoolInfo {
+        #[arg(long)]
+        pool: Pubkey,
+    },
+    /// List every pool of the program
+    ListPools,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PoolType {
+    Standard,
+    Stable,
+    Concentrated,
+}
+
+impl PoolType {
+    fn id(self) -> u8 {
+        match self {
+            PoolType::Standard => POOL_TYPE_STANDARD,
+            PoolType::Stable => POOL_TYPE_STABLE,
+            PoolType::Concentrated => POOL_TYPE_CONCENTRATED,
+        }
+    }
+}
+
+fn pool_type_name(pool_type: u8) -> &'static str {
+    match pool_type {
+        POOL_TYPE_STANDARD => "standard",
+        POOL_TYPE_STABLE => "stable",
+        POOL_TYPE_CONCENTRATED => "concentrated",
+        _ => "unknown",
+    }
+}
+
+struct Context {
+    rpc: RpcClient,
+    keypair_path: PathBuf,
+    json: bool,
+}
+
+impl Context {
+    fn payer(&self) -> Result<Keypair> {
+        read_keypair_file(&self.keypair_path)
+            .map_err(|error| anyhow!("reading keypair {}: {error}", self.keypair_path.display()))
+    }
+
+    fn send(&self, payer: &Keypair, instructions: &[Instruction]) -> Result<String> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash);
+        Ok(self.rpc.send_and_confirm_transaction(&bincode::serialize(&transaction)?)?)
+    }
+
+    fn account_data(&self, address: &Pubkey) -> Result<Vec<u8>> {
+        self.rpc.get_account_data(address)?.ok_or_else(|| anyhow!("account {address} not found"))
+    }
+
+    fn pool(&self, address: &Pubkey) -> Result<(PoolAddresses, PoolState)> {
+        let state = decode_pool_state(&self.account_data(address)?).with_context(|| format!("loading pool {address}"))?;
+        let pool = PoolAddresses::from_state(&state);
+        if pool.pool_state != *address {
+            bail!("{address} is not the pool account for its mints");
+        }
+        Ok((pool, state))
+    }
+
+    // Prices a swap as the program would, walking the pool's ticks once
+    // ranged positions take part
+    fn quote(&self, pool: &PoolAddresses, state: &PoolState, amount_in: u64, a_to_b: bool) -> Result<SwapQuote> {
+        let quote = if state.uses_ticks() {
+            let ticks = self.rpc.get_pool_ticks(pool)?;
+            state.quote_swap_with_ticks(&ticks, amount_in, a_to_b)
+        } else {
+            state.quote_swap(amount_in, a_to_b)
+        };
+        quote.map_err(|error| anyhow!("{error}"))
+    }
+
+    fn mint(&self, address: &Pubkey) -> Result<Mint> {
+        Ok(Mint::unpack(&self.account_data(address)?)?)
+    }
+
+    // Balance of a token account, zero if it doesn't exist yet
+    fn token_balance(&self, address: &Pubkey) -> Result<u64> {
+        match self.rpc.get_account_data(address)? {
+            Some(data) => Ok(TokenAccount::unpack(&data)?.amount),
+            None => Ok(0),
+        }
+    }
+
+    fn print(&self, summary: &str, json: Value) {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&json).expect("JSON output"));
+        } else {
+            println!("{summary}");
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let keypair_path = cli.keypair.clone().unwrap_or_else(default_keypair_path);
+    let context = Context { rpc: RpcClient::new(&cli.url), keypair_path, json: cli.json };
+
+    if let Err(error) = run(&context, cli.command) {
+        // Name the program error behind a failed transaction
+        let program_error = error.downcast_ref::<ClientError>().and_then(ClientError::swap_error);
+        if context.json {
+            let json = json!({
+                "error": error.to_string(),
+                "program_error": program_error.map(|error| error.name()),
+            });
+            println!("{}", serde_json::to_string_pretty(&json).expect("JSON output"));
+        } else {
+            eprintln!("error: {error:#}");
+            if let Some(program_error) = program_error {
+                eprintln!("program error: {} ({program_error})", program_error.name());
+            }
+        }
+        std::process::exit(1);
+    }
+}
+
+fn default_keypair_path() -> PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_default();
+    PathBuf::from(home).join(".config/solana/id.json")
+}
+
+fn deadline(seconds_from_now: Option<i64>) -> Option<i64> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).expect("clock after 1970").as_secs() as i64;
+    seconds_from_now.map(|seconds| now + seconds)
+}
+
+fn apply_slippage(amount: u64, slippage_bps: u16) -> Result<u64> {
+    if slippage_bps as u128 > BPS_DENOMINATOR {
+        bail!("slippage can't exceed 10000 bps");
+    }
+    Ok((amount as u128 * (BPS_DENOMINATOR - slippage_bps as u128) / BPS_DENOMINATOR) as u64)
+}
+
+fn run(context: &Context, command: Command) -> Result<()> {
+    match command {
+        Command::CreatePool { mint_a, mint_b, pool_type } => create_pool(context, mint_a, mint_b, pool_type),
+        Command::AddLiquidity { pool, amount_a, amount_b, native_sol, deadline_secs } => {
+            add_liquidity(context, &pool, &amount_a, &amount_b, native_sol, deadline(deadline_secs))
+        }
+        Command::RemoveLiquidity { pool, lp_amount, all: _, slippage_bps, native_sol, deadline_secs } => {
+            remove_liquidity(context, &pool, lp_amount.as_deref(), slippage_bps, native_sol, deadline(deadline_secs))
+        }
+        Command::Swap { pool, sell, amount, slippage_bps, max_price_impact_bps, native_sol, deadline_secs } => {
+            swap(context, &pool, &sell, &amount, slippage_bps, max_price_impact_bps, native_sol, deadline(deadline_secs))
+        }
+        Command::Quote { pool, sell, amount } => quote(context, &pool, &sell, &amount),
+        Command::PoolInfo { pool } => pool_info(context, &pool),
+        Command::ListPools => list_pools(context),
+    }
+}
+
+fn create_pool(context: &Context, mint_a: Pubkey, mint_b: Pubkey, pool_type: PoolType) -> Result<()> {
+    let payer = context.payer()?;
+    let config = context.rpc.get_config()?;
+    let pool = PoolAddresses::new(mint_a, mint_b);
+
+    // While creation is permissioned the payer has to be on the allowlist
+    let allowlisted = !config.permissionless_pool_creation;
+    let ix = instructions::initialize_pool(payer.pubkey(), &pool, pool_type.id(), config.treasury, allowlisted);
+    let signature = context.send(&payer, &[ix])?;
+
+    context.print(
+        &format!("Created {} pool {}\nLP mint: {}\nSignature: {signature}", pool_type_name(pool_type.id()), pool.pool_state, pool.lp_mint),
+        json!({
+            "pool": pool.pool_state.to_string(),
+            "pool_type": pool_type_name(pool_type.id()),
+            "lp_mint": pool.lp_mint.to_string(),
+            "vault_a": pool.vault_a.to_string(),
+            "vault_b": pool.vault_b.to_string(),
+            "signature": signature,
+        }),
+    );
+    Ok(())
+}
+
+// The payer's accounts for the pool, with the wallet itself standing in for
+// the wrapped SOL side when trading native SOL
+fn user_accounts(owner: Pubkey, pool: &PoolAddresses, native_sol: bool) -> Result<UserAccounts> {
+    if !native_sol {
+        return Ok(UserAccounts::associated(owner, pool));
+    }
+    let user = UserAccounts::native_sol(owner, pool);
+    if !user.uses_native_sol() {
+        bail!("pool {} doesn't pair wrapped SOL", pool.pool_state);
+    }
+    Ok(user)
+}
+
+fn add_liquidity(
+    context: &Context,
+    address: &Pubkey,
+    amount_a: &str,
+    amount_b: &str,
+    native_sol: bool,
+    deadline: Option<i64>,
+) -> Result<()> {
+    let payer = context.payer()?;
+    let (pool, state) = context.pool(address)?;
+    let user = user_accounts(payer.pubkey(), &pool, native_sol)?;
+    let amount_a = parse_amount(amount_a, state.token_a_decimals)?;
+    let amount_b = parse_amount(amount_b, state.token_b_decimals)?;
+
+    let lp_before = context.token_balance(&user.lp)?;
+    let signature = context.send(
+        &payer,
+        &[
+            create_associated_token_account_idempotent(&payer.pubkey(), &payer.pubkey(), &pool.lp_mint, &spl_token::ID),
+            instructions::add_liquidity(&pool, &user, amount_a, amount_b, deadline),
+        ],
+    )?;
+    let lp_minted = context.token_balance(&user.lp)?.saturating_sub(lp_before);
+    let lp_decimals = context.mint(&pool.lp_mint)?.decimals;
+
+    context.print(
+        &format!(
+            "Deposited {} A and {} B for {} LP\nSignature: {signature}",
+            format_amount(amount_a, state.token_a_decimals),
+            format_amount(amount_b, state.token_b_decimals),
+            format_amount(lp_minted, lp_decimals),
+        ),
+        json!({
+            "pool": address.to_string(),
+            "amount_a": amount_a,
+            "amount_b": amount_b,
+            "lp_minted": lp_minted,
+            "signature": signature,
+        }),
+    );
+    Ok(())
+}
+
+fn remove_liquidity(
+    context: &Context,
+    address: &Pubkey,
+    lp_amount: Option<&str>,
+    slippage_bps: u16,
+    native_sol: bool,
+    deadline: Option<i64>,
+) -> Result<()> {
+    let payer = context.payer()?;
+    let (pool, state) = context.pool(address)?;
+    let user = user_accounts(payer.pubkey(), &pool, native_sol)?;
+    let lp_mint = context.mint(&pool.lp_mint)?;
+    let lp_amount = match lp_amount {
+        Some(amount) => parse_amount(amount, lp_mint.decimals)?,
+        None => context.token_balance(&user.lp)?,
+    };
+    if lp_amount == 0 || lp_mint.supply == 0 {
+        bail!("nothing to withdraw");
+    }
+
+    // Withdrawals are pro rata, so the expected output is exact at current reserves
+    let share = |reserve: u64| (reserve as u128 * lp_amount as u128 / lp_mint.supply as u128) as u64;
+    let (expected_a, expected_b) = (share(state.reserve_a), share(state.reserve_b));
+    let ix = instructions::remove_liquidity(
+        &pool,
+        &user,
+        lp_amount,
+        apply_slippage(expected_a, slippage_bps)?,
+        apply_slippage(expected_b, slippage_bps)?,
+        deadline,
+    );
+    let signature = context.send(&payer, &[ix])?;
+
+    context.print(
+        &format!(
+            "Burned {} LP for about {} A and {} B\nSignature: {signature}",
+            format_amount(lp_amount, lp_mint.decimals),
+            format_amount(expected_a, state.token_a_decimals),
+            format_amount(expected_b, state.token_b_decimals),
+        ),
+        json!({
+            "pool": address.to_string(),
+            "lp_amount": lp_amount,
+            "expected_a": expected_a,
+            "expected_b": expected_b,
+            "signature": signature,
+        }),
+    );
+    Ok(())
+}
+
+// Direction and decimals (input, output) of a swap selling `sell`
+fn swap_side(state: &PoolState, sell: &Pubkey) -> Result<(bool, u8, u8)> {
+    if *sell == state.token_a {
+        Ok((true, state.token_a_decimals, state.token_b_decimals))
+    } else if *sell == state.token_b {
+        Ok((false, state.token_b_decimals, state.token_a_decimals))
+    } else {
+        bail!("{sell} is not one of the pool's mints")
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn swap(
+    context: &Context,
+    address: &Pubkey,
+    sell: &Pubkey,
+    amount: &str,
+    slippage_bps: u16,
+    max_price_impact_bps: Option<u16>,
+    native_sol: bool,
+    deadline: Option<i64>,
+) -> Result<()> {
+    let payer = context.payer()?;
+    let (pool, state) = context.pool(address)?;
+    let (a_to_b, input_decimals, output_decimals) = swap_side(&state, sell)?;
+    let amount_in = parse_amount(amount, input_decimals)?;
+    let quote = context.quote(&pool, &state, amount_in, a_to_b)?;
+    let minimum_out = apply_slippage(quote.amount_out, slippage_bps)?;
+
+    let user = user_accounts(payer.pubkey(), &pool, native_sol)?;
+    let (output_mint, output_account) = if a_to_b { (state.token_b, user.token_b) } else { (state.token_a, user.token_a) };
+    let mut ixs = Vec::new();
+    // Native SOL output lands in the wallet itself
+    if output_account != payer.pubkey() {
+        ixs.push(create_associated_token_account_idempotent(&payer.pubkey(), &payer.pubkey(), &output_mint, &spl_token::ID));
+    }
+    ixs.push(instructions::swap_with_limits(&pool, &user, amount_in, minimum_out, a_to_b, max_price_impact_bps, None, deadline));
+    let signature = context.send(&payer, &ixs)?;
+
+    context.print(
+        &format!(
+            "Sold {} for about {} (at least {})\nSignature: {signature}",
+            format_amount(amount_in, input_decimals),
+            format_amount(quote.amount_out, output_decimals),
+            format_amount(minimum_out, output_decimals),
+        ),
+        json!({
+            "pool": address.to_string(),
+            "a_to_b": a_to_b,
+            "amount_in": amount_in,
+            "expected_out": quote.amount_out,
+            "minimum_out": minimum_out,
+            "signature": signature,
+        }),
+    );
+    Ok(())
+}
+
+fn quote(context: &Context, address: &Pubkey, sell: &Pubkey, amount: &str) -> Result<()> {
+    let (pool, state) = context.pool(address)?;
+    let (a_to_b, input_decimals, output_decimals) = swap_side(&state, sell)?;
+    let amount_in = parse_amount(amount, input_decimals)?;
+    let quote = context.quote(&pool, &state, amount_in, a_to_b)?;
+
+    // Output per unit of input, in whole tokens
+    let price = (quote.amount_out as f64 / 10f64.powi(output_decimals as i32))
+        / (amount_in as f64 / 10f64.powi(input_decimals as i32));
+    context.print(
+        &format!(
+            "{} in -> {} out\nFee: {}\nPrice: {price:.6} per token sold",
+            format_amount(amount_in, input_decimals),
+            format_amount(quote.amount_out, output_decimals),
+            format_amount(quote.fee, input_decimals),
+        ),
+        json!({
+            "pool": address.to_string(),
+            "a_to_b": a_to_b,
+            "amount_in": amount_in,
+            "amount_out": quote.amount_out,
+            "fee": quote.fee,
+            "creator_fee": quote.creator_fee,
+            "price": price,
+        }),
+    );
+    Ok(())
+}
+
+fn pool_json(address: &Pubkey, state: &PoolState) -> Value {
+    json!({
+        "pool": address.to_string(),
+        "pool_type": pool_type_name(state.pool_type),
+        "fee_rate": state.fee_rate,
+        "token_a": state.token_a.to_string(),
+        "token_b": state.token_b.to_string(),
+        "lp_mint": state.lp_mint.to_string(),
+        "reserve_a": state.reserve_a,
+        "reserve_b": state.reserve_b,
+        "token_a_decimals": state.token_a_decimals,
+        "token_b_decimals": state.token_b_decimals,
+        "creator": state.creator.to_string(),
+        "creator_fee_share_bps": state.creator_fee_share,
+        "creator_fees_a": state.creator_fees_a,
+        "creator_fees_b": state.creator_fees_b,
+        "version": state.version,
+    })
+}
+
+fn pool_info(context: &Context, address: &Pubkey) -> Result<()> {
+    let (pool, state) = context.pool(address)?;
+    let lp_mint = context.mint(&pool.lp_mint)?;
+    let mut json = pool_json(address, &state);
+    json["lp_supply"] = lp_mint.supply.into();
+
+    context.print(
+        &format!(
+            "Pool {address}\n\
+             Type: {} ({}% fee)\n\
+             Token A: {} (reserve {})\n\
+             Token B: {} (reserve {})\n\
+             LP mint: {} (supply {})\n\
+             Creator: {} ({} bps of fees, owed {} A / {} B)\n\
+             Version: {}",
+            pool_type_name(state.pool_type),
+            state.fee_rate as f64 / FEE_RATE_DENOMINATOR * 100.0,
+            state.token_a,
+            format_amount(state.reserve_a, state.token_a_decimals),
+            state.token_b,
+            format_amount(state.reserve_b, state.token_b_decimals),
+            state.lp_mint,
+            format_amount(lp_mint.supply, lp_mint.decimals),
+            state.creator,
+            state.creator_fee_share,
+            format_amount(state.creator_fees_a, state.token_a_decimals),
+            format_amount(state.creator_fees_b, state.token_b_decimals),
+            state.version,
+        ),
+        json,
+    );
+    Ok(())
+}
+
+fn list_pools(context: &Context) -> Result<()> {
+    let pools = context.rpc.get_pool_states()?;
+
+    let mut summary = format!("{} pools", pools.len());
+    let mut json = Vec::new();
+    for (address, state) in &pools {
+        match state {
+            Ok(state) => {
+                summary.push_str(&format!(
+                    "\n{address}  {:<12}  {} A / {} B",
+                    pool_type_name(state.pool_type),
+                    format_amount(state.reserve_a, state.token_a_decimals),
+                    format_amount(state.reserve_b, state.token_b_decimals),
+                ));
+                json.push(pool_json(address, state));
+            }
+            Err(error) => {
+                summary.push_str(&format!("\n{address}  {error}"));
+                json.push(json!({ "pool": address.to_string(), "error": error.to_string() }));
+            }
+        }
+    }
+    context.print(&summary, Value::Array(json));
+    Ok(())
+}
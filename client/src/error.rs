@@ -0,0 +1,159 @@
+// Client errors, and decoding of the program's SwapError from the forms a
+// failed transaction reports it in: a custom error code, the program logs,
+// or an RPC error message.
+
+use anchor_lang::error::ERROR_CODE_OFFSET;
+use anchor_lang::prelude::{ProgramError, Pubkey};
+use solana_amm_educational_template::{SwapError, MIN_POOL_STATE_VERSION};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("account data is not a valid {0}")]
+    InvalidAccountData(&'static str),
+    #[error("pool state version {0} predates version {MIN_POOL_STATE_VERSION}; older pools need migrate_pool")]
+    UnsupportedPoolVersion(u8),
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("RPC request failed: {0}")]
+    Rpc(String),
+    #[error("transaction failed: {message}")]
+    TransactionFailed { message: String, logs: Vec<String> },
+}
+
+impl ClientError {
+    // The program error behind a failed transaction, if it was a SwapError
+    pub fn swap_error(&self) -> Option<SwapError> {
+        match self {
+            ClientError::TransactionFailed { message, logs } => {
+                swap_error_from_logs(logs).or_else(|| swap_error_from_message(message))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Lists every SwapError with its custom error code. The assertions make
+// this fail to compile if a code no longer matches the program's, and the
+// match if the program gains an error the list lacks.
+macro_rules! swap_errors {
+    ($($code:literal => $variant:ident),* $(,)?) => {
+        const SWAP_ERRORS: &[(u32, SwapError)] = &[$(($code, SwapError::$variant)),*];
+
+        const _: () = {
+            $(assert!(SwapError::$variant as u32 + ERROR_CODE_OFFSET == $code);)*
+        };
+
+        #[allow(dead_code)]
+        fn listed(error: SwapError) {
+            match error {
+                $(SwapError::$variant)|* => {}
+            }
+        }
+    };
+}
+
+swap_errors!(
+    6000 => InsufficientLiquidity,
+    6001 => InvalidAmount,
+    6002 => SlippageExceeded,
+    6003 => InvalidTokenMint,
+    6004 => InvalidVaultAuthority,
+    6005 => InsufficientLpBalance,
+    6006 => InvalidPoolType,
+    6007 => MissingCallbackProgram,
+    6008 => InvalidCallbackProgram,
+    6009 => FlashSwapNotRepaid,
+    6010 => DeadlineExceeded,
+    6011 => InvalidPoolAccount,
+    6012 => PoolNotMigrated,
+    6013 => MathOverflow,
+    6014 => UnsupportedMintDecimals,
+    6015 => IdenticalMints,
+    6016 => MintRejectedByPolicy,
+    6017 => Unauthorized,
+    6018 => InvalidConfig,
+    6019 => InvalidTreasury,
+    6020 => InvalidFarmSchedule,
+    6021 => FarmUnderfunded,
+    6022 => InsufficientStake,
+    6023 => InvalidTickRange,
+    6024 => TooManyTicks,
+    6025 => InsufficientPositionLiquidity,
+    6026 => NotPositionHolder,
+    6027 => MissingPoolTicks,
+    6028 => InvalidOrderPrice,
+    6029 => OrderNotFilled,
+    6030 => OrderAlreadyFilled,
+    6031 => ReferralFeeTooHigh,
+    6032 => MissingReferrer,
+    6033 => PriceImpactExceeded,
+    6034 => PriceLimitExceeded,
+    6035 => InvalidPriceLimit,
+    6036 => CircuitBreakerTripped,
+    6037 => PoolNotEmpty,
+    6038 => InvalidNativeSol,
+    6039 => InvalidBatchSwap,
+    6040 => FarmNotFinished,
+    6041 => LiquidityBelowMinimum,
+    6042 => LimitOrderExists,
+);
+
+// The SwapError with the given custom error code, e.g. 6002
+pub fn decode_swap_error(code: u32) -> Option<SwapError> {
+    SWAP_ERRORS.iter().find(|(listed, _)| *listed == code).map(|(_, error)| *error)
+}
+
+pub fn swap_error_from_program_error(error: &ProgramError) -> Option<SwapError> {
+    match error {
+        ProgramError::Custom(code) => decode_swap_error(*code),
+        _ => None,
+    }
+}
+
+// Finds the error in a failed transaction's logs, which Anchor writes as
+// "... Error Code: SlippageExceeded. Error Number: 6002. ..."
+pub fn swap_error_from_logs<S: AsRef<str>>(logs: &[S]) -> Option<SwapError> {
+    logs.iter().find_map(|line| {
+        let number = line.as_ref().split("Error Number: ").nth(1)?;
+        let digits = number.split(|c: char| !c.is_ascii_digit()).next()?;
+        decode_swap_error(digits.parse().ok()?)
+    })
+}
+
+// Finds the error in a simulation or send error message, which reports it as
+// "custom program error: 0x1772"
+pub fn swap_error_from_message(message: &str) -> Option<SwapError> {
+    let hex = message.split("custom program error: 0x").nth(1)?;
+    let digits = hex.split(|c: char| !c.is_ascii_hexdigit()).next()?;
+    decode_swap_error(u32::from_str_radix(digits, 16).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listed_codes_match_the_program() {
+        for (index, (code, error)) in SWAP_ERRORS.iter().enumerate() {
+            assert_eq!(u32::from(*error), *code, "{error:?}");
+            assert_eq!(*code, ERROR_CODE_OFFSET + index as u32, "{error:?}");
+            assert_eq!(decode_swap_error(*code).map(u32::from), Some(*code));
+        }
+        assert!(decode_swap_error(ERROR_CODE_OFFSET + SWAP_ERRORS.len() as u32).is_none());
+        assert!(decode_swap_error(2006).is_none());
+    }
+
+    #[test]
+    fn decodes_logs_and_messages() {
+        let logs = [
+            "Program B6WsBQgwpFpQZMYLPt9groFwSjp2nKL7JBoTJASyEYb4 invoke [1]",
+            "Program log: AnchorError thrown in programs/solana-amm-educational-template/src/lib.rs:420. Error Code: SlippageExceeded. Error Number: 6002. Error Message: Slippage tolerance exceeded.",
+        ];
+        assert!(matches!(swap_error_from_logs(&logs), Some(SwapError::SlippageExceeded)));
+        assert!(swap_error_from_logs(&logs[..1]).is_none());
+
+        let message = "Error processing Instruction 0: custom program error: 0x1772";
+        assert!(matches!(swap_error_from_message(message), Some(SwapError::SlippageExceeded)));
+        assert!(swap_error_from_message("custom program error: 0x7d6").is_none());
+    }
+}
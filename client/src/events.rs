@@ -0,0 +1,71 @@
+// Decoding of the program's events. Anchor's `emit!` logs each event as a
+// "Program data: <base64>" line holding the event's discriminator followed by
+// its Borsh-encoded fields.
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::Engine;
+use solana_amm_educational_template::{
+    CircuitBreakerTripped, LimitOrdersFilled, LiquidityAdded, LiquidityRemoved, PoolInitialized, ReservesSynced,
+    SwapExecuted, ID,
+};
+
+#[derive(Clone, Debug)]
+pub enum AmmEvent {
+    PoolInitialized(PoolInitialized),
+    LiquidityAdded(LiquidityAdded),
+    LiquidityRemoved(LiquidityRemoved),
+    SwapExecuted(SwapExecuted),
+    ReservesSynced(ReservesSynced),
+    LimitOrdersFilled(LimitOrdersFilled),
+    CircuitBreakerTripped(CircuitBreakerTripped),
+}
+
+fn decode<T: AnchorDeserialize + Discriminator>(data: &[u8]) -> Option<T> {
+    let mut fields = data.strip_prefix(T::DISCRIMINATOR)?;
+    T::deserialize(&mut fields).ok()
+}
+
+// Decodes one `sol_log_data` payload, None if it isn't one of our events
+pub fn decode_event(data: &[u8]) -> Option<AmmEvent> {
+    decode(data)
+        .map(AmmEvent::PoolInitialized)
+        .or_else(|| decode(data).map(AmmEvent::LiquidityAdded))
+        .or_else(|| decode(data).map(AmmEvent::LiquidityRemoved))
+        .or_else(|| decode(data).map(AmmEvent::SwapExecuted))
+        .or_else(|| decode(data).map(AmmEvent::ReservesSynced))
+        .or_else(|| decode(data).map(AmmEvent::LimitOrdersFilled))
+        .or_else(|| decode(data).map(AmmEvent::CircuitBreakerTripped))
+}
+
+// Events in a transaction's logs, in emission order. Data lines only count
+// while the program itself is executing, so another program can't forge
+// events by logging the same bytes.
+pub fn events_from_logs<S: AsRef<str>>(logs: &[S]) -> Vec<AmmEvent> {
+    let program = ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        let line = line.as_ref();
+        if let Some(data) = line.strip_prefix("Program data: ") {
+            if stack.last() != Some(&program.as_str()) {
+                continue;
+            }
+            // Anchor logs a single field per event
+            let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data.trim()) else {
+                continue;
+            };
+            events.extend(decode_event(&bytes));
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            let mut words = rest.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(id), Some("invoke")) => stack.push(id),
+                (Some(_), Some("success" | "failed:")) => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    events
+}
@@ -0,0 +1,734 @@
+// Instruction builders, one per program instruction. Accounts are laid out by
+// the program's own `accounts::*` structs and data encoded by its
+// `instruction::*` structs.
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::{instruction::Instruction, sysvar};
+use anchor_lang::{system_program, AnchorDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use anchor_spl::{associated_token, token, token_2022};
+use solana_amm_educational_template::{accounts, instruction, ConfigParams, SwapLeg, ID};
+
+use crate::pda::{
+    find_config_address, find_farm_address, find_limit_order_address, find_pool_creator_address,
+    find_program_data_address, find_wsol_address, FarmAddresses, PoolAddresses, PositionAddresses, UserAccounts,
+};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction { program_id: ID, accounts: accounts.to_account_metas(None), data: data.data() }
+}
+
+// A user's token account as the program takes it: left out where
+// `UserAccounts::native_sol` put the wallet in its place
+fn user_token(account: Pubkey, user: &UserAccounts) -> Option<Pubkey> {
+    (account != user.owner).then_some(account)
+}
+
+// The wSOL account, native mint and system program a native SOL user adds
+fn native_sol_accounts(user: &UserAccounts) -> (Option<Pubkey>, Option<Pubkey>, Option<Pubkey>) {
+    if user.uses_native_sol() {
+        (Some(find_wsol_address(&user.owner).0), Some(token::spl_token::native_mint::ID), Some(system_program::ID))
+    } else {
+        (None, None, None)
+    }
+}
+
+// ---------- config ----------
+
+// Signed by the program's upgrade authority, which becomes the config admin
+pub fn initialize_config(admin: Pubkey, params: ConfigParams) -> Instruction {
+    build(
+        accounts::InitializeConfig {
+            amm_config: find_config_address().0,
+            admin,
+            program: ID,
+            program_data: find_program_data_address().0,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeConfig { params },
+    )
+}
+
+pub fn update_config(admin: Pubkey, params: ConfigParams) -> Instruction {
+    build(
+        accounts::UpdateConfig { amm_config: find_config_address().0, admin },
+        instruction::UpdateConfig { params },
+    )
+}
+
+pub fn add_pool_creator(admin: Pubkey, creator: Pubkey) -> Instruction {
+    build(
+        accounts::AddPoolCreator {
+            amm_config: find_config_address().0,
+            pool_creator: find_pool_creator_address(&creator).0,
+            admin,
+            system_program: system_program::ID,
+        },
+        instruction::AddPoolCreator { creator },
+    )
+}
+
+pub fn remove_pool_creator(admin: Pubkey, creator: Pubkey) -> Instruction {
+    build(
+        accounts::RemovePoolCreator {
+            amm_config: find_config_address().0,
+            pool_creator: find_pool_creator_address(&creator).0,
+            admin,
+        },
+        instruction::RemovePoolCreator {},
+    )
+}
+
+// ---------- pools ----------
+
+// `treasury` must be the config's treasury. Pass `allowlisted` when pool
+// creation is permissioned and the payer has a pool creator entry.
+pub fn initialize_pool(
+    payer: Pubkey,
+    pool: &PoolAddresses,
+    pool_type: u8,
+    treasury: Pubkey,
+    allowlisted: bool,
+) -> Instruction {
+    build(
+        accounts::InitializePool {
+            amm_config: find_config_address().0,
+            pool_creator: allowlisted.then(|| find_pool_creator_address(&payer).0),
+            treasury,
+            pool_state: pool.pool_state,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            lp_mint: pool.lp_mint,
+            token_a_vault: pool.vault_a,
+            token_b_vault: pool.vault_b,
+            pool_authority: pool.pool_authority,
+            payer,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::InitializePool { pool_type },
+    )
+}
+
+pub fn migrate_pool(payer: Pubkey, pool: &PoolAddresses) -> Instruction {
+    build(
+        accounts::MigratePool {
+            pool_state: pool.pool_state,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            token_a_vault: pool.vault_a,
+            token_b_vault: pool.vault_b,
+            payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigratePool {},
+    )
+}
+
+// Admin only. The pool must have no LP supply, empty vaults and no farm
+// (close_farm removes one); pass `with_ticks` if it ever had ranged
+// positions or limit orders, so its tick account is closed too.
+pub fn close_pool(admin: Pubkey, pool: &PoolAddresses, recipient: Pubkey, with_ticks: bool) -> Instruction {
+    build(
+        accounts::ClosePool {
+            amm_config: find_config_address().0,
+            pool_state: pool.pool_state,
+            lp_mint: pool.lp_mint,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            pool_authority: pool.pool_authority,
+            pool_ticks: with_ticks.then_some(pool.ticks),
+            farm: find_farm_address(&pool.pool_state).0,
+            admin,
+            recipient,
+            token_program: token::ID,
+        },
+        instruction::ClosePool {},
+    )
+}
+
+// ---------- liquidity ----------
+
+pub fn add_liquidity(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    amount_a: u64,
+    amount_b: u64,
+    deadline: Option<i64>,
+) -> Instruction {
+    let (wsol_account, native_mint, system_program) = native_sol_accounts(user);
+    build(
+        accounts::AddLiquidity {
+            pool_state: pool.pool_state,
+            user_token_a: user_token(user.token_a, user),
+            user_token_b: user_token(user.token_b, user),
+            user_lp_token: user.lp,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            lp_mint: pool.lp_mint,
+            pool_authority: pool.pool_authority,
+            user_authority: user.owner,
+            token_program: token::ID,
+            wsol_account,
+            native_mint,
+            system_program,
+        },
+        instruction::AddLiquidity { amount_a, amount_b, deadline },
+    )
+}
+
+pub fn remove_liquidity(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    lp_amount: u64,
+    minimum_a_out: u64,
+    minimum_b_out: u64,
+    deadline: Option<i64>,
+) -> Instruction {
+    let (wsol_account, native_mint, system_program) = native_sol_accounts(user);
+    build(
+        accounts::RemoveLiquidity {
+            pool_state: pool.pool_state,
+            user_token_a: user_token(user.token_a, user),
+            user_token_b: user_token(user.token_b, user),
+            user_lp_token: user.lp,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            lp_mint: pool.lp_mint,
+            pool_authority: pool.pool_authority,
+            user_authority: user.owner,
+            token_program: token::ID,
+            wsol_account,
+            native_mint,
+            system_program,
+        },
+        instruction::RemoveLiquidity { lp_amount, minimum_a_out, minimum_b_out, deadline },
+    )
+}
+
+// ---------- swaps ----------
+
+fn swap_accounts(pool: &PoolAddresses, user: &UserAccounts, referrer: Option<Pubkey>) -> accounts::Swap {
+    let (wsol_account, native_mint, system_program) = native_sol_accounts(user);
+    accounts::Swap {
+        pool_state: pool.pool_state,
+        user_token_a: user_token(user.token_a, user),
+        user_token_b: user_token(user.token_b, user),
+        pool_token_a_vault: pool.vault_a,
+        pool_token_b_vault: pool.vault_b,
+        pool_authority: pool.pool_authority,
+        user_authority: user.owner,
+        token_program: token::ID,
+        pool_ticks: pool.ticks,
+        amm_config: find_config_address().0,
+        referrer_token_account: referrer,
+        wsol_account,
+        native_mint,
+        system_program,
+    }
+}
+
+pub fn swap(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    a_to_b: bool,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        swap_accounts(pool, user, None),
+        instruction::Swap {
+            amount_in,
+            minimum_amount_out,
+            a_to_b,
+            callback_data: None,
+            deadline,
+            referral_fee_bps: 0,
+            max_price_impact_bps: None,
+            sqrt_price_limit_x64: None,
+        },
+    )
+}
+
+// A swap that fails if it moves the spot price by more than
+// `max_price_impact_bps`, and doesn't take the price past
+// `sqrt_price_limit_x64` (a Q64.64 square root of token B per token A).
+// Standard and concentrated pools stop at the limit and leave the rest of
+// the input with the user; stable pools fail instead.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_with_limits(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    a_to_b: bool,
+    max_price_impact_bps: Option<u16>,
+    sqrt_price_limit_x64: Option<u128>,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        swap_accounts(pool, user, None),
+        instruction::Swap {
+            amount_in,
+            minimum_amount_out,
+            a_to_b,
+            callback_data: None,
+            deadline,
+            referral_fee_bps: 0,
+            max_price_impact_bps,
+            sqrt_price_limit_x64,
+        },
+    )
+}
+
+// A swap that pays `referral_fee_bps` of its fee to `referrer`, a token
+// account for the input token
+#[allow(clippy::too_many_arguments)]
+pub fn swap_with_referral(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    a_to_b: bool,
+    referrer: Pubkey,
+    referral_fee_bps: u16,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        swap_accounts(pool, user, Some(referrer)),
+        instruction::Swap {
+            amount_in,
+            minimum_amount_out,
+            a_to_b,
+            callback_data: None,
+            deadline,
+            referral_fee_bps,
+            max_price_impact_bps: None,
+            sqrt_price_limit_x64: None,
+        },
+    )
+}
+
+// The borrower a flash swap calls between paying out and checking repayment
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlashSwapCallback {
+    pub program: Pubkey,
+    // Instruction data and accounts for the callback instruction
+    pub data: Vec<u8>,
+    pub accounts: Vec<AccountMeta>,
+}
+
+// A swap that pays out first and calls `callback`, which must leave the input
+// vault repaid before the swap completes
+pub fn flash_swap(
+    pool: &PoolAddresses,
+    user: &UserAccounts,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    a_to_b: bool,
+    callback: FlashSwapCallback,
+    deadline: Option<i64>,
+) -> Instruction {
+    let mut ix = build(
+        swap_accounts(pool, user, None),
+        instruction::Swap {
+            amount_in,
+            minimum_amount_out,
+            a_to_b,
+            callback_data: Some(callback.data),
+            deadline,
+            referral_fee_bps: 0,
+            max_price_impact_bps: None,
+            sqrt_price_limit_x64: None,
+        },
+    );
+    ix.accounts.push(AccountMeta::new_readonly(callback.program, false));
+    ix.accounts.extend(callback.accounts);
+    ix
+}
+
+// Runs `legs` in order, all or nothing. Each leg's `pool_index` picks one of
+// `pools`, given with the wallet's token accounts for it; every
+// UserAccounts must have the same owner, who signs. Native SOL isn't
+// supported. The outputs come back as return data, see
+// `decode_batch_swap_outputs`.
+pub fn batch_swap(pools: &[(PoolAddresses, UserAccounts)], legs: Vec<SwapLeg>, deadline: Option<i64>) -> Instruction {
+    let owner = pools.first().map(|(_, user)| user.owner).unwrap_or_default();
+    let mut ix = build(
+        accounts::BatchSwap {
+            user_authority: owner,
+            token_program: token::ID,
+            amm_config: find_config_address().0,
+            amm_program: ID,
+        },
+        instruction::BatchSwap { legs, deadline },
+    );
+    for (pool, user) in pools {
+        ix.accounts.extend([
+            AccountMeta::new(pool.pool_state, false),
+            AccountMeta::new(user.token_a, false),
+            AccountMeta::new(user.token_b, false),
+            AccountMeta::new(pool.vault_a, false),
+            AccountMeta::new(pool.vault_b, false),
+            AccountMeta::new_readonly(pool.pool_authority, false),
+            AccountMeta::new(pool.ticks, false),
+        ]);
+    }
+    ix
+}
+
+// ---------- reserves and fees ----------
+
+pub fn sync(pool: &PoolAddresses) -> Instruction {
+    build(
+        accounts::SyncReserves {
+            pool_state: pool.pool_state,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+        },
+        instruction::Sync {},
+    )
+}
+
+pub fn skim(pool: &PoolAddresses, recipient_token_a: Pubkey, recipient_token_b: Pubkey) -> Instruction {
+    build(
+        accounts::Skim {
+            pool_state: pool.pool_state,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            recipient_token_a,
+            recipient_token_b,
+            pool_authority: pool.pool_authority,
+            token_program: token::ID,
+        },
+        instruction::Skim {},
+    )
+}
+
+// Signed by the pool's creator
+pub fn collect_creator_fees(pool: &PoolAddresses, creator: &UserAccounts) -> Instruction {
+    build(
+        accounts::CollectCreatorFees {
+            pool_state: pool.pool_state,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            creator_token_a: creator.token_a,
+            creator_token_b: creator.token_b,
+            pool_authority: pool.pool_authority,
+            creator: creator.owner,
+            token_program: token::ID,
+        },
+        instruction::CollectCreatorFees {},
+    )
+}
+
+// ---------- ranged positions ----------
+
+// Signed by the owner and the new position mint's keypair. Only for
+// concentrated pools; the NFT goes to the owner's associated account.
+pub fn open_position(
+    pool: &PoolAddresses,
+    position: &PositionAddresses,
+    owner: Pubkey,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Instruction {
+    build(
+        accounts::OpenPosition {
+            pool_state: pool.pool_state,
+            pool_ticks: pool.ticks,
+            position: position.position,
+            position_mint: position.position_mint,
+            position_token_account: position.token_account(&owner),
+            pool_authority: pool.pool_authority,
+            owner,
+            system_program: system_program::ID,
+            token_2022_program: token_2022::ID,
+            associated_token_program: associated_token::ID,
+        },
+        instruction::OpenPosition { tick_lower, tick_upper },
+    )
+}
+
+fn modify_position_accounts(
+    pool: &PoolAddresses,
+    position: &PositionAddresses,
+    user: &UserAccounts,
+) -> accounts::ModifyPosition {
+    accounts::ModifyPosition {
+        pool_state: pool.pool_state,
+        pool_ticks: pool.ticks,
+        position: position.position,
+        position_token_account: position.token_account(&user.owner),
+        user_token_a: user.token_a,
+        user_token_b: user.token_b,
+        pool_token_a_vault: pool.vault_a,
+        pool_token_b_vault: pool.vault_b,
+        pool_authority: pool.pool_authority,
+        owner: user.owner,
+        token_program: token::ID,
+    }
+}
+
+// Signed by whoever holds the position NFT in their associated account
+pub fn increase_liquidity(
+    pool: &PoolAddresses,
+    position: &PositionAddresses,
+    user: &UserAccounts,
+    amount_a_max: u64,
+    amount_b_max: u64,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        modify_position_accounts(pool, position, user),
+        instruction::IncreaseLiquidity { amount_a_max, amount_b_max, deadline },
+    )
+}
+
+pub fn decrease_liquidity(
+    pool: &PoolAddresses,
+    position: &PositionAddresses,
+    user: &UserAccounts,
+    liquidity: u128,
+    minimum_a_out: u64,
+    minimum_b_out: u64,
+    deadline: Option<i64>,
+) -> Instruction {
+    build(
+        modify_position_accounts(pool, position, user),
+        instruction::DecreaseLiquidity { liquidity, minimum_a_out, minimum_b_out, deadline },
+    )
+}
+
+// Pays the position's earned fees to `user`'s token accounts
+pub fn collect_fees(pool: &PoolAddresses, position: &PositionAddresses, user: &UserAccounts) -> Instruction {
+    build(modify_position_accounts(pool, position, user), instruction::CollectFees {})
+}
+
+// ---------- limit orders ----------
+
+// Signed by `user.owner`, who pays the deposit from their token account for
+// the token being sold
+pub fn place_limit_order(pool: &PoolAddresses, user: &UserAccounts, tick: i32, sells_a: bool, amount: u64) -> Instruction {
+    build(
+        accounts::PlaceLimitOrder {
+            pool_state: pool.pool_state,
+            pool_ticks: pool.ticks,
+            limit_order: find_limit_order_address(&pool.pool_state, &user.owner, tick).0,
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            owner: user.owner,
+            system_program: system_program::ID,
+            token_program: token::ID,
+        },
+        instruction::PlaceLimitOrder { tick, sells_a, amount },
+    )
+}
+
+fn settle_limit_order_accounts(pool: &PoolAddresses, user: &UserAccounts, tick: i32) -> accounts::SettleLimitOrder {
+    accounts::SettleLimitOrder {
+        pool_state: pool.pool_state,
+        pool_ticks: pool.ticks,
+        limit_order: find_limit_order_address(&pool.pool_state, &user.owner, tick).0,
+        user_token_a: user.token_a,
+        user_token_b: user.token_b,
+        pool_token_a_vault: pool.vault_a,
+        pool_token_b_vault: pool.vault_b,
+        pool_authority: pool.pool_authority,
+        owner: user.owner,
+        token_program: token::ID,
+    }
+}
+
+pub fn claim_filled_order(pool: &PoolAddresses, user: &UserAccounts, tick: i32) -> Instruction {
+    build(settle_limit_order_accounts(pool, user, tick), instruction::ClaimFilledOrder {})
+}
+
+pub fn cancel_limit_order(pool: &PoolAddresses, user: &UserAccounts, tick: i32) -> Instruction {
+    build(settle_limit_order_accounts(pool, user, tick), instruction::CancelLimitOrder {})
+}
+
+// ---------- farms ----------
+
+// Signed by the config admin; `admin_reward_token` pays for the whole
+// schedule up front
+pub fn create_farm(
+    farm: &FarmAddresses,
+    admin: Pubkey,
+    admin_reward_token: Pubkey,
+    start_time: i64,
+    end_time: i64,
+    emission_rate: u64,
+) -> Instruction {
+    build(
+        accounts::CreateFarm {
+            amm_config: find_config_address().0,
+            pool_state: farm.pool_state,
+            lp_mint: farm.lp_mint,
+            reward_mint: farm.reward_mint,
+            farm: farm.farm,
+            stake_vault: farm.stake_vault,
+            reward_vault: farm.reward_vault,
+            admin_reward_token,
+            admin,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateFarm { start_time, end_time, emission_rate },
+    )
+}
+
+pub fn fund_farm(farm: &FarmAddresses, admin: Pubkey, admin_reward_token: Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::FundFarm {
+            amm_config: find_config_address().0,
+            farm: farm.farm,
+            reward_vault: farm.reward_vault,
+            admin_reward_token,
+            admin,
+            token_program: token::ID,
+        },
+        instruction::FundFarm { amount },
+    )
+}
+
+// The reward vault must already hold everything still owed under the new schedule
+pub fn extend_farm(farm: &FarmAddresses, admin: Pubkey, end_time: i64, emission_rate: u64) -> Instruction {
+    build(
+        accounts::ExtendFarm {
+            amm_config: find_config_address().0,
+            farm: farm.farm,
+            reward_vault: farm.reward_vault,
+            admin,
+        },
+        instruction::ExtendFarm { end_time, emission_rate },
+    )
+}
+
+// Sends the admin what the reward vault holds beyond what the farm still owes
+pub fn reclaim_farm_rewards(farm: &FarmAddresses, admin: Pubkey, admin_reward_token: Pubkey) -> Instruction {
+    build(
+        accounts::ReclaimFarmRewards {
+            amm_config: find_config_address().0,
+            farm: farm.farm,
+            reward_vault: farm.reward_vault,
+            admin_reward_token,
+            admin,
+            token_program: token::ID,
+        },
+        instruction::ReclaimFarmRewards {},
+    )
+}
+
+// Admin only. The farm must have ended with nothing staked and every reward
+// claimed; leftover rewards go to `admin_reward_token`, rent to `recipient`
+pub fn close_farm(farm: &FarmAddresses, admin: Pubkey, admin_reward_token: Pubkey, recipient: Pubkey) -> Instruction {
+    build(
+        accounts::CloseFarm {
+            amm_config: find_config_address().0,
+            farm: farm.farm,
+            lp_mint: farm.lp_mint,
+            stake_vault: farm.stake_vault,
+            reward_vault: farm.reward_vault,
+            admin_reward_token,
+            admin,
+            recipient,
+            token_program: token::ID,
+        },
+        instruction::CloseFarm {},
+    )
+}
+
+pub fn stake_lp(farm: &FarmAddresses, user: &UserAccounts, amount: u64) -> Instruction {
+    build(
+        accounts::StakeLp {
+            farm: farm.farm,
+            stake_position: farm.stake_position(&user.owner),
+            stake_vault: farm.stake_vault,
+            user_lp_token: user.lp,
+            owner: user.owner,
+            system_program: system_program::ID,
+            token_program: token::ID,
+        },
+        instruction::StakeLp { amount },
+    )
+}
+
+pub fn unstake_lp(farm: &FarmAddresses, user: &UserAccounts, amount: u64) -> Instruction {
+    build(
+        accounts::UnstakeLp {
+            farm: farm.farm,
+            stake_position: farm.stake_position(&user.owner),
+            stake_vault: farm.stake_vault,
+            user_lp_token: user.lp,
+            owner: user.owner,
+            token_program: token::ID,
+        },
+        instruction::UnstakeLp { amount },
+    )
+}
+
+pub fn claim_rewards(farm: &FarmAddresses, owner: Pubkey, user_reward_token: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimRewards {
+            farm: farm.farm,
+            stake_position: farm.stake_position(&owner),
+            reward_vault: farm.reward_vault,
+            user_reward_token,
+            owner,
+            token_program: token::ID,
+        },
+        instruction::ClaimRewards {},
+    )
+}
+
+// ---------- decoding ----------
+
+const INSTRUCTION_NAMES: &[(&[u8], &str)] = &[
+    (instruction::InitializeConfig::DISCRIMINATOR, "initialize_config"),
+    (instruction::UpdateConfig::DISCRIMINATOR, "update_config"),
+    (instruction::AddPoolCreator::DISCRIMINATOR, "add_pool_creator"),
+    (instruction::RemovePoolCreator::DISCRIMINATOR, "remove_pool_creator"),
+    (instruction::InitializePool::DISCRIMINATOR, "initialize_pool"),
+    (instruction::MigratePool::DISCRIMINATOR, "migrate_pool"),
+    (instruction::ClosePool::DISCRIMINATOR, "close_pool"),
+    (instruction::AddLiquidity::DISCRIMINATOR, "add_liquidity"),
+    (instruction::RemoveLiquidity::DISCRIMINATOR, "remove_liquidity"),
+    (instruction::Swap::DISCRIMINATOR, "swap"),
+    (instruction::BatchSwap::DISCRIMINATOR, "batch_swap"),
+    (instruction::Sync::DISCRIMINATOR, "sync"),
+    (instruction::Skim::DISCRIMINATOR, "skim"),
+    (instruction::CollectCreatorFees::DISCRIMINATOR, "collect_creator_fees"),
+    (instruction::OpenPosition::DISCRIMINATOR, "open_position"),
+    (instruction::IncreaseLiquidity::DISCRIMINATOR, "increase_liquidity"),
+    (instruction::DecreaseLiquidity::DISCRIMINATOR, "decrease_liquidity"),
+    (instruction::CollectFees::DISCRIMINATOR, "collect_fees"),
+    (instruction::PlaceLimitOrder::DISCRIMINATOR, "place_limit_order"),
+    (instruction::ClaimFilledOrder::DISCRIMINATOR, "claim_filled_order"),
+    (instruction::CancelLimitOrder::DISCRIMINATOR, "cancel_limit_order"),
+    (instruction::CreateFarm::DISCRIMINATOR, "create_farm"),
+    (instruction::FundFarm::DISCRIMINATOR, "fund_farm"),
+    (instruction::ExtendFarm::DISCRIMINATOR, "extend_farm"),
+    (instruction::ReclaimFarmRewards::DISCRIMINATOR, "reclaim_farm_rewards"),
+    (instruction::CloseFarm::DISCRIMINATOR, "close_farm"),
+    (instruction::StakeLp::DISCRIMINATOR, "stake_lp"),
+    (instruction::UnstakeLp::DISCRIMINATOR, "unstake_lp"),
+    (instruction::ClaimRewards::DISCRIMINATOR, "claim_rewards"),
+];
+
+// Name of the program instruction encoded in `data`, e.g. "swap"
+pub fn instruction_name(data: &[u8]) -> Option<&'static str> {
+    INSTRUCTION_NAMES
+        .iter()
+        .find(|(discriminator, _)| data.starts_with(discriminator))
+        .map(|(_, name)| *name)
+}
+
+// Each leg's output from a batch_swap's return data
+pub fn decode_batch_swap_outputs(return_data: &[u8]) -> Option<Vec<u64>> {
+    Vec::<u64>::try_from_slice(return_data).ok()
+}
@@ -0,0 +1,28 @@
+// Rust client for the AMM program: PDA derivation, instruction builders,
+// account, event and SwapError decoding. The account and instruction layouts
+// come from the program crate itself, so they can't drift from what is deployed.
+//
+// Enable the `rpc` feature for blocking helpers that fetch accounts from a node.
+
+pub mod error;
+pub mod events;
+pub mod instructions;
+pub mod pda;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod state;
+
+pub use error::{decode_swap_error, swap_error_from_logs, swap_error_from_message, ClientError};
+pub use events::{decode_event, events_from_logs, AmmEvent};
+pub use pda::{FarmAddresses, PoolAddresses, PositionAddresses, UserAccounts};
+pub use solana_amm_educational_template::{
+    AmmConfig, CircuitBreakerTripped, ConfigParams, Farm, LimitOrder, LimitOrdersFilled, LiquidityAdded, LiquidityRemoved, PoolCreator,
+    PoolInitialized, PoolState, PoolTicks, Position, ReservesSynced, StakePosition, SwapError, SwapExecuted,
+    SwapLeg, SwapQuote, Tick, ID,
+    MINT_FLAG_FREEZE_AUTHORITY, MINT_FLAG_PERMANENT_DELEGATE, MINT_FLAG_TRANSFER_HOOK, MIN_POOL_STATE_VERSION, POOL_STATE_VERSION, TICK_SPACING,
+};
+
+// Pool types accepted by initialize_pool
+pub const POOL_TYPE_STANDARD: u8 = 0;
+pub const POOL_TYPE_STABLE: u8 = 1;
+pub const POOL_TYPE_CONCENTRATED: u8 = 2;
@@ -0,0 +1,220 @@
+// Program derived addresses. Pool accounts are seeded by a prefix and the two
+// token mints, in pool order.
+
+use anchor_lang::prelude::Pubkey;
+// solana-program 2.x deprecates its loader modules in favour of separate
+// interface crates; anchor-lang 0.31 still uses these
+#[allow(deprecated)]
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_spl::associated_token::{get_associated_token_address, get_associated_token_address_with_program_id};
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_2022;
+use solana_amm_educational_template::{PoolState, ID};
+
+const CONFIG_SEED: &[u8] = b"amm_config";
+const POOL_CREATOR_SEED: &[u8] = b"pool_creator";
+const POOL_SEED: &[u8] = b"pool";
+const POOL_AUTHORITY_SEED: &[u8] = b"pool_authority";
+const LP_MINT_SEED: &[u8] = b"lp_mint";
+const VAULT_A_SEED: &[u8] = b"vault_a";
+const VAULT_B_SEED: &[u8] = b"vault_b";
+const FARM_SEED: &[u8] = b"farm";
+const FARM_STAKE_VAULT_SEED: &[u8] = b"farm_stake_vault";
+const FARM_REWARD_VAULT_SEED: &[u8] = b"farm_reward_vault";
+const STAKE_POSITION_SEED: &[u8] = b"stake_position";
+const TICKS_SEED: &[u8] = b"ticks";
+const POSITION_SEED: &[u8] = b"position";
+const LIMIT_ORDER_SEED: &[u8] = b"limit_order";
+const WSOL_SEED: &[u8] = b"wsol";
+
+pub fn find_config_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], &ID)
+}
+
+pub fn find_pool_creator_address(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POOL_CREATOR_SEED, creator.as_ref()], &ID)
+}
+
+// The program's ProgramData account, which records its upgrade authority
+pub fn find_program_data_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID)
+}
+
+fn find_pool_pda(prefix: &[u8], token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[prefix, token_a_mint.as_ref(), token_b_mint.as_ref()], &ID)
+}
+
+pub fn find_pool_address(token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> (Pubkey, u8) {
+    find_pool_pda(POOL_SEED, token_a_mint, token_b_mint)
+}
+
+pub fn find_pool_authority_address(token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> (Pubkey, u8) {
+    find_pool_pda(POOL_AUTHORITY_SEED, token_a_mint, token_b_mint)
+}
+
+pub fn find_lp_mint_address(token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> (Pubkey, u8) {
+    find_pool_pda(LP_MINT_SEED, token_a_mint, token_b_mint)
+}
+
+pub fn find_vault_a_address(token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> (Pubkey, u8) {
+    find_pool_pda(VAULT_A_SEED, token_a_mint, token_b_mint)
+}
+
+pub fn find_vault_b_address(token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> (Pubkey, u8) {
+    find_pool_pda(VAULT_B_SEED, token_a_mint, token_b_mint)
+}
+
+// A concentrated pool's initialized ticks
+pub fn find_pool_ticks_address(pool_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TICKS_SEED, pool_state.as_ref()], &ID)
+}
+
+// The ranged position behind a position NFT
+pub fn find_position_address(position_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POSITION_SEED, position_mint.as_ref()], &ID)
+}
+
+// An owner's limit order at one tick of a concentrated pool
+pub fn find_limit_order_address(pool_state: &Pubkey, owner: &Pubkey, tick: i32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LIMIT_ORDER_SEED, pool_state.as_ref(), owner.as_ref(), &tick.to_le_bytes()], &ID)
+}
+
+// The temporary wSOL account the program wraps an owner's native SOL in
+pub fn find_wsol_address(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[WSOL_SEED, owner.as_ref()], &ID)
+}
+
+// A pool's farm, and the vaults holding its staked LP tokens and rewards
+pub fn find_farm_address(pool_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_SEED, pool_state.as_ref()], &ID)
+}
+
+pub fn find_farm_stake_vault_address(farm: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_STAKE_VAULT_SEED, farm.as_ref()], &ID)
+}
+
+pub fn find_farm_reward_vault_address(farm: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_REWARD_VAULT_SEED, farm.as_ref()], &ID)
+}
+
+pub fn find_stake_position_address(farm: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_POSITION_SEED, farm.as_ref(), owner.as_ref()], &ID)
+}
+
+// Every account a pool owns
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolAddresses {
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub pool_state: Pubkey,
+    pub pool_authority: Pubkey,
+    pub lp_mint: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub ticks: Pubkey,
+}
+
+impl PoolAddresses {
+    pub fn new(token_a_mint: Pubkey, token_b_mint: Pubkey) -> Self {
+        let pool_state = find_pool_address(&token_a_mint, &token_b_mint).0;
+        Self {
+            token_a_mint,
+            token_b_mint,
+            pool_state,
+            pool_authority: find_pool_authority_address(&token_a_mint, &token_b_mint).0,
+            lp_mint: find_lp_mint_address(&token_a_mint, &token_b_mint).0,
+            vault_a: find_vault_a_address(&token_a_mint, &token_b_mint).0,
+            vault_b: find_vault_b_address(&token_a_mint, &token_b_mint).0,
+            ticks: find_pool_ticks_address(&pool_state).0,
+        }
+    }
+
+    pub fn from_state(state: &PoolState) -> Self {
+        Self::new(state.token_a, state.token_b)
+    }
+}
+
+// A wallet and its token accounts for one pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserAccounts {
+    pub owner: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp: Pubkey,
+}
+
+impl UserAccounts {
+    // The owner's associated token accounts for the pool's tokens
+    pub fn associated(owner: Pubkey, pool: &PoolAddresses) -> Self {
+        Self {
+            owner,
+            token_a: get_associated_token_address(&owner, &pool.token_a_mint),
+            token_b: get_associated_token_address(&owner, &pool.token_b_mint),
+            lp: get_associated_token_address(&owner, &pool.lp_mint),
+        }
+    }
+
+    // Like `associated`, but pays and receives the pool's wrapped SOL side in
+    // native SOL: the wallet stands in for that token account, and the
+    // program wraps and unwraps within each instruction
+    pub fn native_sol(owner: Pubkey, pool: &PoolAddresses) -> Self {
+        let mut user = Self::associated(owner, pool);
+        if pool.token_a_mint == native_mint::ID {
+            user.token_a = owner;
+        } else if pool.token_b_mint == native_mint::ID {
+            user.token_b = owner;
+        }
+        user
+    }
+
+    pub fn uses_native_sol(&self) -> bool {
+        self.token_a == self.owner || self.token_b == self.owner
+    }
+}
+
+// Every account a pool's farm owns
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FarmAddresses {
+    pub pool_state: Pubkey,
+    pub lp_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub farm: Pubkey,
+    pub stake_vault: Pubkey,
+    pub reward_vault: Pubkey,
+}
+
+impl FarmAddresses {
+    pub fn new(pool: &PoolAddresses, reward_mint: Pubkey) -> Self {
+        let farm = find_farm_address(&pool.pool_state).0;
+        Self {
+            pool_state: pool.pool_state,
+            lp_mint: pool.lp_mint,
+            reward_mint,
+            farm,
+            stake_vault: find_farm_stake_vault_address(&farm).0,
+            reward_vault: find_farm_reward_vault_address(&farm).0,
+        }
+    }
+
+    pub fn stake_position(&self, owner: &Pubkey) -> Pubkey {
+        find_stake_position_address(&self.farm, owner).0
+    }
+}
+
+// A ranged position and its NFT mint, a fresh keypair for open_position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionAddresses {
+    pub position_mint: Pubkey,
+    pub position: Pubkey,
+}
+
+impl PositionAddresses {
+    pub fn new(position_mint: Pubkey) -> Self {
+        Self { position_mint, position: find_position_address(&position_mint).0 }
+    }
+
+    // The owner's associated Token-2022 account for the position NFT
+    pub fn token_account(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.position_mint, &token_2022::ID)
+    }
+}
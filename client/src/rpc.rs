@@ -0,0 +1,254 @@
+// Minimal blocking JSON-RPC client for reading the program's accounts from a
+// node and submitting signed transactions.
+
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::{Discriminator, Space};
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_amm_educational_template::{AmmConfig, PoolState, PoolTicks, ID};
+
+use crate::error::ClientError;
+use crate::pda::{find_config_address, PoolAddresses};
+use crate::state::{decode_config, decode_pool_state, decode_pool_ticks};
+
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// A pool account's address and its state, or why it couldn't be decoded
+pub type PoolEntry = (Pubkey, Result<PoolState, ClientError>);
+
+// Most signatures getSignaturesForAddress returns per request
+pub const MAX_SIGNATURES_PER_REQUEST: usize = 1000;
+
+// One entry of getSignaturesForAddress
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureInfo {
+    pub signature: String,
+    pub slot: u64,
+    pub failed: bool,
+}
+
+pub struct RpcClient {
+    url: String,
+    commitment: String,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), commitment: "confirmed".to_string() }
+    }
+
+    pub fn with_commitment(url: impl Into<String>, commitment: impl Into<String>) -> Self {
+        Self { url: url.into(), commitment: commitment.into() }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn commitment(&self) -> &str {
+        &self.commitment
+    }
+
+    // Sends one request and returns its `result`
+    pub fn call(&self, method: &str, params: Value) -> Result<Value, ClientError> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut response: Value = ureq::post(&self.url)
+            .send_json(&request)
+            .map_err(|error| ClientError::Rpc(error.to_string()))?
+            .body_mut()
+            .read_json()
+            .map_err(|error| ClientError::Rpc(error.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            // Failed preflight simulations carry the transaction's logs
+            if let Some(logs) = error["data"]["logs"].as_array() {
+                return Err(ClientError::TransactionFailed {
+                    message: error["message"].as_str().unwrap_or_default().to_string(),
+                    logs: logs.iter().filter_map(|line| line.as_str().map(str::to_string)).collect(),
+                });
+            }
+            return Err(ClientError::Rpc(error.to_string()));
+        }
+        Ok(response["result"].take())
+    }
+
+    // Raw account data, None if the account doesn't exist
+    pub fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([address.to_string(), { "encoding": "base64", "commitment": self.commitment }]),
+        )?;
+        let value = &result["value"];
+        if value.is_null() {
+            return Ok(None);
+        }
+        decode_account_data(value).map(Some)
+    }
+
+    fn require_account_data(&self, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+        self.get_account_data(address)?.ok_or(ClientError::AccountNotFound(*address))
+    }
+
+    pub fn get_pool_state(&self, pool: &PoolAddresses) -> Result<PoolState, ClientError> {
+        decode_pool_state(&self.require_account_data(&pool.pool_state)?)
+    }
+
+    pub fn get_pool_ticks(&self, pool: &PoolAddresses) -> Result<PoolTicks, ClientError> {
+        decode_pool_ticks(&self.require_account_data(&pool.ticks)?)
+    }
+
+    pub fn get_config(&self) -> Result<AmmConfig, ClientError> {
+        decode_config(&self.require_account_data(&find_config_address().0)?)
+    }
+
+    // Every pool the program owns, each decoded on its own so one pool at an
+    // old layout doesn't hide the rest. Pools from before PoolState was
+    // versioned are smaller and not listed.
+    pub fn get_pool_states(&self) -> Result<Vec<PoolEntry>, ClientError> {
+        let discriminator = base64::engine::general_purpose::STANDARD.encode(PoolState::DISCRIMINATOR);
+        let result = self.call(
+            "getProgramAccounts",
+            json!([ID.to_string(), {
+                "encoding": "base64",
+                "commitment": self.commitment,
+                "filters": [
+                    { "dataSize": 8 + PoolState::INIT_SPACE },
+                    { "memcmp": { "offset": 0, "bytes": discriminator, "encoding": "base64" } },
+                ],
+            }]),
+        )?;
+        let accounts = result.as_array().ok_or_else(|| unexpected("getProgramAccounts", &result))?;
+
+        accounts
+            .iter()
+            .map(|keyed| {
+                let address = parse_pubkey(&keyed["pubkey"])?;
+                let data = decode_account_data(&keyed["account"])?;
+                Ok((address, decode_pool_state(&data)))
+            })
+            .collect()
+    }
+
+    // Signatures of transactions that touched `address`, newest first.
+    // `before` pages further back; `until` stops at an already seen signature.
+    pub fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<&str>,
+        until: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SignatureInfo>, ClientError> {
+        let result = self.call(
+            "getSignaturesForAddress",
+            json!([address.to_string(), {
+                "before": before,
+                "until": until,
+                "limit": limit.min(MAX_SIGNATURES_PER_REQUEST),
+                "commitment": self.commitment,
+            }]),
+        )?;
+        let entries = result.as_array().ok_or_else(|| unexpected("getSignaturesForAddress", &result))?;
+        entries
+            .iter()
+            .map(|entry| {
+                Ok(SignatureInfo {
+                    signature: entry["signature"]
+                        .as_str()
+                        .ok_or_else(|| unexpected("getSignaturesForAddress", entry))?
+                        .to_string(),
+                    slot: entry["slot"].as_u64().ok_or_else(|| unexpected("getSignaturesForAddress", entry))?,
+                    failed: !entry["err"].is_null(),
+                })
+            })
+            .collect()
+    }
+
+    // A transaction with its metadata and logs in `json` encoding, None if
+    // the node no longer has it
+    pub fn get_transaction(&self, signature: &str) -> Result<Option<Value>, ClientError> {
+        let result = self.call(
+            "getTransaction",
+            json!([signature, {
+                "encoding": "json",
+                "commitment": self.commitment,
+                "maxSupportedTransactionVersion": 0,
+            }]),
+        )?;
+        Ok(if result.is_null() { None } else { Some(result) })
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash, ClientError> {
+        let result = self.call("getLatestBlockhash", json!([{ "commitment": self.commitment }]))?;
+        result["value"]["blockhash"]
+            .as_str()
+            .and_then(|hash| Hash::from_str(hash).ok())
+            .ok_or_else(|| unexpected("getLatestBlockhash", &result))
+    }
+
+    // Submits a signed, serialized transaction and returns its signature.
+    // Preflight simulation failures come back as TransactionFailed with logs.
+    pub fn send_transaction(&self, transaction: &[u8]) -> Result<String, ClientError> {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(transaction);
+        let result = self.call(
+            "sendTransaction",
+            json!([encoded, { "encoding": "base64", "preflightCommitment": self.commitment }]),
+        )?;
+        result.as_str().map(str::to_string).ok_or_else(|| unexpected("sendTransaction", &result))
+    }
+
+    // Waits until the transaction reaches the client's commitment
+    pub fn confirm_transaction(&self, signature: &str) -> Result<(), ClientError> {
+        let start = Instant::now();
+        while start.elapsed() < CONFIRM_TIMEOUT {
+            let result = self.call("getSignatureStatuses", json!([[signature]]))?;
+            let status = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    return Err(ClientError::TransactionFailed { message: status["err"].to_string(), logs: vec![] });
+                }
+                let reached = match status["confirmationStatus"].as_str() {
+                    Some("finalized") => true,
+                    Some("confirmed") => self.commitment != "finalized",
+                    Some(_) => self.commitment == "processed",
+                    None => false,
+                };
+                if reached {
+                    return Ok(());
+                }
+            }
+            sleep(CONFIRM_POLL_INTERVAL);
+        }
+        Err(ClientError::Rpc(format!("transaction {signature} not confirmed after {CONFIRM_TIMEOUT:?}")))
+    }
+
+    pub fn send_and_confirm_transaction(&self, transaction: &[u8]) -> Result<String, ClientError> {
+        let signature = self.send_transaction(transaction)?;
+        self.confirm_transaction(&signature)?;
+        Ok(signature)
+    }
+}
+
+fn unexpected(method: &str, result: &Value) -> ClientError {
+    ClientError::Rpc(format!("unexpected {method} response: {result}"))
+}
+
+fn parse_pubkey(value: &Value) -> Result<Pubkey, ClientError> {
+    value
+        .as_str()
+        .and_then(|key| Pubkey::from_str(key).ok())
+        .ok_or_else(|| ClientError::Rpc(format!("invalid public key: {value}")))
+}
+
+// Data of an account returned with base64 encoding
+fn decode_account_data(account: &Value) -> Result<Vec<u8>, ClientError> {
+    let encoded = account["data"][0].as_str().ok_or_else(|| unexpected("account", account))?;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|error| ClientError::Rpc(error.to_string()))
+}
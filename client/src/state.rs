@@ -0,0 +1,56 @@
+// Decoding of the program's accounts from raw account data, as returned by
+// getAccountInfo.
+
+use anchor_lang::{AccountDeserialize, Discriminator, Space};
+use solana_amm_educational_template::{
+    AmmConfig, Farm, LimitOrder, PoolCreator, PoolState, PoolTicks, Position, StakePosition, MIN_POOL_STATE_VERSION,
+};
+
+use crate::error::ClientError;
+
+// Decodes any program account, checking its discriminator
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T, ClientError> {
+    T::try_deserialize(&mut &data[..]).map_err(|_| ClientError::InvalidAccountData(std::any::type_name::<T>()))
+}
+
+// Only pools at the current layout version decode; older ones have to go
+// through migrate_pool first.
+pub fn decode_pool_state(data: &[u8]) -> Result<PoolState, ClientError> {
+    // Pools from before versioning are too short to decode at all
+    if data.len() < 8 + PoolState::INIT_SPACE && data.starts_with(PoolState::DISCRIMINATOR) {
+        return Err(ClientError::UnsupportedPoolVersion(0));
+    }
+    let pool: PoolState = decode_account(data)?;
+    if pool.version < MIN_POOL_STATE_VERSION {
+        return Err(ClientError::UnsupportedPoolVersion(pool.version));
+    }
+    Ok(pool)
+}
+
+pub fn decode_config(data: &[u8]) -> Result<AmmConfig, ClientError> {
+    decode_account(data)
+}
+
+pub fn decode_pool_creator(data: &[u8]) -> Result<PoolCreator, ClientError> {
+    decode_account(data)
+}
+
+pub fn decode_farm(data: &[u8]) -> Result<Farm, ClientError> {
+    decode_account(data)
+}
+
+pub fn decode_stake_position(data: &[u8]) -> Result<StakePosition, ClientError> {
+    decode_account(data)
+}
+
+pub fn decode_pool_ticks(data: &[u8]) -> Result<PoolTicks, ClientError> {
+    decode_account(data)
+}
+
+pub fn decode_position(data: &[u8]) -> Result<Position, ClientError> {
+    decode_account(data)
+}
+
+pub fn decode_limit_order(data: &[u8]) -> Result<LimitOrder, ClientError> {
+    decode_account(data)
+}
@@ -0,0 +1,381 @@
+// Runs the client's instructions against the program, using the program's
+// in-process test runtime.
+
+#[path = "../../programs/solana-amm-educational-template/tests/common/mod.rs"]
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use base64::Engine;
+use common::*;
+use solana_amm_client::instructions::{self, FlashSwapCallback};
+use solana_amm_client::state::{
+    decode_config, decode_farm, decode_limit_order, decode_pool_state, decode_pool_ticks, decode_position,
+    decode_stake_position,
+};
+use solana_amm_client::{
+    decode_swap_error, events_from_logs, pda, AmmEvent, ClientError, ConfigParams, FarmAddresses, PoolAddresses,
+    PositionAddresses, SwapError, SwapLeg, UserAccounts, ID, MIN_POOL_STATE_VERSION, POOL_TYPE_CONCENTRATED,
+    POOL_TYPE_STABLE,
+};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+
+fn setup() -> (TestEnv, PoolAddresses, UserAccounts) {
+    let env = TestEnv::new();
+    let pool = PoolAddresses::new(env.create_mint(6), env.create_mint(6));
+    let ix = instructions::initialize_pool(env.admin, &pool, POOL_TYPE_STABLE, env.treasury, false);
+    env.svm.process_instruction(ix, &[env.admin]).unwrap();
+
+    let owner = env.wallet();
+    let user = UserAccounts {
+        owner,
+        token_a: env.create_token_account(&pool.token_a_mint, &owner, LIQUIDITY),
+        token_b: env.create_token_account(&pool.token_b_mint, &owner, LIQUIDITY),
+        lp: env.create_token_account(&pool.lp_mint, &owner, 0),
+    };
+    (env, pool, user)
+}
+
+fn failure(result: Result<(), anchor_lang::prelude::ProgramError>) -> Option<SwapError> {
+    match result {
+        Err(anchor_lang::prelude::ProgramError::Custom(code)) => decode_swap_error(code),
+        _ => None,
+    }
+}
+
+#[test]
+fn addresses_match_the_test_runtime() {
+    let mint_a = Pubkey::new_unique();
+    let mint_b = Pubkey::new_unique();
+    let expected = Pool::new(mint_a, mint_b);
+    let pool = PoolAddresses::new(mint_a, mint_b);
+
+    assert_eq!(
+        [pool.pool_state, pool.pool_authority, pool.lp_mint, pool.vault_a, pool.vault_b, pool.ticks],
+        [expected.state, expected.authority, expected.lp_mint, expected.vault_a, expected.vault_b, expected.ticks],
+    );
+    assert_eq!(pda::find_config_address().0, config_address());
+    let creator = Pubkey::new_unique();
+    assert_eq!(pda::find_pool_creator_address(&creator).0, pool_creator_address(&creator));
+    assert_eq!(pda::find_wsol_address(&creator).0, wsol_address(&creator));
+}
+
+#[test]
+fn builders_drive_a_pool() {
+    let (env, pool, user) = setup();
+
+    let ix = instructions::add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    let ix = instructions::swap(&pool, &user, 1_000_000, 0, true, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    let ix = instructions::sync(&pool);
+    env.svm.process_instruction(ix, &[]).unwrap();
+
+    let state = decode_pool_state(&env.svm.account(&pool.pool_state).unwrap().data).unwrap();
+    assert_eq!(PoolAddresses::from_state(&state), pool);
+    assert_eq!(state.reserve_a, LIQUIDITY / 2 + 1_000_000);
+    assert_eq!(state.reserve_b, env.token_balance(&pool.vault_b));
+
+    let lp = env.token_balance(&user.lp);
+    let ix = instructions::remove_liquidity(&pool, &user, lp, 0, 0, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    assert_eq!(env.token_balance(&user.token_a), LIQUIDITY);
+
+    let ix = instructions::close_pool(env.admin, &pool, env.admin, false);
+    env.svm.process_instruction(ix, &[env.admin]).unwrap();
+    assert!(env.svm.account(&pool.pool_state).is_none());
+}
+
+#[test]
+fn flash_swap_passes_callback_accounts() {
+    let (env, pool, user) = setup();
+    let ix = instructions::add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+
+    // The token program itself as the borrower: the callback is a plain
+    // transfer repaying the input
+    let repay = spl_token::instruction::transfer(&spl_token::ID, &user.token_a, &pool.vault_a, &user.owner, &[], 1_000_000)
+        .unwrap();
+    let callback = FlashSwapCallback { program: spl_token::ID, data: repay.data, accounts: repay.accounts };
+    let ix = instructions::flash_swap(&pool, &user, 1_000_000, 0, true, callback, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+
+    assert_eq!(env.token_balance(&user.token_a), LIQUIDITY / 2 - 1_000_000);
+    assert!(env.token_balance(&user.token_b) > LIQUIDITY / 2);
+}
+
+#[test]
+fn swap_with_referral_pays_the_referrer() {
+    let (env, pool, user) = setup();
+    let ix = instructions::add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    let params = ConfigParams { max_referral_fee_bps: 1_000, ..default_config_params(env.treasury) };
+    env.svm.process_instruction(instructions::update_config(env.admin, params), &[env.admin]).unwrap();
+
+    // 10% of the stable pool's 500 fee on 1_000_000
+    let referrer = env.create_token_account(&pool.token_a_mint, &env.wallet(), 0);
+    let ix = instructions::swap_with_referral(&pool, &user, 1_000_000, 0, true, referrer, 1_000, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    assert_eq!(env.token_balance(&referrer), 50);
+
+    let ix = instructions::swap_with_referral(&pool, &user, 1_000_000, 0, true, referrer, 1_001, None);
+    assert!(matches!(failure(env.svm.process_instruction(ix, &[user.owner])), Some(SwapError::ReferralFeeTooHigh)));
+}
+
+#[test]
+fn batch_swap_runs_legs_across_pools() {
+    let (env, first, first_user) = setup();
+    let ix = instructions::add_liquidity(&first, &first_user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[first_user.owner]).unwrap();
+    let second = PoolAddresses::new(first.token_b_mint, env.create_mint(6));
+    let ix = instructions::initialize_pool(env.admin, &second, POOL_TYPE_STABLE, env.treasury, false);
+    env.svm.process_instruction(ix, &[env.admin]).unwrap();
+    let second_user = UserAccounts {
+        token_a: first_user.token_b,
+        token_b: env.create_token_account(&second.token_b_mint, &first_user.owner, LIQUIDITY),
+        lp: env.create_token_account(&second.lp_mint, &first_user.owner, 0),
+        ..first_user
+    };
+    let ix = instructions::add_liquidity(&second, &second_user, LIQUIDITY / 4, LIQUIDITY / 4, None);
+    env.svm.process_instruction(ix, &[first_user.owner]).unwrap();
+
+    // A to B on the first pool, then that B on to the second pool's other token
+    let legs = vec![
+        SwapLeg { pool_index: 0, amount_in: 1_000_000, minimum_amount_out: 0, a_to_b: true },
+        SwapLeg { pool_index: 1, amount_in: 900_000, minimum_amount_out: 0, a_to_b: true },
+    ];
+    let ix = instructions::batch_swap(&[(first, first_user), (second, second_user)], legs, None);
+    env.svm.process_instruction(ix, &[first_user.owner]).unwrap();
+    let (_, data) = env.svm.return_data().unwrap();
+    let outputs = instructions::decode_batch_swap_outputs(&data).unwrap();
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(env.token_balance(&second_user.token_b), LIQUIDITY * 3 / 4 + outputs[1]);
+}
+
+#[test]
+fn native_sol_builders_wrap_and_unwrap() {
+    let env = TestEnv::new();
+    let pool = PoolAddresses::new(env.create_mint(6), env.create_native_mint());
+    let ix = instructions::initialize_pool(env.admin, &pool, POOL_TYPE_STABLE, env.treasury, false);
+    env.svm.process_instruction(ix, &[env.admin]).unwrap();
+    let owner = env.wallet();
+    let user = UserAccounts {
+        token_a: env.create_token_account(&pool.token_a_mint, &owner, LIQUIDITY),
+        lp: env.create_token_account(&pool.lp_mint, &owner, 0),
+        ..UserAccounts::native_sol(owner, &pool)
+    };
+    assert_eq!(user.token_b, owner);
+    let lamports_before = env.svm.lamports(&owner);
+
+    let ix = instructions::add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[owner]).unwrap();
+    assert_eq!(env.svm.lamports(&owner), lamports_before - LIQUIDITY / 2);
+    let ix = instructions::swap(&pool, &user, 1_000_000, 0, true, None);
+    env.svm.process_instruction(ix, &[owner]).unwrap();
+    assert!(env.svm.lamports(&owner) > lamports_before - LIQUIDITY / 2);
+
+    let lp = env.token_balance(&user.lp);
+    let ix = instructions::remove_liquidity(&pool, &user, lp, 0, 0, None);
+    env.svm.process_instruction(ix, &[owner]).unwrap();
+    assert_eq!(env.svm.lamports(&owner), lamports_before);
+    assert_eq!(env.token_balance(&user.token_a), LIQUIDITY);
+}
+
+#[test]
+fn farm_builders_stake_and_claim() {
+    let (env, pool, user) = setup();
+    let ix = instructions::add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+
+    let farm = FarmAddresses::new(&pool, env.create_mint(6));
+    let expected = FarmAccounts::new(&Pool::new(pool.token_a_mint, pool.token_b_mint), farm.reward_mint);
+    assert_eq!(
+        [farm.farm, farm.stake_vault, farm.reward_vault, farm.stake_position(&user.owner)],
+        [expected.address, expected.stake_vault, expected.reward_vault, expected.stake_position(&user.owner)],
+    );
+
+    env.svm.warp_to_timestamp(100);
+    let funding = env.create_token_account(&farm.reward_mint, &env.admin, 1_000);
+    let ix = instructions::create_farm(&farm, env.admin, funding, 100, 200, 10);
+    env.svm.process_instruction(ix, &[env.admin]).unwrap();
+    let lp = env.token_balance(&user.lp);
+    let ix = instructions::stake_lp(&farm, &user, lp);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+
+    env.svm.warp_to_timestamp(150);
+    let rewards = env.create_token_account(&farm.reward_mint, &user.owner, 0);
+    let ix = instructions::claim_rewards(&farm, user.owner, rewards);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    let ix = instructions::unstake_lp(&farm, &user, lp);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+
+    assert_eq!(env.token_balance(&rewards), 500);
+    assert_eq!(env.token_balance(&user.lp), lp);
+    let state = decode_farm(&env.svm.account(&farm.farm).unwrap().data).unwrap();
+    assert_eq!((state.pool, state.rewards_claimed), (pool.pool_state, 500));
+    let position = decode_stake_position(&env.svm.account(&farm.stake_position(&user.owner)).unwrap().data).unwrap();
+    assert_eq!((position.owner, position.amount), (user.owner, 0));
+}
+
+// A concentrated pool at price 1 and a user holding half of LIQUIDITY of
+// each token after funding it
+fn concentrated_pool(env: &TestEnv) -> (PoolAddresses, UserAccounts) {
+    let pool = PoolAddresses::new(env.create_mint(6), env.create_mint(6));
+    let ix = instructions::initialize_pool(env.admin, &pool, POOL_TYPE_CONCENTRATED, env.treasury, false);
+    env.svm.process_instruction(ix, &[env.admin]).unwrap();
+    let owner = env.wallet();
+    let user = UserAccounts {
+        owner,
+        token_a: env.create_token_account(&pool.token_a_mint, &owner, LIQUIDITY),
+        token_b: env.create_token_account(&pool.token_b_mint, &owner, LIQUIDITY),
+        lp: env.create_token_account(&pool.lp_mint, &owner, 0),
+    };
+    let ix = instructions::add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[owner]).unwrap();
+    (pool, user)
+}
+
+#[test]
+fn position_builders_open_and_withdraw() {
+    let env = TestEnv::new();
+    let (pool, user) = concentrated_pool(&env);
+    let owner = user.owner;
+
+    let position = PositionAddresses::new(env.svm.new_signer());
+    let expected = PositionAccounts { mint: position.position_mint, address: position.position };
+    assert_eq!(position.token_account(&owner), expected.token_account(&owner));
+    let ix = instructions::open_position(&pool, &position, owner, -100, 100);
+    env.svm.process_instruction(ix, &[owner, position.position_mint]).unwrap();
+    let ix = instructions::increase_liquidity(&pool, &position, &user, 10_000_000, 10_000_000, None);
+    env.svm.process_instruction(ix, &[owner]).unwrap();
+    let ix = instructions::swap(&pool, &user, 1_000_000, 0, true, None);
+    env.svm.process_instruction(ix, &[owner]).unwrap();
+
+    let state = decode_position(&env.svm.account(&position.position).unwrap().data).unwrap();
+    assert_eq!((state.pool, state.tick_lower, state.tick_upper), (pool.pool_state, -100, 100));
+    let ticks = decode_pool_ticks(&env.svm.account(&pool.ticks).unwrap().data).unwrap();
+    assert_eq!(ticks.ticks.iter().map(|tick| tick.index).collect::<Vec<_>>(), [-100, 100]);
+
+    let ix = instructions::decrease_liquidity(&pool, &position, &user, state.liquidity, 0, 0, None);
+    env.svm.process_instruction(ix, &[owner]).unwrap();
+    let pool_state = decode_pool_state(&env.svm.account(&pool.pool_state).unwrap().data).unwrap();
+    assert_eq!(pool_state.liquidity, 0);
+    let state = decode_position(&env.svm.account(&position.position).unwrap().data).unwrap();
+    assert_eq!(state.liquidity, 0);
+    assert!(state.tokens_owed_a > 0);
+
+    let before = env.token_balance(&user.token_a);
+    let ix = instructions::collect_fees(&pool, &position, &user);
+    env.svm.process_instruction(ix, &[owner]).unwrap();
+    assert_eq!(env.token_balance(&user.token_a), before + state.tokens_owed_a);
+}
+
+#[test]
+fn limit_order_builders_place_and_claim() {
+    let env = TestEnv::new();
+    let (pool, user) = concentrated_pool(&env);
+    let order = pda::find_limit_order_address(&pool.pool_state, &user.owner, 100).0;
+    assert_eq!(order, Pool::new(pool.token_a_mint, pool.token_b_mint).limit_order(&user.owner, 100));
+
+    let ix = instructions::place_limit_order(&pool, &user, 100, true, 1_000_000);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    let placed = decode_limit_order(&env.svm.account(&order).unwrap().data).unwrap();
+    assert_eq!((placed.tick, placed.sells_a, placed.owner), (100, true, user.owner));
+    let ix = instructions::claim_filled_order(&pool, &user, 100);
+    assert!(matches!(failure(env.svm.process_instruction(ix, &[user.owner])), Some(SwapError::OrderNotFilled)));
+
+    let ix = instructions::swap(&pool, &user, 20_000_000, 0, false, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    let data: Vec<String> = env
+        .svm
+        .data_logs()
+        .iter()
+        .map(|fields| format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(&fields[0])))
+        .collect();
+    let mut logs = vec![format!("Program {ID} invoke [1]")];
+    logs.extend(data);
+    logs.push(format!("Program {ID} success"));
+    let filled = events_from_logs(&logs)
+        .into_iter()
+        .find_map(|event| match event {
+            AmmEvent::LimitOrdersFilled(filled) => Some(filled),
+            _ => None,
+        })
+        .expect("limit orders filled");
+    assert_eq!((filled.tick, filled.epoch, filled.liquidity), (100, placed.epoch, placed.liquidity));
+
+    let before = env.token_balance(&user.token_b);
+    let ix = instructions::claim_filled_order(&pool, &user, 100);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    assert_eq!(env.token_balance(&user.token_b), before + filled.amount_out);
+    assert!(env.svm.account(&order).is_none());
+}
+
+#[test]
+fn decodes_program_errors() {
+    let (env, pool, user) = setup();
+    let ix = instructions::add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+
+    let ix = instructions::swap(&pool, &user, 1_000_000, u64::MAX, true, None);
+    assert!(matches!(failure(env.svm.process_instruction(ix, &[user.owner])), Some(SwapError::SlippageExceeded)));
+    let ix = instructions::swap_with_limits(&pool, &user, 1_000_000, 0, true, Some(0), None, None);
+    assert!(matches!(failure(env.svm.process_instruction(ix, &[user.owner])), Some(SwapError::PriceImpactExceeded)));
+
+    let ix = instructions::update_config(user.owner, ConfigParams { creator_fee_share: 1, ..default_config_params(env.treasury) });
+    assert!(matches!(failure(env.svm.process_instruction(ix, &[user.owner])), Some(SwapError::Unauthorized)));
+}
+
+#[test]
+fn decodes_accounts() {
+    let (env, pool, _) = setup();
+
+    let config = decode_config(&env.svm.account(&pda::find_config_address().0).unwrap().data).unwrap();
+    assert_eq!(config.admin, env.admin);
+
+    // A config is not a pool
+    let config_data = env.svm.account(&pda::find_config_address().0).unwrap().data;
+    assert!(matches!(decode_pool_state(&config_data), Err(ClientError::InvalidAccountData(_))));
+
+    // Nor is a pool at a layout too old to use, until migrated
+    let mut account = env.svm.account(&pool.pool_state).unwrap();
+    account.data[110] = MIN_POOL_STATE_VERSION;
+    assert_eq!(decode_pool_state(&account.data).unwrap().version, MIN_POOL_STATE_VERSION);
+    account.data[110] = MIN_POOL_STATE_VERSION - 1;
+    assert!(matches!(decode_pool_state(&account.data), Err(ClientError::UnsupportedPoolVersion(_))));
+    account.data.truncate(110);
+    assert!(matches!(decode_pool_state(&account.data), Err(ClientError::UnsupportedPoolVersion(0))));
+}
+
+#[test]
+fn decodes_events_and_instructions() {
+    let (env, pool, user) = setup();
+    let ix = instructions::add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY / 2, None);
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+    let ix = instructions::swap(&pool, &user, 1_000_000, 0, true, None);
+    assert_eq!(instructions::instruction_name(&ix.data), Some("swap"));
+    env.svm.process_instruction(ix, &[user.owner]).unwrap();
+
+    // Logs as a node reports them, with the same payload also logged by
+    // another program, which must not count
+    let data: Vec<String> = env
+        .svm
+        .data_logs()
+        .iter()
+        .map(|fields| format!("Program data: {}", base64::engine::general_purpose::STANDARD.encode(&fields[0])))
+        .collect();
+    let other = Pubkey::new_unique();
+    let mut logs = vec![format!("Program {ID} invoke [1]")];
+    logs.extend(data.iter().cloned());
+    logs.push(format!("Program {other} invoke [2]"));
+    logs.extend(data.iter().cloned());
+    logs.push(format!("Program {other} success"));
+    logs.push(format!("Program {ID} success"));
+
+    let events = events_from_logs(&logs);
+    assert_eq!(events.len(), 1);
+    let AmmEvent::SwapExecuted(swap) = &events[0] else { panic!("expected a swap, got {events:?}") };
+    let state = decode_pool_state(&env.svm.account(&pool.pool_state).unwrap().data).unwrap();
+    assert_eq!((swap.pool, swap.user, swap.amount_in), (pool.pool_state, user.owner, 1_000_000));
+    assert_eq!((swap.reserve_a, swap.reserve_b), (state.reserve_a, state.reserve_b));
+}
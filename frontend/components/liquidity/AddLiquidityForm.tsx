@@ -102,12 +102,10 @@ const AddLiquidityForm: React.FC<AddLiquidityFormProps> = ({ showToast }) => {
           lpToMint = avgRatio;
           break;
           
-        case 2: // Concentrated pool: bonus LP tokens for providing liquidity
+        case 2: // Concentrated pool: proportional to existing ratio, like standard
           const baseRatioA = (amountA * lpTokenSupply) / vaultABalance;
           const baseRatioB = (amountB * lpTokenSupply) / vaultBBalance;
-          const baseRatio = Math.min(baseRatioA, baseRatioB);
-          // 10% bonus for concentrated liquidity
-          lpToMint = (baseRatio * 110) / 100;
+          lpToMint = Math.min(baseRatioA, baseRatioB);
           break;
           
         default:
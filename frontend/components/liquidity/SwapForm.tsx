@@ -188,9 +188,8 @@ const SwapForm: React.FC<SwapFormProps> = ({ showToast }) => {
         const stabilityBonus = amountInAfterFee / 20; // 5% bonus
         outputAmount = Math.min(standardOut + stabilityBonus, outputReserve - 1);
         break;
-      case 2: // Concentrated Pool - standard + 10% efficiency bonus  
-        const efficiencyBonus = amountInAfterFee / 10; // 10% bonus
-        outputAmount = Math.min(standardOut + efficiencyBonus, outputReserve - 1);
+      case 2: // Concentrated Pool - full-range liquidity, same curve as standard
+        outputAmount = standardOut;
         break;
       default:
         outputAmount = standardOut;
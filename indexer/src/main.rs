@@ -0,0 +1,158 @@
+// amm-indexer: records the AMM program's history in SQLite. It reads the
+// program's transactions from an RPC node (or a file exported from a local
+// ledger) and stores the instructions they ran plus the swaps, liquidity
+// changes and reserve snapshots their events describe.
+//
+//   amm-indexer --db amm.sqlite replay --from-slot 250000000
+//   amm-indexer --db amm.sqlite follow
+
+mod store;
+mod transaction;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use solana_amm_client::rpc::{RpcClient, SignatureInfo, MAX_SIGNATURES_PER_REQUEST};
+use solana_amm_client::ID;
+
+use store::Store;
+use transaction::decode_transaction;
+
+#[derive(Parser)]
+#[command(name = "amm-indexer", version, about = "Index AMM program transactions and events into SQLite")]
+struct Cli {
+    /// SQLite database, created if missing
+    #[arg(long, env = "AMM_INDEX_DB", default_value = "amm-index.sqlite")]
+    db: PathBuf,
+
+    /// RPC endpoint to read transactions from
+    #[arg(long, short, env = "AMM_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Index new transactions as they land, starting after the newest one
+    /// already indexed (or at the chain tip for an empty database)
+    Follow {
+        /// Seconds between polls
+        #[arg(long, default_value_t = 3)]
+        interval_secs: u64,
+    },
+    /// Backfill every transaction from a slot on
+    Replay {
+        #[arg(long)]
+        from_slot: u64,
+        /// Last slot to index; defaults to the newest
+        #[arg(long)]
+        to_slot: Option<u64>,
+    },
+    /// Index getTransaction results (json encoding) from a file, one per line,
+    /// e.g. exported from a local validator's ledger
+    Import { file: PathBuf },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut store = Store::open(&cli.db).with_context(|| format!("opening {}", cli.db.display()))?;
+    let rpc = RpcClient::new(&cli.url);
+
+    match cli.command {
+        Command::Follow { interval_secs } => follow(&rpc, &mut store, Duration::from_secs(interval_secs)),
+        Command::Replay { from_slot, to_slot } => {
+            let signatures = signatures(&rpc, None, Some(from_slot), to_slot)?;
+            let indexed = index_signatures(&rpc, &mut store, &signatures)?;
+            println!("Indexed {indexed} of {} transactions from slot {from_slot}", signatures.len());
+            Ok(())
+        }
+        Command::Import { file } => {
+            let reader = BufReader::new(File::open(&file).with_context(|| format!("opening {}", file.display()))?);
+            let mut indexed = 0;
+            for (number, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let response = serde_json::from_str(&line).with_context(|| format!("line {}", number + 1))?;
+                let transaction = decode_transaction(&response).with_context(|| format!("line {}", number + 1))?;
+                indexed += store.insert(&transaction)? as usize;
+            }
+            println!("Indexed {indexed} transactions from {}", file.display());
+            Ok(())
+        }
+    }
+}
+
+// The program's signatures, oldest first: those newer than `until`, or in
+// the slot range when `from_slot` is given
+fn signatures(rpc: &RpcClient, until: Option<&str>, from_slot: Option<u64>, to_slot: Option<u64>) -> Result<Vec<SignatureInfo>> {
+    let mut found = Vec::new();
+    let mut before: Option<String> = None;
+    loop {
+        let page = rpc.get_signatures_for_address(&ID, before.as_deref(), until, MAX_SIGNATURES_PER_REQUEST)?;
+        let last_page = page.len() < MAX_SIGNATURES_PER_REQUEST;
+        before = page.last().map(|info| info.signature.clone());
+
+        // Pages run newest to oldest, so the first entry below the range ends the walk
+        for info in page {
+            if from_slot.is_some_and(|from_slot| info.slot < from_slot) {
+                found.reverse();
+                return Ok(found);
+            }
+            if to_slot.is_none_or(|to_slot| info.slot <= to_slot) {
+                found.push(info);
+            }
+        }
+        if last_page || before.is_none() {
+            found.reverse();
+            return Ok(found);
+        }
+    }
+}
+
+// Fetches and stores each transaction, returning how many were new
+fn index_signatures(rpc: &RpcClient, store: &mut Store, signatures: &[SignatureInfo]) -> Result<usize> {
+    let mut indexed = 0;
+    for info in signatures {
+        let Some(response) = rpc.get_transaction(&info.signature)? else {
+            eprintln!("warning: transaction {} is no longer available", info.signature);
+            continue;
+        };
+        let transaction = decode_transaction(&response).with_context(|| format!("decoding {}", info.signature))?;
+        indexed += store.insert(&transaction)? as usize;
+    }
+    Ok(indexed)
+}
+
+fn follow(rpc: &RpcClient, store: &mut Store, interval: Duration) -> Result<()> {
+    let mut cursor = match store.latest_signature()? {
+        Some((signature, slot)) => {
+            println!("Following from slot {slot}");
+            Some(signature)
+        }
+        None => {
+            // Nothing indexed yet: start at the tip, replay backfills history
+            let newest = rpc.get_signatures_for_address(&ID, None, None, 1)?;
+            println!("Following from the chain tip");
+            newest.into_iter().next().map(|info| info.signature)
+        }
+    };
+
+    loop {
+        let signatures = signatures(rpc, cursor.as_deref(), None, None)?;
+        if let Some(newest) = signatures.last() {
+            let indexed = index_signatures(rpc, store, &signatures)?;
+            println!("Indexed {indexed} transactions up to slot {}", newest.slot);
+            cursor = Some(newest.signature.clone());
+        }
+        sleep(interval);
+    }
+}
@@ -0,0 +1,323 @@
+// SQLite storage for indexed transactions. Every row is keyed by the
+// transaction signature, so indexing the same transaction twice (overlapping
+// replays, a restarted follower) changes nothing.
+//
+// Token amounts are stored as INTEGER, SQLite's signed 64-bit type; an amount
+// above i64::MAX fails the insert rather than being stored wrapped.
+
+use std::path::Path;
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use solana_amm_client::AmmEvent;
+
+use crate::transaction::IndexedTransaction;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature   TEXT PRIMARY KEY,
+    slot        INTEGER NOT NULL,
+    block_time  INTEGER,
+    error       TEXT
+);
+CREATE INDEX IF NOT EXISTS transactions_slot ON transactions (slot);
+
+CREATE TABLE IF NOT EXISTS instructions (
+    signature          TEXT NOT NULL REFERENCES transactions (signature),
+    ordinal            INTEGER NOT NULL,
+    instruction_index  INTEGER NOT NULL,
+    inner_index        INTEGER,
+    name               TEXT NOT NULL,
+    PRIMARY KEY (signature, ordinal)
+);
+
+CREATE TABLE IF NOT EXISTS pools (
+    address     TEXT PRIMARY KEY,
+    token_a     TEXT NOT NULL,
+    token_b     TEXT NOT NULL,
+    lp_mint     TEXT NOT NULL,
+    pool_type   INTEGER NOT NULL,
+    fee_rate    INTEGER NOT NULL,
+    creator     TEXT NOT NULL,
+    signature   TEXT NOT NULL REFERENCES transactions (signature),
+    slot        INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS swaps (
+    signature    TEXT NOT NULL REFERENCES transactions (signature),
+    event_index  INTEGER NOT NULL,
+    slot         INTEGER NOT NULL,
+    block_time   INTEGER,
+    pool         TEXT NOT NULL,
+    user         TEXT NOT NULL,
+    a_to_b       INTEGER NOT NULL,
+    amount_in    INTEGER NOT NULL,
+    amount_out   INTEGER NOT NULL,
+    fee          INTEGER NOT NULL,
+    creator_fee  INTEGER NOT NULL,
+    referrer     TEXT,
+    referral_fee INTEGER NOT NULL,
+    flash        INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS swaps_pool_slot ON swaps (pool, slot);
+
+-- kind is 'add' or 'remove'
+CREATE TABLE IF NOT EXISTS liquidity_changes (
+    signature    TEXT NOT NULL REFERENCES transactions (signature),
+    event_index  INTEGER NOT NULL,
+    slot         INTEGER NOT NULL,
+    block_time   INTEGER,
+    pool         TEXT NOT NULL,
+    user         TEXT NOT NULL,
+    kind         TEXT NOT NULL,
+    amount_a     INTEGER NOT NULL,
+    amount_b     INTEGER NOT NULL,
+    lp_amount    INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS liquidity_changes_pool_slot ON liquidity_changes (pool, slot);
+
+-- Pool reserves after each event that moved them; lp_supply is only known
+-- after liquidity changes
+CREATE TABLE IF NOT EXISTS reserve_snapshots (
+    signature    TEXT NOT NULL REFERENCES transactions (signature),
+    event_index  INTEGER NOT NULL,
+    slot         INTEGER NOT NULL,
+    block_time   INTEGER,
+    pool         TEXT NOT NULL,
+    reserve_a    INTEGER NOT NULL,
+    reserve_b    INTEGER NOT NULL,
+    lp_supply    INTEGER,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS reserve_snapshots_pool_slot ON reserve_snapshots (pool, slot);
+
+-- Batches of limit orders a swap filled; liquidity is TEXT as it can exceed 64 bits
+CREATE TABLE IF NOT EXISTS limit_order_fills (
+    signature    TEXT NOT NULL REFERENCES transactions (signature),
+    event_index  INTEGER NOT NULL,
+    slot         INTEGER NOT NULL,
+    block_time   INTEGER,
+    pool         TEXT NOT NULL,
+    tick         INTEGER NOT NULL,
+    sells_a      INTEGER NOT NULL,
+    epoch        INTEGER NOT NULL,
+    liquidity    TEXT NOT NULL,
+    amount_out   INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS limit_order_fills_pool_slot ON limit_order_fills (pool, slot);
+
+-- Swaps rejected by the circuit breaker; their transactions failed
+CREATE TABLE IF NOT EXISTS circuit_breaker_trips (
+    signature           TEXT NOT NULL REFERENCES transactions (signature),
+    event_index         INTEGER NOT NULL,
+    slot                INTEGER NOT NULL,
+    block_time          INTEGER,
+    pool                TEXT NOT NULL,
+    slot_price_x64      TEXT NOT NULL,
+    price_x64           TEXT NOT NULL,
+    circuit_breaker_bps INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS circuit_breaker_trips_pool_slot ON circuit_breaker_trips (pool, slot);
+";
+
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    fn init(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    // Most recently indexed transaction, where following picks up from
+    pub fn latest_signature(&self) -> Result<Option<(String, u64)>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT signature, slot FROM transactions ORDER BY slot DESC, rowid DESC LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    // Stores a transaction and everything decoded from it. Returns false if
+    // it was already indexed.
+    pub fn insert(&mut self, transaction: &IndexedTransaction) -> Result<bool> {
+        let db = self.connection.transaction()?;
+        let inserted = db.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time, error) VALUES (?1, ?2, ?3, ?4)",
+            params![transaction.signature, transaction.slot, transaction.block_time, transaction.error],
+        )?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        let signature = &transaction.signature;
+        let (slot, block_time) = (transaction.slot, transaction.block_time);
+        for (ordinal, instruction) in transaction.instructions.iter().enumerate() {
+            db.execute(
+                "INSERT INTO instructions (signature, ordinal, instruction_index, inner_index, name)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![signature, ordinal, instruction.instruction_index, instruction.inner_index, instruction.name],
+            )?;
+        }
+
+        for (event_index, event) in transaction.events.iter().enumerate() {
+            let snapshot = |pool: String, reserve_a: u64, reserve_b: u64, lp_supply: Option<u64>| {
+                db.execute(
+                    "INSERT INTO reserve_snapshots (signature, event_index, slot, block_time, pool, reserve_a, reserve_b, lp_supply)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![signature, event_index, slot, block_time, pool, reserve_a, reserve_b, lp_supply],
+                )
+            };
+            let liquidity = |pool: String, user: String, kind: &str, amounts: (u64, u64, u64)| {
+                db.execute(
+                    "INSERT INTO liquidity_changes (signature, event_index, slot, block_time, pool, user, kind, amount_a, amount_b, lp_amount)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![signature, event_index, slot, block_time, pool, user, kind, amounts.0, amounts.1, amounts.2],
+                )
+            };
+
+            match event {
+                AmmEvent::PoolInitialized(event) => {
+                    db.execute(
+                        "INSERT OR IGNORE INTO pools (address, token_a, token_b, lp_mint, pool_type, fee_rate, creator, signature, slot)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                        params![
+                            event.pool.to_string(),
+                            event.token_a.to_string(),
+                            event.token_b.to_string(),
+                            event.lp_mint.to_string(),
+                            event.pool_type,
+                            event.fee_rate,
+                            event.creator.to_string(),
+                            signature,
+                            slot,
+                        ],
+                    )?;
+                }
+                AmmEvent::LiquidityAdded(event) => {
+                    let amounts = (event.amount_a, event.amount_b, event.lp_amount);
+                    liquidity(event.pool.to_string(), event.user.to_string(), "add", amounts)?;
+                    snapshot(event.pool.to_string(), event.reserve_a, event.reserve_b, Some(event.lp_supply))?;
+                }
+                AmmEvent::LiquidityRemoved(event) => {
+                    let amounts = (event.amount_a, event.amount_b, event.lp_amount);
+                    liquidity(event.pool.to_string(), event.user.to_string(), "remove", amounts)?;
+                    snapshot(event.pool.to_string(), event.reserve_a, event.reserve_b, Some(event.lp_supply))?;
+                }
+                AmmEvent::SwapExecuted(event) => {
+                    db.execute(
+                        "INSERT INTO swaps (signature, event_index, slot, block_time, pool, user, a_to_b, amount_in, amount_out, fee, creator_fee, referrer, referral_fee, flash)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                        params![
+                            signature,
+                            event_index,
+                            slot,
+                            block_time,
+                            event.pool.to_string(),
+                            event.user.to_string(),
+                            event.a_to_b,
+                            event.amount_in,
+                            event.amount_out,
+                            event.fee,
+                            event.creator_fee,
+                            event.referrer.map(|referrer| referrer.to_string()),
+                            event.referral_fee,
+                            event.flash,
+                        ],
+                    )?;
+                    snapshot(event.pool.to_string(), event.reserve_a, event.reserve_b, None)?;
+                }
+                AmmEvent::ReservesSynced(event) => {
+                    snapshot(event.pool.to_string(), event.reserve_a, event.reserve_b, None)?;
+                }
+                AmmEvent::LimitOrdersFilled(event) => {
+                    db.execute(
+                        "INSERT INTO limit_order_fills (signature, event_index, slot, block_time, pool, tick, sells_a, epoch, liquidity, amount_out)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                        params![
+                            signature,
+                            event_index,
+                            slot,
+                            block_time,
+                            event.pool.to_string(),
+                            event.tick,
+                            event.sells_a,
+                            event.epoch,
+                            event.liquidity.to_string(),
+                            event.amount_out,
+                        ],
+                    )?;
+                }
+                AmmEvent::CircuitBreakerTripped(event) => {
+                    db.execute(
+                        "INSERT INTO circuit_breaker_trips (signature, event_index, slot, block_time, pool, slot_price_x64, price_x64, circuit_breaker_bps)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                        params![
+                            signature,
+                            event_index,
+                            slot,
+                            block_time,
+                            event.pool.to_string(),
+                            event.slot_price_x64.to_string(),
+                            event.price_x64.to_string(),
+                            event.circuit_breaker_bps,
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        db.commit()?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::decode_transaction;
+    use crate::transaction::tests::{swap_event, swap_response};
+    use anchor_lang::prelude::Pubkey;
+    use serde_json::Value;
+
+    #[test]
+    fn indexes_each_transaction_once() {
+        let mut store = Store::init(Connection::open_in_memory().unwrap()).unwrap();
+        assert!(store.latest_signature().unwrap().is_none());
+
+        let pool = Pubkey::new_unique();
+        let first = decode_transaction(&swap_response("sig1", 10, &swap_event(pool), Value::Null)).unwrap();
+        let second = decode_transaction(&swap_response("sig2", 11, &swap_event(pool), Value::Null)).unwrap();
+        assert!(store.insert(&first).unwrap());
+        assert!(store.insert(&second).unwrap());
+        assert!(!store.insert(&first).unwrap());
+
+        let count = |table: &str| -> i64 {
+            store.connection.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| row.get(0)).unwrap()
+        };
+        assert_eq!((count("transactions"), count("instructions"), count("swaps"), count("reserve_snapshots")), (2, 2, 2, 2));
+        assert_eq!(store.latest_signature().unwrap(), Some(("sig2".to_string(), 11)));
+
+        let (amount_out, reserve_b): (i64, i64) = store
+            .connection
+            .query_row(
+                "SELECT amount_out, reserve_b FROM swaps JOIN reserve_snapshots USING (signature, event_index) WHERE signature = 'sig1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((amount_out, reserve_b), (990, 99_010));
+    }
+}
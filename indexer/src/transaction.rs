@@ -0,0 +1,189 @@
+// Decoding of a getTransaction response (`json` encoding) into what the index
+// keeps: which program instructions ran and the events they emitted.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use solana_amm_client::instructions::instruction_name;
+use solana_amm_client::{events_from_logs, AmmEvent, ID};
+
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    // Failed transactions change nothing, so the only events they carry
+    // are the ones explaining why, like a tripped circuit breaker
+    pub error: Option<String>,
+    pub instructions: Vec<ProgramInstruction>,
+    pub events: Vec<AmmEvent>,
+}
+
+// One invocation of the program, top-level or through a CPI
+pub struct ProgramInstruction {
+    pub instruction_index: u32,   // Top-level instruction it ran in
+    pub inner_index: Option<u32>, // Position among that instruction's CPIs
+    pub name: &'static str,
+}
+
+pub fn decode_transaction(response: &Value) -> Result<IndexedTransaction> {
+    let signature = response["transaction"]["signatures"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("transaction has no signature"))?
+        .to_string();
+    let slot = response["slot"].as_u64().with_context(|| format!("transaction {signature} has no slot"))?;
+    let meta = &response["meta"];
+    let error = (!meta["err"].is_null()).then(|| meta["err"].to_string());
+
+    // Versioned transactions list the accounts loaded from lookup tables
+    // after the ones in the message
+    let message = &response["transaction"]["message"];
+    let account_keys: Vec<&str> = [
+        &message["accountKeys"],
+        &meta["loadedAddresses"]["writable"],
+        &meta["loadedAddresses"]["readonly"],
+    ]
+    .into_iter()
+    .filter_map(Value::as_array)
+    .flatten()
+    .filter_map(Value::as_str)
+    .collect();
+
+    let program = ID.to_string();
+    let name = |instruction: &Value| -> Result<Option<&'static str>> {
+        let program_index = instruction["programIdIndex"].as_u64().context("instruction without program")?;
+        if account_keys.get(program_index as usize) != Some(&program.as_str()) {
+            return Ok(None);
+        }
+        let data = bs58::decode(instruction["data"].as_str().unwrap_or_default()).into_vec()?;
+        Ok(Some(instruction_name(&data).unwrap_or("unknown")))
+    };
+
+    let mut instructions = Vec::new();
+    for (index, instruction) in message["instructions"].as_array().into_iter().flatten().enumerate() {
+        if let Some(name) = name(instruction)? {
+            instructions.push(ProgramInstruction { instruction_index: index as u32, inner_index: None, name });
+        }
+    }
+    for inner in meta["innerInstructions"].as_array().into_iter().flatten() {
+        let index = inner["index"].as_u64().context("inner instructions without index")? as u32;
+        for (inner_index, instruction) in inner["instructions"].as_array().into_iter().flatten().enumerate() {
+            if let Some(name) = name(instruction)? {
+                instructions.push(ProgramInstruction { instruction_index: index, inner_index: Some(inner_index as u32), name });
+            }
+        }
+    }
+    instructions.sort_by_key(|instruction| (instruction.instruction_index, instruction.inner_index));
+
+    let logs: Vec<&str> = meta["logMessages"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+    let mut events = events_from_logs(&logs);
+    if error.is_some() {
+        events.retain(|event| matches!(event, AmmEvent::CircuitBreakerTripped(_)));
+    }
+
+    Ok(IndexedTransaction {
+        signature,
+        slot,
+        block_time: response["blockTime"].as_i64(),
+        error,
+        instructions,
+        events,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::Event;
+    use base64::Engine;
+    use serde_json::json;
+    use solana_amm_client::{
+        instructions, CircuitBreakerTripped, PoolAddresses, SwapError, SwapExecuted, UserAccounts,
+    };
+
+    pub(crate) fn swap_event(pool: Pubkey) -> SwapExecuted {
+        SwapExecuted {
+            pool,
+            user: Pubkey::new_unique(),
+            a_to_b: true,
+            amount_in: 1_000,
+            amount_out: 990,
+            fee: 3,
+            creator_fee: 0,
+            referrer: None,
+            referral_fee: 0,
+            flash: false,
+            reserve_a: 101_000,
+            reserve_b: 99_010,
+        }
+    }
+
+    // A getTransaction response for one swap instruction that emitted `event`
+    pub(crate) fn swap_response(signature: &str, slot: u64, event: &SwapExecuted, err: Value) -> Value {
+        let pool = PoolAddresses::new(Pubkey::new_unique(), Pubkey::new_unique());
+        let user = UserAccounts::associated(event.user, &pool);
+        let data = instructions::swap(&pool, &user, event.amount_in, 0, event.a_to_b, None).data;
+        let encoded_event = base64::engine::general_purpose::STANDARD.encode(event.data());
+        json!({
+            "slot": slot,
+            "blockTime": 1_700_000_000,
+            "transaction": {
+                "signatures": [signature],
+                "message": {
+                    "accountKeys": [Pubkey::new_unique().to_string(), ID.to_string()],
+                    "instructions": [{ "programIdIndex": 1, "accounts": [0], "data": bs58::encode(data).into_string() }],
+                },
+            },
+            "meta": {
+                "err": err,
+                "innerInstructions": [],
+                "logMessages": [
+                    format!("Program {ID} invoke [1]"),
+                    "Program log: Instruction: Swap".to_string(),
+                    format!("Program data: {encoded_event}"),
+                    format!("Program {ID} success"),
+                ],
+            },
+        })
+    }
+
+    #[test]
+    fn decodes_instructions_and_events() {
+        let pool = Pubkey::new_unique();
+        let event = swap_event(pool);
+        let transaction = decode_transaction(&swap_response("sig1", 42, &event, Value::Null)).unwrap();
+
+        assert_eq!((transaction.signature.as_str(), transaction.slot), ("sig1", 42));
+        assert_eq!(transaction.block_time, Some(1_700_000_000));
+        assert!(transaction.error.is_none());
+        assert_eq!(transaction.instructions.len(), 1);
+        assert_eq!(transaction.instructions[0].name, "swap");
+        let [AmmEvent::SwapExecuted(decoded)] = &transaction.events[..] else { panic!("expected one swap event") };
+        assert_eq!((decoded.pool, decoded.amount_out), (pool, 990));
+
+        // Logs of a failed transaction don't describe anything that happened
+        let failed = decode_transaction(&swap_response("sig2", 43, &event, json!({ "InstructionError": [0, { "Custom": 6002 }] })))
+            .unwrap();
+        assert!(failed.error.is_some());
+        assert!(failed.events.is_empty());
+    }
+
+    #[test]
+    fn keeps_circuit_breaker_trips_of_failed_swaps() {
+        let pool = Pubkey::new_unique();
+        let trip = CircuitBreakerTripped {
+            pool,
+            slot: 43,
+            slot_price_x64: 1 << 64,
+            price_x64: 1 << 63,
+            circuit_breaker_bps: 100,
+        };
+        let code = anchor_lang::error::ERROR_CODE_OFFSET + SwapError::CircuitBreakerTripped as u32;
+        let mut response = swap_response("sig1", 43, &swap_event(pool), json!({ "InstructionError": [0, { "Custom": code }] }));
+        let encoded_trip = base64::engine::general_purpose::STANDARD.encode(trip.data());
+        response["meta"]["logMessages"][2] = json!(format!("Program data: {encoded_trip}"));
+
+        let transaction = decode_transaction(&response).unwrap();
+        let [AmmEvent::CircuitBreakerTripped(decoded)] = &transaction.events[..] else { panic!("expected one trip") };
+        assert_eq!((decoded.pool, decoded.circuit_breaker_bps), (pool, 100));
+    }
+}
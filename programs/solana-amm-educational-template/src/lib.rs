@@ -1,5 +1,15 @@
+// The IDL account instructions generated by `#[program]` still call the
+// deprecated `AccountInfo::realloc` on anchor-lang 0.31.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint, MintTo, Burn};
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke};
+use anchor_lang::system_program;
+use anchor_lang::InstructionData;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint, MintTo, Burn, CloseAccount};
+use anchor_spl::token_2022::{self, spl_token_2022::instruction::AuthorityType, Token2022};
+use anchor_spl::token_interface::{self, spl_token_metadata_interface::state::TokenMetadata};
 
 declare_id!("B6WsBQgwpFpQZMYLPt9groFwSjp2nKL7JBoTJASyEYb4");
 
@@ -7,21 +17,98 @@ declare_id!("B6WsBQgwpFpQZMYLPt9groFwSjp2nKL7JBoTJASyEYb4");
 pub mod solana_amm_educational_template {
     use super::*;
 
+    // Creates the program-wide config. Only the program's upgrade authority
+    // can call it, and it becomes the config admin.
+    pub fn initialize_config(ctx: Context<InitializeConfig>, params: ConfigParams) -> Result<()> {
+        let config = &mut ctx.accounts.amm_config;
+        config.admin = ctx.accounts.admin.key();
+        config.bump = ctx.bumps.amm_config;
+        config.apply(&params)?;
+
+        msg!("AMM config initialized, admin: {}", config.admin);
+        Ok(())
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams) -> Result<()> {
+        ctx.accounts.amm_config.apply(&params)?;
+
+        msg!("AMM config updated");
+        Ok(())
+    }
+
+    // Lets `creator` create pools while pool creation is permissioned
+    pub fn add_pool_creator(ctx: Context<AddPoolCreator>, creator: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.pool_creator;
+        entry.creator = creator;
+        entry.bump = ctx.bumps.pool_creator;
+
+        msg!("Pool creator allowed: {}", creator);
+        Ok(())
+    }
+
+    pub fn remove_pool_creator(ctx: Context<RemovePoolCreator>) -> Result<()> {
+        msg!("Pool creator removed: {}", ctx.accounts.pool_creator.creator);
+        Ok(())
+    }
+
     pub fn initialize_pool(ctx: Context<InitializePool>, pool_type: u8) -> Result<()> {
+        require_keys_neq!(
+            ctx.accounts.token_a_mint.key(),
+            ctx.accounts.token_b_mint.key(),
+            SwapError::IdenticalMints
+        );
+
+        // Record risky mint settings so UIs can warn about them, and refuse
+        // the ones the config rules out
+        let token_a_flags = mint_risk_flags(&ctx.accounts.token_a_mint);
+        let token_b_flags = mint_risk_flags(&ctx.accounts.token_b_mint);
+        require!(
+            (token_a_flags | token_b_flags) & ctx.accounts.amm_config.rejected_mint_flags == 0,
+            SwapError::MintRejectedByPolicy
+        );
+
+        // While creation is permissioned the payer needs an allowlist entry;
+        // its seeds already tie it to the payer
+        let config = &ctx.accounts.amm_config;
+        require!(
+            config.permissionless_pool_creation || ctx.accounts.pool_creator.is_some(),
+            SwapError::Unauthorized
+        );
+
+        if config.pool_creation_fee > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            );
+            system_program::transfer(cpi_ctx, config.pool_creation_fee)?;
+        }
+        let creator_fee_share = config.creator_fee_share;
+
+        // A re-created pair's LP mint is left from the closed pool, which
+        // needed all of its supply burned
+        require!(ctx.accounts.lp_mint.supply == 0, SwapError::PoolNotEmpty);
+
         let pool = &mut ctx.accounts.pool_state;
         pool.token_a = ctx.accounts.token_a_mint.key();
         pool.token_b = ctx.accounts.token_b_mint.key();
         pool.lp_mint = ctx.accounts.lp_mint.key();
         pool.pool_type = pool_type; // 0=standard, 1=stable, 2=concentrated
         pool.bump = ctx.bumps.pool_state;
+        pool.version = POOL_STATE_VERSION;
+        pool.token_a_decimals = ctx.accounts.token_a_mint.decimals;
+        pool.token_b_decimals = ctx.accounts.token_b_mint.decimals;
+        (pool.token_a_rate, pool.token_b_rate) =
+            token_rates(pool.token_a_decimals, pool.token_b_decimals)?;
+        pool.token_a_flags = token_a_flags;
+        pool.token_b_flags = token_b_flags;
+        pool.creator = ctx.accounts.payer.key();
+        pool.creator_fee_share = creator_fee_share;
         
         // Set fee rate based on pool type
-        pool.fee_rate = match pool_type {
-            0 => 300,  // Standard: 0.3%
-            1 => 50,   // Stable: 0.05% 
-            2 => 500,  // Concentrated: 0.5%
-            _ => return Err(SwapError::InvalidPoolType.into()),
-        };
+        pool.fee_rate = pool_fee_rate(pool_type).ok_or(SwapError::InvalidPoolType)?;
         
         let pool_type_name = match pool_type {
             0 => "Standard",
@@ -30,29 +117,175 @@ pub mod solana_amm_educational_template {
             _ => "Unknown"
         };
         
-        msg!("{} pool initialized for tokens: {} and {}, LP mint: {}, Fee: {}bp", 
-             pool_type_name, pool.token_a, pool.token_b, pool.lp_mint, pool.fee_rate);
+        msg!("{} pool initialized for tokens: {} and {}, LP mint: {} ({} decimals), Fee: {}bp, creator: {}", 
+             pool_type_name, pool.token_a, pool.token_b, pool.lp_mint,
+             ctx.accounts.lp_mint.decimals, pool.fee_rate, pool.creator);
+        emit!(PoolInitialized {
+            pool: pool.key(),
+            token_a: pool.token_a,
+            token_b: pool.token_b,
+            lp_mint: pool.lp_mint,
+            pool_type: pool.pool_type,
+            fee_rate: pool.fee_rate,
+            creator: pool.creator,
+        });
+        Ok(())
+    }
+
+    // Brings a pool account written by an older program version up to the
+    // current PoolState layout. Only pools older than MIN_POOL_STATE_VERSION
+    // need it. Anyone can call it; the payer covers any extra rent needed
+    // for the larger account.
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        let pool_info = ctx.accounts.pool_state.to_account_info();
+        {
+            let data = pool_info.try_borrow_data()?;
+            require!(
+                data.len() >= LEGACY_POOL_STATE_LEN && data[..8] == *PoolState::DISCRIMINATOR,
+                SwapError::InvalidPoolAccount
+            );
+        }
+
+        let new_len = 8 + PoolState::INIT_SPACE;
+        if pool_info.data_len() < new_len {
+            let rent_due = Rent::get()?
+                .minimum_balance(new_len)
+                .saturating_sub(pool_info.lamports());
+            if rent_due > 0 {
+                let cpi_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: pool_info.clone(),
+                    },
+                );
+                system_program::transfer(cpi_ctx, rent_due)?;
+            }
+            // New bytes are zeroed, so a legacy pool reads back as version 0
+            pool_info.resize(new_len)?;
+        }
+
+        let mut pool = PoolState::try_deserialize(&mut &pool_info.try_borrow_data()?[..])?;
+        let from_version = pool.version;
+        if from_version >= POOL_STATE_VERSION {
+            msg!("Pool already at version {}", from_version);
+            return Ok(());
+        }
+
+        // The vaults are derived from these mints, so matching them to the pool
+        // also pins the balances read into the reserves to this pool's vaults
+        require_keys_eq!(ctx.accounts.token_a_mint.key(), pool.token_a, SwapError::InvalidTokenMint);
+        require_keys_eq!(ctx.accounts.token_b_mint.key(), pool.token_b, SwapError::InvalidTokenMint);
+
+        // Each layout version fills the defaults for the fields it introduced.
+        // Version 1 only added the version byte and reserved space.
+        if from_version < 2 {
+            pool.token_a_decimals = ctx.accounts.token_a_mint.decimals;
+            pool.token_b_decimals = ctx.accounts.token_b_mint.decimals;
+        }
+        if from_version < 3 {
+            (pool.token_a_rate, pool.token_b_rate) =
+                token_rates(pool.token_a_decimals, pool.token_b_decimals)?;
+        }
+        if from_version < 4 {
+            pool.reserve_a = ctx.accounts.token_a_vault.amount;
+            pool.reserve_b = ctx.accounts.token_b_vault.amount;
+        }
+        if from_version < 5 {
+            pool.token_a_flags = mint_risk_flags(&ctx.accounts.token_a_mint);
+            pool.token_b_flags = mint_risk_flags(&ctx.accounts.token_b_mint);
+        }
+        // Version 6 added the creator record. Older pools have no known creator
+        // and no creator fee share, which the zeroed bytes already say.
+        // Version 7 added ranged position pricing, which a zero price leaves off.
+        // Version 8 added ranged position fees, which start from zero.
+        // Version 9 added limit orders, of which there are none yet.
+        // Version 10 added the circuit breaker's slot price, which the next
+        // swap records.
+        pool.version = POOL_STATE_VERSION;
+        pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data()?[..])?;
+
+        msg!("Pool migrated from version {} to {}", from_version, POOL_STATE_VERSION);
+        Ok(())
+    }
+
+    // Closes an emptied pool and returns its rent. The whole LP supply has
+    // to be burned first; no minimum liquidity is locked at the first
+    // deposit, so the last provider can always withdraw everything. The LP
+    // mint can't be closed and stays behind; initialize_pool picks it up
+    // again if the pair is re-created. A farm has to be closed with
+    // close_farm first, and ranged positions or limit orders still holding
+    // liquidity block closing. Emptied positions and claimed orders don't:
+    // they hold nothing, and their owners can still close them.
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        require!(ctx.accounts.lp_mint.supply == 0, SwapError::PoolNotEmpty);
+        require!(
+            ctx.accounts.pool_token_a_vault.amount == 0 && ctx.accounts.pool_token_b_vault.amount == 0,
+            SwapError::PoolNotEmpty
+        );
+        require!(ctx.accounts.farm.data_is_empty(), SwapError::PoolNotEmpty);
+        // A pool that ever priced from ticks has a tick account to close too
+        require!(
+            ctx.accounts.pool_state.sqrt_price_x64 == 0 || ctx.accounts.pool_ticks.is_some(),
+            SwapError::MissingPoolTicks
+        );
+        if let Some(ticks) = &ctx.accounts.pool_ticks {
+            require!(ticks.ticks.is_empty(), SwapError::PoolNotEmpty);
+        }
+
+        let seeds = &[
+            b"pool_authority",
+            ctx.accounts.pool_state.token_a.as_ref(),
+            ctx.accounts.pool_state.token_b.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&seeds[..]];
+        for vault in [&ctx.accounts.pool_token_a_vault, &ctx.accounts.pool_token_b_vault] {
+            token::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: vault.to_account_info(),
+                    destination: ctx.accounts.recipient.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            ))?;
+        }
+
+        msg!("Pool {} closed, rent sent to {}", ctx.accounts.pool_state.key(), ctx.accounts.recipient.key());
         Ok(())
     }
 
     pub fn add_liquidity(
         ctx: Context<AddLiquidity>, 
         amount_a: u64, 
-        amount_b: u64
+        amount_b: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
+        check_deadline(deadline)?;
         require!(amount_a > 0 && amount_b > 0, SwapError::InvalidAmount);
 
-        // CRITICAL FIX: Get vault balances BEFORE any transfers
-        // This ensures we calculate LP tokens based on the pool state before this deposit
-        let vault_a_balance_before = ctx.accounts.pool_token_a_vault.amount;
-        let vault_b_balance_before = ctx.accounts.pool_token_b_vault.amount;
+        // CRITICAL FIX: Get reserves BEFORE any transfers
+        // This ensures we calculate LP tokens based on the pool state before this deposit.
+        // Tracked reserves ignore tokens donated straight to the vaults.
+        let reserve_a_before = ctx.accounts.pool_state.reserve_a;
+        let reserve_b_before = ctx.accounts.pool_state.reserve_b;
         let lp_supply = ctx.accounts.lp_mint.supply;
 
+        // A native SOL side is wrapped for the deposit
+        let (user_token_a, user_token_b, wsol) = ctx.accounts.native_sol(ctx.bumps.wsol_account).user_token_accounts(
+            &ctx.accounts.pool_state,
+            &ctx.accounts.user_token_a,
+            &ctx.accounts.user_token_b,
+            amount_a,
+            amount_b,
+        )?;
+
         // Transfer token A from user to pool
         let cpi_ctx_a = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.user_token_a.to_account_info(),
+                from: user_token_a,
                 to: ctx.accounts.pool_token_a_vault.to_account_info(),
                 authority: ctx.accounts.user_authority.to_account_info(),
             },
@@ -63,50 +296,36 @@ pub mod solana_amm_educational_template {
         let cpi_ctx_b = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.user_token_b.to_account_info(),
+                from: user_token_b,
                 to: ctx.accounts.pool_token_b_vault.to_account_info(),
                 authority: ctx.accounts.user_authority.to_account_info(),
             },
         );
         token::transfer(cpi_ctx_b, amount_b)?;
+        // Only its rent is left to give back
+        ctx.accounts.native_sol(ctx.bumps.wsol_account).unwrap_sol(wsol)?;
 
         // Calculate LP tokens based on pool type
         let lp_to_mint = if lp_supply == 0 {
             // First liquidity provider gets a fixed amount of LP tokens
             1_000_000_u64
         } else {
-            match ctx.accounts.pool_state.pool_type {
-                0 => {
-                    // Standard pool: proportional to existing ratio
-                    let ratio_a = (amount_a as u128 * lp_supply as u128) / vault_a_balance_before as u128;
-                    let ratio_b = (amount_b as u128 * lp_supply as u128) / vault_b_balance_before as u128;
-                    std::cmp::min(ratio_a, ratio_b) as u64
-                },
-                1 => {
-                    // Stable pool: more generous LP tokens since assets are similar
-                    let avg_ratio = ((amount_a as u128 + amount_b as u128) * lp_supply as u128) 
-                        / ((vault_a_balance_before + vault_b_balance_before) as u128 * 2);
-                    avg_ratio as u64
-                },
-                2 => {
-                    // Concentrated pool: bonus LP tokens for providing liquidity
-                    let ratio_a = (amount_a as u128 * lp_supply as u128) / vault_a_balance_before as u128;
-                    let ratio_b = (amount_b as u128 * lp_supply as u128) / vault_b_balance_before as u128;
-                    let base_ratio = std::cmp::min(ratio_a, ratio_b);
-                    // 10% bonus for concentrated liquidity
-                    (base_ratio * 110 / 100) as u64
-                },
-                _ => {
-                    // Fallback to standard calculation
-                    let ratio_a = (amount_a as u128 * lp_supply as u128) / vault_a_balance_before as u128;
-                    let ratio_b = (amount_b as u128 * lp_supply as u128) / vault_b_balance_before as u128;
-                    std::cmp::min(ratio_a, ratio_b) as u64
-                }
-            }
+            calculate_deposit_lp(
+                &ctx.accounts.pool_state,
+                reserve_a_before,
+                reserve_b_before,
+                amount_a,
+                amount_b,
+                lp_supply,
+            ).ok_or(SwapError::MathOverflow)?
         };
 
         require!(lp_to_mint > 0, SwapError::InvalidAmount);
 
+        let pool = &mut ctx.accounts.pool_state;
+        pool.reserve_a = reserve_a_before.checked_add(amount_a).ok_or(SwapError::MathOverflow)?;
+        pool.reserve_b = reserve_b_before.checked_add(amount_b).ok_or(SwapError::MathOverflow)?;
+
         // Mint LP tokens to the user using pool authority as signer
         let seeds = &[
             b"pool_authority",
@@ -129,6 +348,16 @@ pub mod solana_amm_educational_template {
 
         msg!("Liquidity added: {} token A, {} token B, {} LP tokens minted", 
              amount_a, amount_b, lp_to_mint);
+        emit!(LiquidityAdded {
+            pool: ctx.accounts.pool_state.key(),
+            user: ctx.accounts.user_authority.key(),
+            amount_a,
+            amount_b,
+            lp_amount: lp_to_mint,
+            reserve_a: ctx.accounts.pool_state.reserve_a,
+            reserve_b: ctx.accounts.pool_state.reserve_b,
+            lp_supply: lp_supply + lp_to_mint,
+        });
         Ok(())
     }
 
@@ -137,29 +366,34 @@ pub mod solana_amm_educational_template {
         lp_amount: u64,
         minimum_a_out: u64,
         minimum_b_out: u64,
+        deadline: Option<i64>,
     ) -> Result<()> {
+        check_deadline(deadline)?;
         require!(lp_amount > 0, SwapError::InvalidAmount);
         require!(ctx.accounts.user_lp_token.amount >= lp_amount, SwapError::InsufficientLpBalance);
 
         // Calculate how much of each token the user should receive
-        // Formula: (lp_amount / total_lp_supply) * vault_balance
+        // Formula: (lp_amount / total_lp_supply) * reserve
         let lp_supply = ctx.accounts.lp_mint.supply;
-        let vault_a_balance = ctx.accounts.pool_token_a_vault.amount;
-        let vault_b_balance = ctx.accounts.pool_token_b_vault.amount;
+        let reserve_a = ctx.accounts.pool_state.reserve_a;
+        let reserve_b = ctx.accounts.pool_state.reserve_b;
 
-        let amount_a_out = (vault_a_balance as u128 * lp_amount as u128) / lp_supply as u128;
-        let amount_b_out = (vault_b_balance as u128 * lp_amount as u128) / lp_supply as u128;
-
-        let amount_a_out = amount_a_out as u64;
-        let amount_b_out = amount_b_out as u64;
+        let amount_a_out = calculate_withdrawal(reserve_a, lp_amount, lp_supply)
+            .ok_or(SwapError::MathOverflow)?;
+        let amount_b_out = calculate_withdrawal(reserve_b, lp_amount, lp_supply)
+            .ok_or(SwapError::MathOverflow)?;
 
         // Slippage protection - ensure user gets at least minimum amounts
         require!(amount_a_out >= minimum_a_out, SwapError::SlippageExceeded);
         require!(amount_b_out >= minimum_b_out, SwapError::SlippageExceeded);
 
         // Ensure pool has enough liquidity
-        require!(vault_a_balance >= amount_a_out, SwapError::InsufficientLiquidity);
-        require!(vault_b_balance >= amount_b_out, SwapError::InsufficientLiquidity);
+        require!(reserve_a >= amount_a_out, SwapError::InsufficientLiquidity);
+        require!(reserve_b >= amount_b_out, SwapError::InsufficientLiquidity);
+
+        let pool = &mut ctx.accounts.pool_state;
+        pool.reserve_a = reserve_a - amount_a_out;
+        pool.reserve_b = reserve_b - amount_b_out;
 
         // Burn the user's LP tokens first
         let cpi_ctx_burn = CpiContext::new(
@@ -172,6 +406,15 @@ pub mod solana_amm_educational_template {
         );
         token::burn(cpi_ctx_burn, lp_amount)?;
 
+        // A native SOL side is paid into a temporary wSOL account and unwrapped
+        let (user_token_a, user_token_b, wsol) = ctx.accounts.native_sol(ctx.bumps.wsol_account).user_token_accounts(
+            &ctx.accounts.pool_state,
+            &ctx.accounts.user_token_a,
+            &ctx.accounts.user_token_b,
+            0,
+            0,
+        )?;
+
         // Transfer token A from pool to user using pool authority
         let seeds = &[
             b"pool_authority",
@@ -185,7 +428,7 @@ pub mod solana_amm_educational_template {
             ctx.accounts.token_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.pool_token_a_vault.to_account_info(),
-                to: ctx.accounts.user_token_a.to_account_info(),
+                to: user_token_a,
                 authority: ctx.accounts.pool_authority.to_account_info(),
             },
             signer,
@@ -197,80 +440,137 @@ pub mod solana_amm_educational_template {
             ctx.accounts.token_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.pool_token_b_vault.to_account_info(),
-                to: ctx.accounts.user_token_b.to_account_info(),
+                to: user_token_b,
                 authority: ctx.accounts.pool_authority.to_account_info(),
             },
             signer,
         );
         token::transfer(cpi_ctx_b, amount_b_out)?;
+        ctx.accounts.native_sol(ctx.bumps.wsol_account).unwrap_sol(wsol)?;
 
         msg!("Liquidity removed: {} LP tokens burned, {} token A, {} token B withdrawn", 
              lp_amount, amount_a_out, amount_b_out);
+        emit!(LiquidityRemoved {
+            pool: ctx.accounts.pool_state.key(),
+            user: ctx.accounts.user_authority.key(),
+            amount_a: amount_a_out,
+            amount_b: amount_b_out,
+            lp_amount,
+            reserve_a: ctx.accounts.pool_state.reserve_a,
+            reserve_b: ctx.accounts.pool_state.reserve_b,
+            lp_supply: lp_supply - lp_amount,
+        });
         Ok(())
     }
 
-    pub fn swap(
-        ctx: Context<Swap>, 
-        amount_in: u64, 
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+        amount_in: u64,
         minimum_amount_out: u64,
-        a_to_b: bool
+        a_to_b: bool,
+        callback_data: Option<Vec<u8>>,
+        deadline: Option<i64>,
+        referral_fee_bps: u16,
+        max_price_impact_bps: Option<u16>,
+        sqrt_price_limit_x64: Option<u128>,
     ) -> Result<()> {
+        check_deadline(deadline)?;
         require!(amount_in > 0, SwapError::InvalidAmount);
 
-        let (input_vault, output_vault, user_input, user_output) = if a_to_b {
-            (
-                &ctx.accounts.pool_token_a_vault,
-                &ctx.accounts.pool_token_b_vault,
-                &ctx.accounts.user_token_a,
-                &ctx.accounts.user_token_b,
-            )
-        } else {
-            (
-                &ctx.accounts.pool_token_b_vault,
-                &ctx.accounts.pool_token_a_vault,
-                &ctx.accounts.user_token_b,
-                &ctx.accounts.user_token_a,
-            )
+        // Referrers are paid in the input token, up to the config's cap
+        require!(
+            referral_fee_bps <= ctx.accounts.amm_config.max_referral_fee_bps,
+            SwapError::ReferralFeeTooHigh
+        );
+        let referrer = match &ctx.accounts.referrer_token_account {
+            Some(account) => {
+                let input_mint = if a_to_b { ctx.accounts.pool_state.token_a } else { ctx.accounts.pool_state.token_b };
+                require_keys_eq!(account.mint, input_mint, SwapError::InvalidTokenMint);
+                Some(account.key())
+            }
+            None => {
+                require!(referral_fee_bps == 0, SwapError::MissingReferrer);
+                None
+            }
         };
 
-        let input_balance = input_vault.amount;
-        let output_balance = output_vault.amount;
-
-        // Ensure sufficient liquidity
-        require!(input_balance > 0 && output_balance > 0, SwapError::InsufficientLiquidity);
+        // Native SOL is wrapped for the swap, whole input included since a
+        // price limit may not use all of it, and unwrapped once it's done
+        let wrap = if callback_data.is_some() { 0 } else { amount_in };
+        let (user_token_a, user_token_b, wsol) = ctx.accounts.native_sol(ctx.bumps.wsol_account).user_token_accounts(
+            &ctx.accounts.pool_state,
+            &ctx.accounts.user_token_a,
+            &ctx.accounts.user_token_b,
+            if a_to_b { wrap } else { 0 },
+            if a_to_b { 0 } else { wrap },
+        )?;
 
-        // Calculate fee
-        let fee_amount = (amount_in as u128 * ctx.accounts.pool_state.fee_rate as u128) / 100000;
-        let amount_in_after_fee = amount_in - fee_amount as u64;
+        let (input_vault, output_vault, user_input, user_output) = if a_to_b {
+            (&ctx.accounts.pool_token_a_vault, &ctx.accounts.pool_token_b_vault, user_token_a, user_token_b)
+        } else {
+            (&ctx.accounts.pool_token_b_vault, &ctx.accounts.pool_token_a_vault, user_token_b, user_token_a)
+        };
 
-        // Calculate output amount based on pool type
-        let amount_out = match ctx.accounts.pool_state.pool_type {
-            0 => calculate_standard_swap(input_balance, output_balance, amount_in_after_fee),
-            1 => calculate_stable_swap(input_balance, output_balance, amount_in_after_fee),
-            2 => calculate_concentrated_swap(input_balance, output_balance, amount_in_after_fee),
-            _ => calculate_standard_swap(input_balance, output_balance, amount_in_after_fee), // fallback
+        // Price off the tracked reserves, not the vault balances, so tokens
+        // donated straight to a vault can't move the price
+        let (input_balance, output_balance) = ctx.accounts.pool_state.reserves(a_to_b);
+        let pool_type = ctx.accounts.pool_state.pool_type;
+        let price_before = match max_price_impact_bps {
+            Some(_) => Some(ctx.accounts.pool_state.spot_price_x64().ok_or(SwapError::InsufficientLiquidity)?),
+            None => None,
+        };
+        let circuit_breaker_bps = ctx.accounts.amm_config.circuit_breaker_bps;
+        if circuit_breaker_bps > 0 {
+            ctx.accounts.pool_state.note_slot_price(Clock::get()?.slot);
+        }
+        // Concentrated pools with ranged positions walk their ticks instead,
+        // stopping at the price limit with the rest of the input unspent
+        let concentrated = if ctx.accounts.pool_state.uses_ticks() {
+            let ticks = load_pool_ticks(&ctx.accounts.pool_ticks)?;
+            let swap = ctx.accounts.pool_state.swap_through_ticks(
+                &ticks,
+                amount_in,
+                a_to_b,
+                referral_fee_bps,
+                sqrt_price_limit_x64,
+            )?;
+            Some((ticks, swap))
+        } else {
+            None
+        };
+        let walked_ticks = concentrated.is_some();
+        let amount_in = match (&concentrated, sqrt_price_limit_x64) {
+            (Some((_, swap)), _) => swap.amount_in,
+            // Constant product curves only take the input that gets the price
+            // to the limit; stable pools just check the limit afterwards
+            (None, Some(limit)) if pool_type != 1 => {
+                require!(input_balance > 0 && output_balance > 0, SwapError::InsufficientLiquidity);
+                let fee_rate = ctx.accounts.pool_state.fee_rate;
+                let max_in = input_to_price_limit(input_balance, output_balance, fee_rate, limit, a_to_b);
+                require!(max_in > 0, SwapError::InvalidPriceLimit);
+                amount_in.min(max_in)
+            }
+            (None, _) => amount_in,
         };
+        let SwapQuote { amount_out, fee: mut fee_amount, mut creator_fee } = match &concentrated {
+            Some((_, swap)) => SwapQuote { amount_out: swap.amount_out, fee: swap.fee, creator_fee: swap.creator_fee },
+            None => ctx.accounts.pool_state.quote_swap(amount_in, a_to_b)?,
+        };
+        let mut referral_fee = calculate_referral_fee(fee_amount, referral_fee_bps);
 
         // Slippage protection
         require!(amount_out >= minimum_amount_out, SwapError::SlippageExceeded);
-        require!(amount_out <= output_balance, SwapError::InsufficientLiquidity);
 
-        // Transfer input token from user to pool
-        let cpi_ctx_in = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: user_input.to_account_info(),
-                to: input_vault.to_account_info(),
-                authority: ctx.accounts.user_authority.to_account_info(),
-            },
-        );
-        token::transfer(cpi_ctx_in, amount_in)?;
+        let input_vault = input_vault.to_account_info();
+        let output_vault = output_vault.to_account_info();
 
-        // Transfer output token from pool to user using PDA authority
+        // Copied out so the pool can still be updated while the authority signs
+        let (token_a, token_b) = (ctx.accounts.pool_state.token_a, ctx.accounts.pool_state.token_b);
         let seeds = &[
             b"pool_authority",
-            ctx.accounts.pool_state.token_a.as_ref(),
-            ctx.accounts.pool_state.token_b.as_ref(),
+            token_a.as_ref(),
+            token_b.as_ref(),
             &[ctx.bumps.pool_authority],
         ];
         let signer = &[&seeds[..]];
@@ -278,15 +578,174 @@ pub mod solana_amm_educational_template {
         let cpi_ctx_out = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: output_vault.to_account_info(),
-                to: user_output.to_account_info(),
+                from: output_vault,
+                to: user_output,
                 authority: ctx.accounts.pool_authority.to_account_info(),
             },
             signer,
         );
-        token::transfer(cpi_ctx_out, amount_out)?;
 
-        let pool_type_name = match ctx.accounts.pool_state.pool_type {
+        let flash = callback_data.is_some();
+        let mut amount_paid = amount_in;
+        if let Some(data) = callback_data {
+            // Flash swap: send the output first, let the caller's program use it,
+            // then check the vaults were paid back at least as much as a normal swap costs
+            let (input_vault_before, output_vault_before) = ctx.accounts.vault_balances(a_to_b);
+            token::transfer(cpi_ctx_out, amount_out)?;
+
+            invoke_flash_callback(ctx.remaining_accounts, data)?;
+
+            ctx.accounts.pool_token_a_vault.reload()?;
+            ctx.accounts.pool_token_b_vault.reload()?;
+            let (input_vault_after, output_vault_after) = ctx.accounts.vault_balances(a_to_b);
+
+            // Only what moved during this swap counts; earlier donations stay skimmable
+            let input_balance_after = input_vault_after
+                .checked_sub(input_vault_before)
+                .and_then(|paid| input_balance.checked_add(paid))
+                .ok_or(SwapError::FlashSwapNotRepaid)?;
+            let output_balance_after = output_vault_before
+                .checked_sub(output_vault_after)
+                .and_then(|taken| output_balance.checked_sub(taken))
+                .ok_or(SwapError::FlashSwapNotRepaid)?;
+
+            if let Some((_, swap)) = &concentrated {
+                // Ranged positions priced the swap as quoted, so it has to be
+                // settled as quoted: amount_in back for at most amount_out.
+                // Anything paid beyond that is left for sync or skim.
+                require!(
+                    input_balance_after - input_balance >= amount_in
+                        && output_balance - output_balance_after <= amount_out,
+                    SwapError::FlashSwapNotRepaid
+                );
+                ctx.accounts.pool_state.apply_concentrated_swap(a_to_b, swap)?;
+            } else {
+                require!(
+                    flash_swap_invariant_holds(
+                        &ctx.accounts.pool_state,
+                        a_to_b,
+                        input_balance,
+                        output_balance,
+                        input_balance_after,
+                        output_balance_after,
+                    ),
+                    SwapError::FlashSwapNotRepaid
+                );
+
+                // The creator's and referrer's cuts come out of the fee on what was actually repaid
+                amount_paid = input_balance_after - input_balance;
+                fee_amount = calculate_fee(amount_paid, ctx.accounts.pool_state.fee_rate);
+                creator_fee = ctx.accounts.pool_state.creator_fee(fee_amount);
+                referral_fee = calculate_referral_fee(fee_amount, referral_fee_bps);
+                let pool = &mut ctx.accounts.pool_state;
+                pool.set_reserves(a_to_b, input_balance_after - creator_fee - referral_fee, output_balance_after);
+                pool.accrue_creator_fee(a_to_b, creator_fee)?;
+            }
+
+            msg!("Flash swap repaid: {} in, {} out",
+                 input_balance_after - input_balance,
+                 output_balance - output_balance_after);
+        } else {
+            // Transfer input token from user to pool
+            let cpi_ctx_in = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: user_input,
+                    to: input_vault,
+                    authority: ctx.accounts.user_authority.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx_in, amount_in)?;
+
+            // Transfer output token from pool to user using PDA authority
+            token::transfer(cpi_ctx_out, amount_out)?;
+
+            if let Some((_, swap)) = &concentrated {
+                ctx.accounts.pool_state.apply_concentrated_swap(a_to_b, swap)?;
+            } else {
+                // The creator's and referrer's cuts of the fee don't join the reserves
+                let input_balance_after = input_balance
+                    .checked_add(amount_in - creator_fee - referral_fee)
+                    .ok_or(SwapError::MathOverflow)?;
+                let pool = &mut ctx.accounts.pool_state;
+                pool.set_reserves(a_to_b, input_balance_after, output_balance - amount_out);
+                pool.accrue_creator_fee(a_to_b, creator_fee)?;
+            }
+        }
+
+        if referral_fee > 0 {
+            let (input_vault, referrer_account) = match &ctx.accounts.referrer_token_account {
+                Some(account) if a_to_b => (&ctx.accounts.pool_token_a_vault, account),
+                Some(account) => (&ctx.accounts.pool_token_b_vault, account),
+                None => return err!(SwapError::MissingReferrer),
+            };
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: input_vault.to_account_info(),
+                        to: referrer_account.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    signer,
+                ),
+                referral_fee,
+            )?;
+            msg!("Referral fee paid: {}", referral_fee);
+        }
+        ctx.accounts.native_sol(ctx.bumps.wsol_account).unwrap_sol(wsol)?;
+
+        // Crossed ticks swap what's inside and outside them for fee growth
+        if let Some((mut ticks, swap)) = concentrated {
+            let pool = &ctx.accounts.pool_state;
+            for (index, fee_growth_in) in swap.crossed_ticks {
+                let (growth_a, growth_b) = if a_to_b {
+                    (fee_growth_in, pool.fee_growth_global_b_x64)
+                } else {
+                    (pool.fee_growth_global_a_x64, fee_growth_in)
+                };
+                ticks.cross(index, growth_a, growth_b);
+
+                // Limit orders the price just passed all the way through have filled
+                let order_tick = if a_to_b { index } else { index - TICK_SPACING };
+                if let Some((liquidity, epoch, sells_a)) = ticks.fill_orders(order_tick, a_to_b, pool)? {
+                    let amount_out = filled_order_amount(order_tick, sells_a, liquidity)?;
+                    msg!("Limit orders filled at tick {}: {} out", order_tick, amount_out);
+                    emit!(LimitOrdersFilled { pool: pool.key(), tick: order_tick, sells_a, epoch, liquidity, amount_out });
+                }
+            }
+            store_pool_ticks(&ctx.accounts.pool_ticks, &ticks)?;
+        }
+
+        if let (Some(price_before), Some(max_bps)) = (price_before, max_price_impact_bps) {
+            let price_after = ctx.accounts.pool_state.spot_price_x64().ok_or(SwapError::InsufficientLiquidity)?;
+            check_price_impact(price_before, price_after, max_bps)?;
+        }
+        // Stable curves can't stop part way, so there a price limit just
+        // checks where the swap left the price
+        if let (Some(limit), false) = (sqrt_price_limit_x64, walked_ticks) {
+            let price_after = ctx.accounts.pool_state.spot_price_x64().ok_or(SwapError::InsufficientLiquidity)?;
+            check_price_limit(price_after, limit, a_to_b)?;
+        }
+        // However the swaps in a slot are split up, together they can't move
+        // the price further than the circuit breaker allows
+        if circuit_breaker_bps > 0 {
+            let pool = &ctx.accounts.pool_state;
+            let price_after = pool.spot_price_x64().ok_or(SwapError::InsufficientLiquidity)?;
+            if price_moved_beyond(U256::from(pool.breaker_price_x64), price_after, circuit_breaker_bps) {
+                msg!("Circuit breaker tripped in slot {}", pool.breaker_slot);
+                emit!(CircuitBreakerTripped {
+                    pool: pool.key(),
+                    slot: pool.breaker_slot,
+                    slot_price_x64: pool.breaker_price_x64,
+                    price_x64: u128::try_from(price_after).unwrap_or(u128::MAX),
+                    circuit_breaker_bps,
+                });
+                return err!(SwapError::CircuitBreakerTripped);
+            }
+        }
+
+        let pool_type_name = match pool_type {
             0 => "Standard",
             1 => "Stable",
             2 => "Concentrated",
@@ -295,222 +754,3532 @@ pub mod solana_amm_educational_template {
 
         msg!("{} swap completed: {} in, {} out, fee: {}", 
              pool_type_name, amount_in, amount_out, fee_amount);
+        emit!(SwapExecuted {
+            pool: ctx.accounts.pool_state.key(),
+            user: ctx.accounts.user_authority.key(),
+            a_to_b,
+            amount_in: amount_paid,
+            amount_out,
+            fee: fee_amount,
+            creator_fee,
+            referrer,
+            referral_fee,
+            flash,
+            reserve_a: ctx.accounts.pool_state.reserve_a,
+            reserve_b: ctx.accounts.pool_state.reserve_b,
+        });
         Ok(())
     }
-}
 
-// ========== SWAP CALCULATION FUNCTIONS ==========
+    // Runs several swaps, possibly across pools, all or nothing. Each leg is
+    // an ordinary swap the program makes to itself, on the pool at
+    // `pool_index` among the groups of BATCH_SWAP_POOL_ACCOUNTS passed in
+    // remaining_accounts. Returns what each leg paid out.
+    pub fn batch_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchSwap<'info>>,
+        legs: Vec<SwapLeg>,
+        deadline: Option<i64>,
+    ) -> Result<Vec<u64>> {
+        check_deadline(deadline)?;
+        let pools: Vec<&[AccountInfo<'info>]> = ctx.remaining_accounts.chunks(BATCH_SWAP_POOL_ACCOUNTS).collect();
+        let whole_pools = pools.len() * BATCH_SWAP_POOL_ACCOUNTS == ctx.remaining_accounts.len();
+        require!(!legs.is_empty() && whole_pools, SwapError::InvalidBatchSwap);
 
-fn calculate_standard_swap(input_balance: u64, output_balance: u64, amount_in: u64) -> u64 {
-    // Standard constant product formula: x * y = k
-    let amount_out = (output_balance as u128 * amount_in as u128)
-        / (input_balance as u128 + amount_in as u128);
-    amount_out as u64
-}
+        let mut amounts_out = Vec::with_capacity(legs.len());
+        for (index, leg) in legs.iter().enumerate() {
+            let pool = *pools.get(leg.pool_index as usize).ok_or(SwapError::InvalidBatchSwap)?;
+            let [pool_state, user_token_a, user_token_b, pool_token_a_vault, pool_token_b_vault, pool_authority, pool_ticks] =
+                pool
+            else {
+                return err!(SwapError::InvalidBatchSwap);
+            };
+            let user_output = if leg.a_to_b { user_token_b } else { user_token_a };
+            let balance_before = token::accessor::amount(user_output)?;
 
-fn calculate_stable_swap(input_balance: u64, output_balance: u64, amount_in: u64) -> u64 {
-    // Stable swap: much lower slippage for similar assets
-    let standard_out = calculate_standard_swap(input_balance, output_balance, amount_in);
-    
-    // Apply stability factor - less slippage for stable pairs
-    let stability_bonus = amount_in / 20; // 5% bonus to reduce slippage
-    std::cmp::min(standard_out + stability_bonus, output_balance - 1)
-}
+            let ix = Instruction {
+                program_id: crate::ID,
+                accounts: accounts::Swap {
+                    pool_state: pool_state.key(),
+                    user_token_a: Some(user_token_a.key()),
+                    user_token_b: Some(user_token_b.key()),
+                    pool_token_a_vault: pool_token_a_vault.key(),
+                    pool_token_b_vault: pool_token_b_vault.key(),
+                    pool_authority: pool_authority.key(),
+                    user_authority: ctx.accounts.user_authority.key(),
+                    token_program: ctx.accounts.token_program.key(),
+                    pool_ticks: pool_ticks.key(),
+                    amm_config: ctx.accounts.amm_config.key(),
+                    referrer_token_account: None,
+                    wsol_account: None,
+                    native_mint: None,
+                    system_program: None,
+                }
+                .to_account_metas(None),
+                data: instruction::Swap {
+                    amount_in: leg.amount_in,
+                    minimum_amount_out: leg.minimum_amount_out,
+                    a_to_b: leg.a_to_b,
+                    callback_data: None,
+                    deadline: None,
+                    referral_fee_bps: 0,
+                    max_price_impact_bps: None,
+                    sqrt_price_limit_x64: None,
+                }
+                .data(),
+            };
+            let mut account_infos = pool.to_vec();
+            account_infos.extend([
+                ctx.accounts.user_authority.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.amm_config.to_account_info(),
+                ctx.accounts.amm_program.to_account_info(),
+            ]);
+            invoke(&ix, &account_infos).inspect_err(|_| msg!("Batch swap leg {} failed", index))?;
 
-fn calculate_concentrated_swap(input_balance: u64, output_balance: u64, amount_in: u64) -> u64 {
-    // Concentrated liquidity: better price within range
-    let standard_out = calculate_standard_swap(input_balance, output_balance, amount_in);
-    let efficiency_bonus = amount_in / 10; // 10% better price
-    std::cmp::min(standard_out + efficiency_bonus, output_balance - 1)
-}
+            let amount_out = token::accessor::amount(user_output)?
+                .checked_sub(balance_before)
+                .ok_or(SwapError::MathOverflow)?;
+            amounts_out.push(amount_out);
+        }
 
-// ========== ACCOUNTS & STATE ==========
+        msg!("Batch swap completed: {} legs, {:?} out", legs.len(), amounts_out);
+        Ok(amounts_out)
+    }
 
-#[account]
-pub struct PoolState {
-    pub token_a: Pubkey,
-    pub token_b: Pubkey,
-    pub lp_mint: Pubkey,    // Added LP mint address
-    pub fee_rate: u32,      // Fee rate in basis points (300 = 0.3%)
-    pub pool_type: u8,      // 0=standard, 1=stable, 2=concentrated
-    pub bump: u8,
-}
+    // Accepts the current vault balances as the pool's reserves, folding any
+    // donated tokens into the pool for its LPs
+    pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool_state;
+        pool.reserve_a = ctx.accounts.pool_token_a_vault.amount
+            .saturating_sub(pool.creator_fees_a)
+            .saturating_sub(pool.position_reserve_a)
+            .saturating_sub(pool.position_fees_a);
+        pool.reserve_b = ctx.accounts.pool_token_b_vault.amount
+            .saturating_sub(pool.creator_fees_b)
+            .saturating_sub(pool.position_reserve_b)
+            .saturating_sub(pool.position_fees_b);
 
-#[derive(Accounts)]
-pub struct InitializePool<'info> {
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + 32 + 32 + 32 + 4 + 1 + 1, // discriminator + token_a + token_b + lp_mint + fee_rate + pool_type + bump
-        seeds = [b"pool", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
-        bump
-    )]
-    pub pool_state: Account<'info, PoolState>,
+        msg!("Reserves synced: {} token A, {} token B", pool.reserve_a, pool.reserve_b);
+        emit!(ReservesSynced { pool: pool.key(), reserve_a: pool.reserve_a, reserve_b: pool.reserve_b });
+        Ok(())
+    }
 
-    /// CHECK: Token A mint account
-    pub token_a_mint: AccountInfo<'info>,
-    /// CHECK: Token B mint account  
-    pub token_b_mint: AccountInfo<'info>,
+    // Sends whatever the vaults hold beyond the tracked reserves (and unclaimed
+    // creator fees and ranged positions' tokens and fees) to the recipient
+    pub fn skim(ctx: Context<Skim>) -> Result<()> {
+        let pool = &ctx.accounts.pool_state;
+        let surplus_a = ctx.accounts.pool_token_a_vault.amount
+            .saturating_sub(pool.reserve_a)
+            .saturating_sub(pool.creator_fees_a)
+            .saturating_sub(pool.position_reserve_a)
+            .saturating_sub(pool.position_fees_a);
+        let surplus_b = ctx.accounts.pool_token_b_vault.amount
+            .saturating_sub(pool.reserve_b)
+            .saturating_sub(pool.creator_fees_b)
+            .saturating_sub(pool.position_reserve_b)
+            .saturating_sub(pool.position_fees_b);
 
-    // LP token mint - owned and controlled by the pool authority
-    #[account(
-        init,
-        payer = payer,
-        mint::decimals = 6,
-        mint::authority = pool_authority,
-        seeds = [b"lp_mint", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
-        bump
-    )]
-    pub lp_mint: Account<'info, Mint>,
+        let seeds = &[
+            b"pool_authority",
+            ctx.accounts.pool_state.token_a.as_ref(),
+            ctx.accounts.pool_state.token_b.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&seeds[..]];
 
-    // FIXED: Vault A as PDA - no signer required
-    #[account(
-        init,
-        payer = payer,
-        token::mint = token_a_mint,
-        token::authority = pool_authority,
-        seeds = [b"vault_a", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
-        bump
-    )]
-    pub token_a_vault: Account<'info, TokenAccount>,
-    
-    // FIXED: Vault B as PDA - no signer required
-    #[account(
-        init,
-        payer = payer,
-        token::mint = token_b_mint,
-        token::authority = pool_authority,
-        seeds = [b"vault_b", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
-        bump
-    )]
-    pub token_b_vault: Account<'info, TokenAccount>,
+        if surplus_a > 0 {
+            let cpi_ctx_a = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_a_vault.to_account_info(),
+                    to: ctx.accounts.recipient_token_a.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_a, surplus_a)?;
+        }
 
-    /// CHECK: PDA used as authority for token vaults and LP mint
-    #[account(
-        seeds = [b"pool_authority", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
-        bump
-    )]
-    pub pool_authority: UncheckedAccount<'info>,
+        if surplus_b > 0 {
+            let cpi_ctx_b = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_b_vault.to_account_info(),
+                    to: ctx.accounts.recipient_token_b.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_b, surplus_b)?;
+        }
 
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        msg!("Skimmed {} token A, {} token B", surplus_a, surplus_b);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct AddLiquidity<'info> {
-    #[account(
-        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump = pool_state.bump
-    )]
-    pub pool_state: Account<'info, PoolState>,
+    // Pays the pool creator their accrued share of swap fees
+    pub fn collect_creator_fees(ctx: Context<CollectCreatorFees>) -> Result<()> {
+        let fees_a = ctx.accounts.pool_state.creator_fees_a;
+        let fees_b = ctx.accounts.pool_state.creator_fees_b;
 
-    #[account(
-        mut,
-        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
-    )]
-    pub user_token_a: Account<'info, TokenAccount>,
-    
-    #[account(
-        mut,
-        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
-    )]
-    pub user_token_b: Account<'info, TokenAccount>,
+        let seeds = &[
+            b"pool_authority",
+            ctx.accounts.pool_state.token_a.as_ref(),
+            ctx.accounts.pool_state.token_b.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&seeds[..]];
 
-    // User's LP token account - where minted LP tokens will be sent
-    #[account(
-        mut,
-        constraint = user_lp_token.mint == pool_state.lp_mint @ SwapError::InvalidTokenMint
-    )]
-    pub user_lp_token: Account<'info, TokenAccount>,
+        if fees_a > 0 {
+            let cpi_ctx_a = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_a_vault.to_account_info(),
+                    to: ctx.accounts.creator_token_a.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_a, fees_a)?;
+        }
 
-    #[account(
-        mut,
-        constraint = pool_token_a_vault.mint == pool_state.token_a @ SwapError::InvalidTokenMint,
-        constraint = pool_token_a_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
-        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump
-    )]
-    pub pool_token_a_vault: Account<'info, TokenAccount>,
-    
-    #[account(
-        mut,
-        constraint = pool_token_b_vault.mint == pool_state.token_b @ SwapError::InvalidTokenMint,
-        constraint = pool_token_b_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
-        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump
-    )]
-    pub pool_token_b_vault: Account<'info, TokenAccount>,
+        if fees_b > 0 {
+            let cpi_ctx_b = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_b_vault.to_account_info(),
+                    to: ctx.accounts.creator_token_b.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_b, fees_b)?;
+        }
 
-    #[account(
-        mut,
-        constraint = lp_mint.key() == pool_state.lp_mint @ SwapError::InvalidTokenMint
-    )]
-    pub lp_mint: Account<'info, Mint>,
+        let pool = &mut ctx.accounts.pool_state;
+        pool.creator_fees_a = 0;
+        pool.creator_fees_b = 0;
 
-    /// CHECK: PDA authority for vaults and LP mint
-    #[account(
-        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump
+        msg!("Creator fees collected: {} token A, {} token B", fees_a, fees_b);
+        Ok(())
+    }
+
+    // Opens an empty ranged position in a concentrated pool and mints its
+    // NFT to the owner. The first position switches the pool to tick pricing
+    // at the price its reserves imply.
+    pub fn open_position(ctx: Context<OpenPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        validate_tick_range(tick_lower, tick_upper)?;
+
+        ctx.accounts.pool_state.start_tick_pricing()?;
+
+        let pool_ticks = &mut ctx.accounts.pool_ticks;
+        pool_ticks.pool = ctx.accounts.pool_state.key();
+        pool_ticks.bump = ctx.bumps.pool_ticks;
+
+        let position = &mut ctx.accounts.position;
+        position.pool = ctx.accounts.pool_state.key();
+        position.position_mint = ctx.accounts.position_mint.key();
+        position.tick_lower = tick_lower;
+        position.tick_upper = tick_upper;
+        position.bump = ctx.bumps.position;
+
+        let seeds = &[
+            b"pool_authority",
+            ctx.accounts.pool_state.token_a.as_ref(),
+            ctx.accounts.pool_state.token_b.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Token-2022 grows the mint to hold the metadata but doesn't pay its rent
+        let mint_info = ctx.accounts.position_mint.to_account_info();
+        let metadata = TokenMetadata {
+            update_authority: Some(ctx.accounts.pool_authority.key()).try_into()?,
+            mint: mint_info.key(),
+            name: POSITION_NFT_NAME.to_string(),
+            symbol: POSITION_NFT_SYMBOL.to_string(),
+            uri: String::new(),
+            additional_metadata: vec![],
+        };
+        let new_len = mint_info.data_len() + metadata.tlv_size_of()?;
+        let rent_due = Rent::get()?.minimum_balance(new_len).saturating_sub(mint_info.lamports());
+        if rent_due > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: mint_info.clone(),
+                },
+            );
+            system_program::transfer(cpi_ctx, rent_due)?;
+        }
+
+        let cpi_ctx_metadata = CpiContext::new_with_signer(
+            ctx.accounts.token_2022_program.to_account_info(),
+            token_interface::TokenMetadataInitialize {
+                program_id: ctx.accounts.token_2022_program.to_account_info(),
+                metadata: mint_info.clone(),
+                update_authority: ctx.accounts.pool_authority.to_account_info(),
+                mint_authority: ctx.accounts.pool_authority.to_account_info(),
+                mint: mint_info.clone(),
+            },
+            signer,
+        );
+        token_interface::token_metadata_initialize(cpi_ctx_metadata, metadata.name, metadata.symbol, metadata.uri)?;
+
+        let cpi_ctx_mint = CpiContext::new_with_signer(
+            ctx.accounts.token_2022_program.to_account_info(),
+            token_2022::MintTo {
+                mint: mint_info.clone(),
+                to: ctx.accounts.position_token_account.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer,
+        );
+        token_2022::mint_to(cpi_ctx_mint, 1)?;
+
+        // One of one: nobody can mint another
+        let cpi_ctx_authority = CpiContext::new_with_signer(
+            ctx.accounts.token_2022_program.to_account_info(),
+            token_2022::SetAuthority {
+                current_authority: ctx.accounts.pool_authority.to_account_info(),
+                account_or_mint: mint_info,
+            },
+            signer,
+        );
+        token_2022::set_authority(cpi_ctx_authority, AuthorityType::MintTokens, None)?;
+
+        msg!("Position opened: ticks {} to {}, NFT {}",
+             tick_lower, tick_upper, ctx.accounts.position_mint.key());
+        Ok(())
+    }
+
+    // Adds as much liquidity to a position as the maximum amounts fund at
+    // the current price
+    pub fn increase_liquidity(
+        ctx: Context<ModifyPosition>,
+        amount_a_max: u64,
+        amount_b_max: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        check_deadline(deadline)?;
+        let pool = &ctx.accounts.pool_state;
+        let position = &ctx.accounts.position;
+        let sqrt_price_lower = sqrt_price_at_tick(position.tick_lower).ok_or(SwapError::InvalidTickRange)?;
+        let sqrt_price_upper = sqrt_price_at_tick(position.tick_upper).ok_or(SwapError::InvalidTickRange)?;
+
+        let liquidity = liquidity_for_amounts(
+            pool.sqrt_price_x64,
+            sqrt_price_lower,
+            sqrt_price_upper,
+            amount_a_max,
+            amount_b_max,
+        )
+        .ok_or(SwapError::MathOverflow)?;
+        require!(liquidity > 0, SwapError::InvalidAmount);
+        let new_liquidity = position.liquidity.checked_add(liquidity).ok_or(SwapError::MathOverflow)?;
+        require!(new_liquidity >= MIN_TICK_LIQUIDITY, SwapError::LiquidityBelowMinimum);
+        let liquidity_delta = i128::try_from(liquidity).map_err(|_| SwapError::MathOverflow)?;
+
+        // Round deposits up so the position never owns more than was paid in
+        let (amount_a, amount_b) =
+            position_amounts(pool.sqrt_price_x64, sqrt_price_lower, sqrt_price_upper, liquidity, true)
+                .ok_or(SwapError::MathOverflow)?;
+        require!(amount_a <= amount_a_max && amount_b <= amount_b_max, SwapError::SlippageExceeded);
+
+        if amount_a > 0 {
+            let cpi_ctx_a = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token_a.to_account_info(),
+                    to: ctx.accounts.pool_token_a_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx_a, amount_a)?;
+        }
+        if amount_b > 0 {
+            let cpi_ctx_b = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user_token_b.to_account_info(),
+                    to: ctx.accounts.pool_token_b_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx_b, amount_b)?;
+        }
+
+        let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+        ctx.accounts.pool_ticks.update_range(tick_lower, tick_upper, liquidity_delta, &ctx.accounts.pool_state)?;
+        // Fees earned so far belong to the liquidity that earned them
+        let fee_growth_inside = ctx.accounts.pool_ticks
+            .fee_growth_inside(tick_lower, tick_upper, &ctx.accounts.pool_state)
+            .ok_or(SwapError::InvalidTickRange)?;
+        let position = &mut ctx.accounts.position;
+        position.accrue_fees(fee_growth_inside)?;
+        position.liquidity = new_liquidity;
+
+        let pool = &mut ctx.accounts.pool_state;
+        if (tick_lower..tick_upper).contains(&pool.tick_current) {
+            pool.liquidity = pool.liquidity.checked_add(liquidity).ok_or(SwapError::MathOverflow)?;
+        }
+        pool.position_reserve_a = pool.position_reserve_a.checked_add(amount_a).ok_or(SwapError::MathOverflow)?;
+        pool.position_reserve_b = pool.position_reserve_b.checked_add(amount_b).ok_or(SwapError::MathOverflow)?;
+
+        msg!("Position liquidity increased by {}: {} token A, {} token B", liquidity, amount_a, amount_b);
+        Ok(())
+    }
+
+    // Takes liquidity out of a position and pays its tokens to the NFT holder
+    pub fn decrease_liquidity(
+        ctx: Context<ModifyPosition>,
+        liquidity: u128,
+        minimum_a_out: u64,
+        minimum_b_out: u64,
+        deadline: Option<i64>,
+    ) -> Result<()> {
+        check_deadline(deadline)?;
+        require!(liquidity > 0, SwapError::InvalidAmount);
+        let pool = &ctx.accounts.pool_state;
+        let position = &ctx.accounts.position;
+        require!(liquidity <= position.liquidity, SwapError::InsufficientPositionLiquidity);
+        let remaining = position.liquidity - liquidity;
+        require!(remaining == 0 || remaining >= MIN_TICK_LIQUIDITY, SwapError::LiquidityBelowMinimum);
+        let liquidity_delta = i128::try_from(liquidity).map_err(|_| SwapError::MathOverflow)?;
+        let sqrt_price_lower = sqrt_price_at_tick(position.tick_lower).ok_or(SwapError::InvalidTickRange)?;
+        let sqrt_price_upper = sqrt_price_at_tick(position.tick_upper).ok_or(SwapError::InvalidTickRange)?;
+
+        let (amount_a, amount_b) =
+            position_amounts(pool.sqrt_price_x64, sqrt_price_lower, sqrt_price_upper, liquidity, false)
+                .ok_or(SwapError::MathOverflow)?;
+        require!(amount_a >= minimum_a_out && amount_b >= minimum_b_out, SwapError::SlippageExceeded);
+        require!(
+            amount_a <= pool.position_reserve_a && amount_b <= pool.position_reserve_b,
+            SwapError::InsufficientLiquidity
+        );
+
+        // Settle fees while the range's ticks still exist; collect_fees pays them
+        let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+        let fee_growth_inside = ctx.accounts.pool_ticks
+            .fee_growth_inside(tick_lower, tick_upper, pool)
+            .ok_or(SwapError::InvalidTickRange)?;
+        ctx.accounts.position.accrue_fees(fee_growth_inside)?;
+        ctx.accounts.pool_ticks.update_range(tick_lower, tick_upper, -liquidity_delta, &ctx.accounts.pool_state)?;
+        ctx.accounts.position.liquidity -= liquidity;
+
+        let pool = &mut ctx.accounts.pool_state;
+        if (tick_lower..tick_upper).contains(&pool.tick_current) {
+            pool.liquidity -= liquidity;
+        }
+        pool.position_reserve_a -= amount_a;
+        pool.position_reserve_b -= amount_b;
+
+        let seeds = &[
+            b"pool_authority",
+            ctx.accounts.pool_state.token_a.as_ref(),
+            ctx.accounts.pool_state.token_b.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&seeds[..]];
+
+        if amount_a > 0 {
+            let cpi_ctx_a = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_a_vault.to_account_info(),
+                    to: ctx.accounts.user_token_a.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_a, amount_a)?;
+        }
+        if amount_b > 0 {
+            let cpi_ctx_b = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_b_vault.to_account_info(),
+                    to: ctx.accounts.user_token_b.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_b, amount_b)?;
+        }
+
+        msg!("Position liquidity decreased by {}: {} token A, {} token B", liquidity, amount_a, amount_b);
+        Ok(())
+    }
+
+    // Pays a position's earned fees to the NFT holder, leaving its liquidity
+    // in place
+    pub fn collect_fees(ctx: Context<ModifyPosition>) -> Result<()> {
+        let position = &ctx.accounts.position;
+        if position.liquidity > 0 {
+            let fee_growth_inside = ctx.accounts.pool_ticks
+                .fee_growth_inside(position.tick_lower, position.tick_upper, &ctx.accounts.pool_state)
+                .ok_or(SwapError::InvalidTickRange)?;
+            ctx.accounts.position.accrue_fees(fee_growth_inside)?;
+        }
+
+        let position = &mut ctx.accounts.position;
+        let (fees_a, fees_b) = (position.tokens_owed_a, position.tokens_owed_b);
+        position.tokens_owed_a = 0;
+        position.tokens_owed_b = 0;
+        let pool = &mut ctx.accounts.pool_state;
+        pool.position_fees_a = pool.position_fees_a.checked_sub(fees_a).ok_or(SwapError::MathOverflow)?;
+        pool.position_fees_b = pool.position_fees_b.checked_sub(fees_b).ok_or(SwapError::MathOverflow)?;
+
+        let seeds = &[
+            b"pool_authority",
+            ctx.accounts.pool_state.token_a.as_ref(),
+            ctx.accounts.pool_state.token_b.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&seeds[..]];
+
+        if fees_a > 0 {
+            let cpi_ctx_a = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_a_vault.to_account_info(),
+                    to: ctx.accounts.user_token_a.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_a, fees_a)?;
+        }
+        if fees_b > 0 {
+            let cpi_ctx_b = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_b_vault.to_account_info(),
+                    to: ctx.accounts.user_token_b.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_b, fees_b)?;
+        }
+
+        msg!("Position fees collected: {} token A, {} token B", fees_a, fees_b);
+        Ok(())
+    }
+
+    // Places a limit order selling `amount` of one token between `tick` and
+    // the next tick spacing, which has to be on the far side of the price:
+    // above it to sell token A, below it to sell token B. A wallet has one
+    // order per tick; the one there has to be claimed or cancelled first.
+    pub fn place_limit_order(ctx: Context<PlaceLimitOrder>, tick: i32, sells_a: bool, amount: u64) -> Result<()> {
+        require_keys_eq!(ctx.accounts.limit_order.owner, Pubkey::default(), SwapError::LimitOrderExists);
+        validate_tick_range(tick, tick + TICK_SPACING)?;
+        ctx.accounts.pool_state.start_tick_pricing()?;
+        let pool = &ctx.accounts.pool_state;
+        // An order in range would start out partly filled
+        let beyond_price = if sells_a {
+            tick > pool.tick_current
+        } else {
+            tick + TICK_SPACING <= pool.tick_current
+        };
+        require!(beyond_price, SwapError::InvalidOrderPrice);
+
+        let sqrt_price_lower = sqrt_price_at_tick(tick).ok_or(SwapError::InvalidTickRange)?;
+        let sqrt_price_upper = sqrt_price_at_tick(tick + TICK_SPACING).ok_or(SwapError::InvalidTickRange)?;
+        let (amount_a_max, amount_b_max) = if sells_a { (amount, 0) } else { (0, amount) };
+        let liquidity =
+            liquidity_for_amounts(pool.sqrt_price_x64, sqrt_price_lower, sqrt_price_upper, amount_a_max, amount_b_max)
+                .ok_or(SwapError::MathOverflow)?;
+        require!(liquidity > 0, SwapError::InvalidAmount);
+        require!(liquidity >= MIN_TICK_LIQUIDITY, SwapError::LiquidityBelowMinimum);
+        let (amount_a, amount_b) =
+            position_amounts(pool.sqrt_price_x64, sqrt_price_lower, sqrt_price_upper, liquidity, true)
+                .ok_or(SwapError::MathOverflow)?;
+
+        let (user_token, vault, deposit) = if sells_a {
+            (&ctx.accounts.user_token_a, &ctx.accounts.pool_token_a_vault, amount_a)
+        } else {
+            (&ctx.accounts.user_token_b, &ctx.accounts.pool_token_b_vault, amount_b)
+        };
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: user_token.to_account_info(),
+                to: vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        );
+        token::transfer(cpi_ctx, deposit)?;
+
+        let pool_ticks = &mut ctx.accounts.pool_ticks;
+        pool_ticks.pool = ctx.accounts.pool_state.key();
+        pool_ticks.bump = ctx.bumps.pool_ticks;
+        let epoch = pool_ticks.place_order(tick, sells_a, liquidity, &ctx.accounts.pool_state)?;
+
+        let pool = &mut ctx.accounts.pool_state;
+        pool.position_reserve_a = pool.position_reserve_a.checked_add(amount_a).ok_or(SwapError::MathOverflow)?;
+        pool.position_reserve_b = pool.position_reserve_b.checked_add(amount_b).ok_or(SwapError::MathOverflow)?;
+
+        let order = &mut ctx.accounts.limit_order;
+        order.pool = ctx.accounts.pool_state.key();
+        order.owner = ctx.accounts.owner.key();
+        order.tick = tick;
+        order.sells_a = sells_a;
+        order.liquidity = liquidity;
+        order.epoch = epoch;
+        order.bump = ctx.bumps.limit_order;
+
+        msg!("Limit order placed at tick {}: selling {} token {}",
+             tick, deposit, if sells_a { "A" } else { "B" });
+        Ok(())
+    }
+
+    // Pays a filled limit order's proceeds to its owner and closes it
+    pub fn claim_filled_order(ctx: Context<SettleLimitOrder>) -> Result<()> {
+        let order = &ctx.accounts.limit_order;
+        require!(
+            ctx.accounts.pool_ticks.order_filled(order.tick, order.epoch),
+            SwapError::OrderNotFilled
+        );
+        let amount_out = filled_order_amount(order.tick, order.sells_a, order.liquidity)?;
+        let sells_a = order.sells_a;
+
+        let pool = &mut ctx.accounts.pool_state;
+        let position_reserve_out = if sells_a { &mut pool.position_reserve_b } else { &mut pool.position_reserve_a };
+        *position_reserve_out = position_reserve_out
+            .checked_sub(amount_out)
+            .ok_or(SwapError::InsufficientLiquidity)?;
+
+        let seeds = &[
+            b"pool_authority",
+            ctx.accounts.pool_state.token_a.as_ref(),
+            ctx.accounts.pool_state.token_b.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&seeds[..]];
+
+        let (vault, user_token) = if sells_a {
+            (&ctx.accounts.pool_token_b_vault, &ctx.accounts.user_token_b)
+        } else {
+            (&ctx.accounts.pool_token_a_vault, &ctx.accounts.user_token_a)
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: vault.to_account_info(),
+                to: user_token.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount_out)?;
+
+        msg!("Limit order claimed: {} token {}", amount_out, if sells_a { "B" } else { "A" });
+        Ok(())
+    }
+
+    // Takes back an unfilled limit order, in whatever mix of tokens it holds
+    // at the current price, and closes it
+    pub fn cancel_limit_order(ctx: Context<SettleLimitOrder>) -> Result<()> {
+        let order = &ctx.accounts.limit_order;
+        require!(
+            !ctx.accounts.pool_ticks.order_filled(order.tick, order.epoch),
+            SwapError::OrderAlreadyFilled
+        );
+        let (tick, liquidity) = (order.tick, order.liquidity);
+        let sqrt_price_lower = sqrt_price_at_tick(tick).ok_or(SwapError::InvalidTickRange)?;
+        let sqrt_price_upper = sqrt_price_at_tick(tick + TICK_SPACING).ok_or(SwapError::InvalidTickRange)?;
+        let (amount_a, amount_b) = position_amounts(
+            ctx.accounts.pool_state.sqrt_price_x64,
+            sqrt_price_lower,
+            sqrt_price_upper,
+            liquidity,
+            false,
+        )
+        .ok_or(SwapError::MathOverflow)?;
+
+        ctx.accounts.pool_ticks.cancel_order(tick, liquidity, &ctx.accounts.pool_state)?;
+        let pool = &mut ctx.accounts.pool_state;
+        if (tick..tick + TICK_SPACING).contains(&pool.tick_current) {
+            pool.liquidity -= liquidity;
+            pool.order_liquidity -= liquidity;
+        }
+        pool.position_reserve_a = pool.position_reserve_a.checked_sub(amount_a).ok_or(SwapError::InsufficientLiquidity)?;
+        pool.position_reserve_b = pool.position_reserve_b.checked_sub(amount_b).ok_or(SwapError::InsufficientLiquidity)?;
+
+        let seeds = &[
+            b"pool_authority",
+            ctx.accounts.pool_state.token_a.as_ref(),
+            ctx.accounts.pool_state.token_b.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer = &[&seeds[..]];
+
+        if amount_a > 0 {
+            let cpi_ctx_a = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_a_vault.to_account_info(),
+                    to: ctx.accounts.user_token_a.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_a, amount_a)?;
+        }
+        if amount_b > 0 {
+            let cpi_ctx_b = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.pool_token_b_vault.to_account_info(),
+                    to: ctx.accounts.user_token_b.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx_b, amount_b)?;
+        }
+
+        msg!("Limit order cancelled: {} token A, {} token B", amount_a, amount_b);
+        Ok(())
+    }
+
+    // Opens the pool's LP farm, which pays `emission_rate` reward tokens per
+    // second between `start_time` and `end_time`, shared among stakers in
+    // proportion to their stake. The admin funds the whole schedule up front.
+    pub fn create_farm(
+        ctx: Context<CreateFarm>,
+        start_time: i64,
+        end_time: i64,
+        emission_rate: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let start_time = start_time.max(now);
+        require!(end_time > start_time && emission_rate > 0, SwapError::InvalidFarmSchedule);
+
+        let farm = &mut ctx.accounts.farm;
+        farm.pool = ctx.accounts.pool_state.key();
+        farm.lp_mint = ctx.accounts.lp_mint.key();
+        farm.reward_mint = ctx.accounts.reward_mint.key();
+        farm.emission_rate = emission_rate;
+        farm.start_time = start_time;
+        farm.end_time = end_time;
+        farm.last_update_time = now;
+        farm.bump = ctx.bumps.farm;
+        let funding = farm.emissions_between(start_time, end_time).ok_or(SwapError::MathOverflow)?;
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.admin_reward_token.to_account_info(),
+                to: ctx.accounts.reward_vault.to_account_info(),
+                authority: ctx.accounts.admin.to_account_info(),
+            },
+        );
+        token::transfer(cpi_ctx, funding)?;
+
+        msg!("Farm created for pool {}: {} reward tokens/s from {} to {}, funded with {}",
+             farm.pool, emission_rate, start_time, end_time, funding);
+        Ok(())
+    }
+
+    // Adds reward tokens to a farm's vault, e.g. ahead of extend_farm
+    pub fn fund_farm(ctx: Context<FundFarm>, amount: u64) -> Result<()> {
+        require!(amount > 0, SwapError::InvalidAmount);
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.admin_reward_token.to_account_info(),
+                to: ctx.accounts.reward_vault.to_account_info(),
+                authority: ctx.accounts.admin.to_account_info(),
+            },
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        msg!("Farm funded with {} reward tokens", amount);
+        Ok(())
+    }
+
+    // Moves a farm's end time later and sets its emission rate from now on.
+    // The reward vault must already cover everything owed and scheduled.
+    pub fn extend_farm(ctx: Context<ExtendFarm>, end_time: i64, emission_rate: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let farm = &mut ctx.accounts.farm;
+        require!(
+            end_time >= farm.end_time && end_time > now && emission_rate > 0,
+            SwapError::InvalidFarmSchedule
+        );
+
+        // Pay out the old rate up to now before the new one applies
+        farm.update(now)?;
+        farm.end_time = end_time;
+        farm.emission_rate = emission_rate;
+        let obligations = farm.obligations(now).ok_or(SwapError::MathOverflow)?;
+        require!(ctx.accounts.reward_vault.amount >= obligations, SwapError::FarmUnderfunded);
+
+        msg!("Farm extended to {} at {} reward tokens/s", end_time, emission_rate);
+        Ok(())
+    }
+
+    // Sends the admin whatever the reward vault holds beyond what the farm
+    // still owes: emissions nobody was staked for, rounding dust and
+    // overfunding
+    pub fn reclaim_farm_rewards(ctx: Context<ReclaimFarmRewards>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let farm = &mut ctx.accounts.farm;
+        farm.update(now)?;
+        let obligations = farm.obligations(now).ok_or(SwapError::MathOverflow)?;
+        let surplus = ctx.accounts.reward_vault.amount.saturating_sub(obligations);
+
+        if surplus > 0 {
+            let seeds = &[b"farm", farm.pool.as_ref(), &[farm.bump]];
+            let signer = &[&seeds[..]];
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.admin_reward_token.to_account_info(),
+                    authority: farm.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx, surplus)?;
+        }
+
+        msg!("Reclaimed {} reward tokens, {} still owed", surplus, obligations);
+        Ok(())
+    }
+
+    // Closes a farm that has ended with nothing staked and every reward
+    // claimed, which close_pool needs first. Leftover reward tokens go to
+    // the admin, LP tokens sent to the stake vault outside stake_lp are
+    // burned, and the rent of the farm and its vaults goes to the recipient.
+    // Stake positions stay behind, empty.
+    pub fn close_farm(ctx: Context<CloseFarm>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let farm = &mut ctx.accounts.farm;
+        farm.update(now)?;
+        require!(
+            now >= farm.end_time && farm.total_staked == 0 && farm.obligations(now) == Some(0),
+            SwapError::FarmNotFinished
+        );
+
+        let seeds = &[b"farm", farm.pool.as_ref(), &[farm.bump]];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let stray_lp = ctx.accounts.stake_vault.amount;
+        if stray_lp > 0 {
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.clone(),
+                Burn {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    from: ctx.accounts.stake_vault.to_account_info(),
+                    authority: farm.to_account_info(),
+                },
+                signer,
+            );
+            token::burn(cpi_ctx, stray_lp)?;
+        }
+        let leftover_rewards = ctx.accounts.reward_vault.amount;
+        if leftover_rewards > 0 {
+            let cpi_ctx = CpiContext::new_with_signer(
+                token_program.clone(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.admin_reward_token.to_account_info(),
+                    authority: farm.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx, leftover_rewards)?;
+        }
+        for vault in [&ctx.accounts.stake_vault, &ctx.accounts.reward_vault] {
+            token::close_account(CpiContext::new_with_signer(
+                token_program.clone(),
+                CloseAccount {
+                    account: vault.to_account_info(),
+                    destination: ctx.accounts.recipient.to_account_info(),
+                    authority: farm.to_account_info(),
+                },
+                signer,
+            ))?;
+        }
+
+        msg!("Farm {} closed, {} reward tokens returned", farm.key(), leftover_rewards);
+        Ok(())
+    }
+
+    // Deposits LP tokens into the pool's farm
+    pub fn stake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+        require!(amount > 0, SwapError::InvalidAmount);
+        let farm = &mut ctx.accounts.farm;
+        farm.update(Clock::get()?.unix_timestamp)?;
+
+        let position = &mut ctx.accounts.stake_position;
+        if position.owner == Pubkey::default() {
+            position.farm = farm.key();
+            position.owner = ctx.accounts.owner.key();
+            position.bump = ctx.bumps.stake_position;
+        }
+        position.settle(farm.reward_per_share)?;
+        position.amount = position.amount.checked_add(amount).ok_or(SwapError::MathOverflow)?;
+        position.reset_reward_debt(farm.reward_per_share)?;
+        farm.total_staked = farm.total_staked.checked_add(amount).ok_or(SwapError::MathOverflow)?;
+
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.user_lp_token.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            },
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        msg!("Staked {} LP tokens, {} staked in total", amount, position.amount);
+        Ok(())
+    }
+
+    // Withdraws staked LP tokens. Rewards earned so far stay claimable.
+    pub fn unstake_lp(ctx: Context<UnstakeLp>, amount: u64) -> Result<()> {
+        require!(amount > 0, SwapError::InvalidAmount);
+        let farm = &mut ctx.accounts.farm;
+        farm.update(Clock::get()?.unix_timestamp)?;
+
+        let position = &mut ctx.accounts.stake_position;
+        require!(position.amount >= amount, SwapError::InsufficientStake);
+        position.settle(farm.reward_per_share)?;
+        position.amount -= amount;
+        position.reset_reward_debt(farm.reward_per_share)?;
+        farm.total_staked -= amount;
+
+        let seeds = &[b"farm", farm.pool.as_ref(), &[farm.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.stake_vault.to_account_info(),
+                to: ctx.accounts.user_lp_token.to_account_info(),
+                authority: farm.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        msg!("Unstaked {} LP tokens, {} still staked", amount, position.amount);
+        Ok(())
+    }
+
+    // Pays out the rewards a stake has earned
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        let farm = &mut ctx.accounts.farm;
+        farm.update(Clock::get()?.unix_timestamp)?;
+
+        let position = &mut ctx.accounts.stake_position;
+        position.settle(farm.reward_per_share)?;
+        let rewards = position.pending_rewards;
+        position.pending_rewards = 0;
+        farm.rewards_claimed = farm.rewards_claimed.checked_add(rewards).ok_or(SwapError::MathOverflow)?;
+
+        if rewards > 0 {
+            let seeds = &[b"farm", farm.pool.as_ref(), &[farm.bump]];
+            let signer = &[&seeds[..]];
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.user_reward_token.to_account_info(),
+                    authority: farm.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(cpi_ctx, rewards)?;
+        }
+
+        msg!("Claimed {} reward tokens", rewards);
+        Ok(())
+    }
+}
+
+// ========== TRANSACTION GUARDS ==========
+
+// Rejects transactions that land after the caller's unix timestamp deadline,
+// so a trade stuck in a queue can't execute at a stale price
+fn check_deadline(deadline: Option<i64>) -> Result<()> {
+    if let Some(deadline) = deadline {
+        require!(Clock::get()?.unix_timestamp <= deadline, SwapError::DeadlineExceeded);
+    }
+    Ok(())
+}
+
+// Whether a price moved by more than `max_bps` of where it started, in
+// either direction
+fn price_moved_beyond(price_before: U256, price_after: U256, max_bps: u16) -> bool {
+    let moved = if price_after > price_before { price_after - price_before } else { price_before - price_after };
+    moved * U256::from(BPS_DENOMINATOR) > price_before * U256::from(max_bps)
+}
+
+// Rejects swaps that moved the pool's spot price by more than
+// `max_price_impact_bps` in either direction
+fn check_price_impact(price_before: U256, price_after: U256, max_price_impact_bps: u16) -> Result<()> {
+    require!(
+        !price_moved_beyond(price_before, price_after, max_price_impact_bps),
+        SwapError::PriceImpactExceeded
+    );
+    Ok(())
+}
+
+// Rejects swaps that left the spot price beyond the caller's limit, given as
+// a Q64.64 square root like a concentrated pool's price
+fn check_price_limit(price_after: U256, sqrt_price_limit_x64: u128, a_to_b: bool) -> Result<()> {
+    let limit = (U256::from(sqrt_price_limit_x64) * U256::from(sqrt_price_limit_x64)) >> 64;
+    let within = if a_to_b { price_after >= limit } else { price_after <= limit };
+    require!(within, SwapError::PriceLimitExceeded);
+    Ok(())
+}
+
+// Most a constant product swap can take in, fee included, before the spot
+// price reaches `sqrt_price_limit_x64`. The fee joins the input reserve
+// without being priced, so with `target` the input reserve where a fee-free
+// swap would meet the limit, the input g solves
+// (x + g·(1 - fee)) · (x + g) = target². Rounded down, and taken two units
+// short for the rounded-down fee, so the price stops at or before the limit.
+// Zero if the price is already at or past it.
+fn input_to_price_limit(
+    input_balance: u64,
+    output_balance: u64,
+    fee_rate: u32,
+    sqrt_price_limit_x64: u128,
+    a_to_b: bool,
+) -> u64 {
+    let x = U256::from(input_balance);
+    let root_k = (x * U256::from(output_balance)).integer_sqrt();
+    let limit = U256::from(sqrt_price_limit_x64);
+    let target = if a_to_b { (root_k << 64) / limit.max(U256::one()) } else { (root_k * limit) >> 64 };
+    if target <= x {
+        return 0;
+    }
+    // Past this the input wouldn't fit in a u64 anyway
+    let target = target.min(U256::from(u64::MAX) << 1);
+
+    let denominator = U256::from(FEE_DENOMINATOR);
+    let a = denominator - U256::from(fee_rate);
+    let b = x * (denominator + a);
+    let c = denominator * (target * target - x * x);
+    let gross = ((b * b + U256::from(4) * a * c).integer_sqrt() - b) / (U256::from(2) * a);
+    gross.saturating_sub(U256::from(2)).min(U256::from(u64::MAX)).as_u64()
+}
+
+// ========== FLASH SWAP HELPERS ==========
+
+// Calls the flash swap borrower. remaining_accounts[0] is the program to call,
+// the rest are handed to it as the callback instruction's accounts.
+fn invoke_flash_callback<'info>(remaining_accounts: &[AccountInfo<'info>], data: Vec<u8>) -> Result<()> {
+    let (callback_program, callback_accounts) = remaining_accounts
+        .split_first()
+        .ok_or(SwapError::MissingCallbackProgram)?;
+
+    require!(callback_program.executable, SwapError::InvalidCallbackProgram);
+    // Re-entering the AMM from the callback would let a nested swap move the
+    // balances this swap is about to check
+    require_keys_neq!(callback_program.key(), crate::ID, SwapError::InvalidCallbackProgram);
+
+    let ix = Instruction {
+        program_id: callback_program.key(),
+        accounts: callback_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+    invoke(&ix, remaining_accounts)?;
+    Ok(())
+}
+
+// The vaults must end up where an ordinary swap would have left them or better:
+// whatever came into the input vault, after fees, has to buy at least what left
+// the output vault. Paying back part of the output token is allowed.
+fn flash_swap_invariant_holds(
+    pool: &PoolState,
+    a_to_b: bool,
+    input_before: u64,
+    output_before: u64,
+    input_after: u64,
+    output_after: u64,
+) -> bool {
+    if input_after < input_before {
+        return false;
+    }
+    let amount_out = output_before.saturating_sub(output_after);
+    if amount_out == 0 {
+        return true;
+    }
+
+    let amount_in = input_after - input_before;
+    let amount_in_after_fee = amount_in - calculate_fee(amount_in, pool.fee_rate);
+    calculate_swap_output(pool, a_to_b, input_before, output_before, amount_in_after_fee)
+        .is_some_and(|quote| quote >= amount_out)
+}
+
+// ========== NATIVE SOL HELPERS ==========
+
+// The optional accounts that let a user pay or be paid in native SOL on a
+// pool pairing wrapped SOL, instead of managing a wSOL token account
+struct NativeSol<'a, 'info> {
+    wsol_account: Option<&'a UncheckedAccount<'info>>,
+    wsol_bump: Option<u8>,
+    native_mint: Option<&'a Account<'info, Mint>>,
+    system_program: Option<&'a Program<'info, System>>,
+    user: &'a Signer<'info>,
+    token_program: &'a Program<'info, Token>,
+}
+
+impl<'info> NativeSol<'_, 'info> {
+    // The user's accounts for tokens A and B. Leaving one out pays or receives
+    // that side, which must be wrapped SOL, in native SOL: `wrap_a` or
+    // `wrap_b` lamports move from the wallet into a temporary wSOL account
+    // that stands in for it. That account comes back third, to be closed with
+    // `unwrap_sol` once the instruction is done with it.
+    fn user_token_accounts(
+        &self,
+        pool: &PoolState,
+        user_token_a: &Option<Account<'info, TokenAccount>>,
+        user_token_b: &Option<Account<'info, TokenAccount>>,
+        wrap_a: u64,
+        wrap_b: u64,
+    ) -> Result<(AccountInfo<'info>, AccountInfo<'info>, Option<AccountInfo<'info>>)> {
+        match (user_token_a, user_token_b) {
+            (Some(token_a), Some(token_b)) => Ok((token_a.to_account_info(), token_b.to_account_info(), None)),
+            (None, Some(token_b)) => {
+                require_keys_eq!(pool.token_a, token::spl_token::native_mint::ID, SwapError::InvalidNativeSol);
+                let wsol = self.wrap_sol(wrap_a)?;
+                Ok((wsol.clone(), token_b.to_account_info(), Some(wsol)))
+            }
+            (Some(token_a), None) => {
+                require_keys_eq!(pool.token_b, token::spl_token::native_mint::ID, SwapError::InvalidNativeSol);
+                let wsol = self.wrap_sol(wrap_b)?;
+                Ok((token_a.to_account_info(), wsol.clone(), Some(wsol)))
+            }
+            (None, None) => err!(SwapError::InvalidNativeSol),
+        }
+    }
+
+    // Creates the user's temporary wSOL account at [b"wsol", user] holding
+    // `amount` lamports of wrapped SOL
+    fn wrap_sol(&self, amount: u64) -> Result<AccountInfo<'info>> {
+        let (Some(wsol_account), Some(bump), Some(native_mint), Some(system_program)) =
+            (self.wsol_account, self.wsol_bump, self.native_mint, self.system_program)
+        else {
+            return err!(SwapError::InvalidNativeSol);
+        };
+        let wsol = wsol_account.to_account_info();
+        let system = system_program.to_account_info();
+        let user = self.user.to_account_info();
+        let user_key = user.key();
+        let seeds = &[b"wsol", user_key.as_ref(), &[bump]];
+        let signer = &[&seeds[..]];
+
+        let lamports = Rent::get()?
+            .minimum_balance(TokenAccount::LEN)
+            .checked_add(amount)
+            .ok_or(SwapError::MathOverflow)?;
+        if wsol.lamports() == 0 {
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    system.clone(),
+                    system_program::CreateAccount { from: user.clone(), to: wsol.clone() },
+                    signer,
+                ),
+                lamports,
+                TokenAccount::LEN as u64,
+                &token::ID,
+            )?;
+        } else {
+            // Anyone can send lamports to the address first, which
+            // create_account refuses, so top it up and claim it instead
+            let shortfall = lamports.saturating_sub(wsol.lamports());
+            if shortfall > 0 {
+                system_program::transfer(
+                    CpiContext::new(system.clone(), system_program::Transfer { from: user.clone(), to: wsol.clone() }),
+                    shortfall,
+                )?;
+            }
+            system_program::allocate(
+                CpiContext::new_with_signer(system.clone(), system_program::Allocate { account_to_allocate: wsol.clone() }, signer),
+                TokenAccount::LEN as u64,
+            )?;
+            system_program::assign(
+                CpiContext::new_with_signer(system, system_program::Assign { account_to_assign: wsol.clone() }, signer),
+                &token::ID,
+            )?;
+        }
+
+        // A native account's balance is whatever it holds above rent
+        token::initialize_account3(CpiContext::new(
+            self.token_program.to_account_info(),
+            token::InitializeAccount3 { account: wsol.clone(), mint: native_mint.to_account_info(), authority: user },
+        ))?;
+        Ok(wsol)
+    }
+
+    // Closes the temporary wSOL account, unwrapping all it holds, rent
+    // included, back into the user's wallet
+    fn unwrap_sol(&self, wsol: Option<AccountInfo<'info>>) -> Result<()> {
+        if let Some(wsol) = wsol {
+            token::close_account(CpiContext::new(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: wsol,
+                    destination: self.user.to_account_info(),
+                    authority: self.user.to_account_info(),
+                },
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+// ========== SWAP CALCULATION FUNCTIONS ==========
+
+// Fee rates are expressed out of this denominator (300 = 0.3%)
+pub const FEE_DENOMINATOR: u128 = 100000;
+
+// Fee rate a new pool of the given type charges
+pub fn pool_fee_rate(pool_type: u8) -> Option<u32> {
+    match pool_type {
+        0 => Some(300), // Standard: 0.3%
+        1 => Some(50),  // Stable: 0.05%
+        2 => Some(500), // Concentrated: 0.5%
+        _ => None,
+    }
+}
+
+fn calculate_fee(amount_in: u64, fee_rate: u32) -> u64 {
+    ((amount_in as u128 * fee_rate as u128) / FEE_DENOMINATOR) as u64
+}
+
+// A referrer's share of a swap fee
+fn calculate_referral_fee(fee_amount: u64, referral_fee_bps: u16) -> u64 {
+    (fee_amount as u128 * referral_fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+// Output for `amount_in` (already net of fees) on the pool's curve.
+// None if the curve math overflows.
+fn calculate_swap_output(
+    pool: &PoolState,
+    a_to_b: bool,
+    input_balance: u64,
+    output_balance: u64,
+    amount_in: u64,
+) -> Option<u64> {
+    match pool.pool_type {
+        0 => Some(calculate_standard_swap(input_balance, output_balance, amount_in)),
+        1 => {
+            let (input_rate, output_rate) = if a_to_b {
+                (pool.token_a_rate, pool.token_b_rate)
+            } else {
+                (pool.token_b_rate, pool.token_a_rate)
+            };
+            calculate_stable_swap(input_balance, output_balance, amount_in, input_rate, output_rate)
+        },
+        2 => Some(calculate_concentrated_swap(input_balance, output_balance, amount_in)),
+        _ => Some(calculate_standard_swap(input_balance, output_balance, amount_in)), // fallback
+    }
+}
+
+fn calculate_standard_swap(input_balance: u64, output_balance: u64, amount_in: u64) -> u64 {
+    // Standard constant product formula: x * y = k
+    let amount_out = (output_balance as u128 * amount_in as u128)
+        / (input_balance as u128 + amount_in as u128);
+    amount_out as u64
+}
+
+fn calculate_stable_swap(
+    input_balance: u64,
+    output_balance: u64,
+    amount_in: u64,
+    input_rate: u64,
+    output_rate: u64,
+) -> Option<u64> {
+    // Stable swap: keep the StableSwap invariant D constant. Balances are scaled
+    // by their rate so both tokens are compared at the same number of decimals.
+    let x = normalize_amount(input_balance, input_rate)?;
+    let y = normalize_amount(output_balance, output_rate)?;
+    let d = stable_invariant(x, y, STABLE_AMP)?;
+
+    let new_x = x.checked_add(normalize_amount(amount_in, input_rate)?)?;
+    let new_y = stable_balance_for_invariant(new_x, d, STABLE_AMP)?;
+
+    // Round against the trader so the invariant can't shrink: one normalized
+    // unit stays in the pool, and scaling back down truncates
+    let amount_out = y.saturating_sub(new_y).saturating_sub(U256::one()) / U256::from(output_rate);
+    Some(amount_out.as_u64())
+}
+
+fn calculate_concentrated_swap(input_balance: u64, output_balance: u64, amount_in: u64) -> u64 {
+    // Concentrated liquidity: until pools track ranged positions, all liquidity
+    // spans the full price range, where the curve is exactly constant product.
+    // (A flat "better price" bonus paid out of the LPs' reserves let every
+    // trade shrink the pool.)
+    calculate_standard_swap(input_balance, output_balance, amount_in)
+}
+
+// The quantity a pool's curve keeps constant across swaps: x * y for
+// constant product pools, D for stable pools. Swaps never shrink it, and
+// deposits and withdrawals never shrink it per LP token (per LP token squared
+// for x * y). None if the stable solver gives up.
+pub fn curve_invariant(pool: &PoolState, reserve_a: u64, reserve_b: u64) -> Option<U256> {
+    match pool.pool_type {
+        1 => stable_invariant(
+            normalize_amount(reserve_a, pool.token_a_rate)?,
+            normalize_amount(reserve_b, pool.token_b_rate)?,
+            STABLE_AMP,
+        ),
+        _ => Some(U256::from(reserve_a) * U256::from(reserve_b)),
+    }
+}
+
+// ========== LP TOKEN CALCULATION FUNCTIONS ==========
+
+// LP tokens for a deposit into a pool that already has liquidity.
+// None if the math overflows or the pool has no reserves to compare against.
+fn calculate_deposit_lp(
+    pool: &PoolState,
+    reserve_a: u64,
+    reserve_b: u64,
+    amount_a: u64,
+    amount_b: u64,
+    lp_supply: u64,
+) -> Option<u64> {
+    match pool.pool_type {
+        // Stable pool: LP tokens follow the growth of the StableSwap invariant
+        1 => calculate_stable_deposit(pool, reserve_a, reserve_b, amount_a, amount_b, lp_supply),
+        // Standard and concentrated pools: proportional to the existing ratio,
+        // credited for the smaller side so a lopsided deposit can't claim the
+        // other side's tokens. (Concentrated pools used to add a 10% bonus,
+        // which let an add followed by a remove drain the pool.)
+        _ => {
+            let ratio_a = (amount_a as u128 * lp_supply as u128).checked_div(reserve_a as u128)?;
+            let ratio_b = (amount_b as u128 * lp_supply as u128).checked_div(reserve_b as u128)?;
+            u64::try_from(std::cmp::min(ratio_a, ratio_b)).ok()
+        }
+    }
+}
+
+// Share of `reserve` owed for burning `lp_amount` out of `lp_supply`, rounded down
+fn calculate_withdrawal(reserve: u64, lp_amount: u64, lp_supply: u64) -> Option<u64> {
+    let amount = (reserve as u128 * lp_amount as u128).checked_div(lp_supply as u128)?;
+    u64::try_from(amount).ok()
+}
+
+// ========== STABLESWAP MATH ==========
+
+mod u256 {
+    #![allow(clippy::all)]
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+    uint::construct_uint! {
+        pub struct U512(8);
+    }
+}
+pub use u256::U256;
+use u256::U512;
+
+// Amplification coefficient for stable pools. The higher it is, the longer
+// the price stays near 1:1 before the curve bends towards constant product.
+pub const STABLE_AMP: u64 = 100;
+
+const STABLE_MAX_ITERATIONS: usize = 255;
+
+fn normalize_amount(amount: u64, rate: u64) -> Option<U256> {
+    U256::from(amount).checked_mul(U256::from(rate))
+}
+
+// Multiplier that lifts an amount with `decimals` to `target_decimals`
+fn decimals_rate(decimals: u8, target_decimals: u8) -> Option<u64> {
+    10u64.checked_pow(u32::from(target_decimals.checked_sub(decimals)?))
+}
+
+fn converged(a: U256, b: U256) -> bool {
+    if a > b { a - b <= U256::one() } else { b - a <= U256::one() }
+}
+
+// Solves the two-token StableSwap invariant for D by Newton's method:
+// 4A(x + y) + D = 4AD + D^3 / (4xy)
+fn stable_invariant(x: U256, y: U256, amp: u64) -> Option<U256> {
+    let sum = x.checked_add(y)?;
+    if sum.is_zero() {
+        return Some(U256::zero());
+    }
+    if x.is_zero() || y.is_zero() {
+        return None;
+    }
+
+    let ann = U256::from(amp) * U256::from(4);
+    let mut d = sum;
+    for _ in 0..STABLE_MAX_ITERATIONS {
+        // d_p = D^3 / (4xy), built up one factor at a time to stay in range.
+        // The remainder of the first division is carried into the second:
+        // dropping it is amplified by D / 2y, which on a badly imbalanced
+        // pool is enough to keep the iteration from ever converging.
+        let two_x = x * U256::from(2);
+        let (quotient, remainder) = d.checked_mul(d)?.div_mod(two_x);
+        let d_p = quotient.checked_mul(d)? / (y * U256::from(2))
+            + remainder.checked_mul(d)? / two_x.checked_mul(y)?.checked_mul(U256::from(2))?;
+
+        let d_prev = d;
+        let numerator = ann
+            .checked_mul(sum)?
+            .checked_add(d_p.checked_mul(U256::from(2))?)?
+            .checked_mul(d)?;
+        let denominator = (ann - U256::one())
+            .checked_mul(d)?
+            .checked_add(d_p.checked_mul(U256::from(3))?)?;
+        d = numerator / denominator;
+
+        if converged(d, d_prev) {
+            return Some(d);
+        }
+    }
+    None
+}
+
+// Balance of the other token that keeps the invariant at `d` once one
+// side holds `x`. Solves y^2 + (b - D)y = c by Newton's method.
+fn stable_balance_for_invariant(x: U256, d: U256, amp: u64) -> Option<U256> {
+    if x.is_zero() {
+        return None;
+    }
+
+    let ann = U256::from(amp) * U256::from(4);
+    let c = d.checked_mul(d)? / (x * U256::from(2));
+    let c = c.checked_mul(d)? / (ann * U256::from(2));
+    let b = x.checked_add(d / ann)?;
+
+    let mut y = d;
+    for _ in 0..STABLE_MAX_ITERATIONS {
+        let y_prev = y;
+        let numerator = y.checked_mul(y)?.checked_add(c)?;
+        let denominator = (y * U256::from(2)).checked_add(b)?.checked_sub(d)?;
+        y = numerator / denominator;
+
+        if converged(y, y_prev) {
+            return Some(y);
+        }
+    }
+    None
+}
+
+// LP tokens for a stable pool deposit, proportional to how much it grows D.
+// Deposits that move the pool away from its current ratio pay half the swap
+// fee on the imbalanced part, so add + remove can't be used as a free swap.
+fn calculate_stable_deposit(
+    pool: &PoolState,
+    balance_a: u64,
+    balance_b: u64,
+    amount_a: u64,
+    amount_b: u64,
+    lp_supply: u64,
+) -> Option<u64> {
+    let x0 = normalize_amount(balance_a, pool.token_a_rate)?;
+    let y0 = normalize_amount(balance_b, pool.token_b_rate)?;
+    let x1 = x0.checked_add(normalize_amount(amount_a, pool.token_a_rate)?)?;
+    let y1 = y0.checked_add(normalize_amount(amount_b, pool.token_b_rate)?)?;
+
+    let d0 = stable_invariant(x0, y0, STABLE_AMP)?;
+    let d1 = stable_invariant(x1, y1, STABLE_AMP)?;
+    if d1 <= d0 {
+        return Some(0);
+    }
+
+    let imbalance_fee = |balance_after: U256, balance_before: U256| -> Option<U256> {
+        let ideal = d1.checked_mul(balance_before)? / d0;
+        let difference = if ideal > balance_after { ideal - balance_after } else { balance_after - ideal };
+        Some(difference.checked_mul(U256::from(pool.fee_rate))? / (U256::from(FEE_DENOMINATOR) * U256::from(2)))
+    };
+    let x2 = x1.checked_sub(imbalance_fee(x1, x0)?)?;
+    let y2 = y1.checked_sub(imbalance_fee(y1, y0)?)?;
+    let d2 = stable_invariant(x2, y2, STABLE_AMP)?;
+
+    let lp = U256::from(lp_supply).checked_mul(d2.saturating_sub(d0))? / d0;
+    u64::try_from(lp).ok()
+}
+
+// Marginal price of token A in token B on a stable pool's curve, as a
+// Q64.64. With u = x/D and v = y/D the slope of the invariant is
+// (16A u^2 v^2 + v) / (16A u^2 v^2 + u), in normalized units.
+fn stable_spot_price_x64(pool: &PoolState) -> Option<U256> {
+    let x = normalize_amount(pool.reserve_a, pool.token_a_rate)?;
+    let y = normalize_amount(pool.reserve_b, pool.token_b_rate)?;
+    let d = stable_invariant(x, y, STABLE_AMP)?;
+    let u = (x << 64).checked_div(d)?;
+    let v = (y << 64).checked_div(d)?;
+    let uv = (u * v) >> 64;
+    let term = ((uv * uv) >> 64).checked_mul(U256::from(16 * STABLE_AMP))?;
+    let price = (term.checked_add(v)? << 64).checked_div(term.checked_add(u)?)?;
+    // Back from normalized to raw units
+    mul_div(price, U256::from(pool.token_a_rate), U256::from(pool.token_b_rate), false)
+}
+
+// ========== CONCENTRATED LIQUIDITY MATH ==========
+
+// Once a concentrated pool has ranged positions it prices swaps off the
+// square root of its price (token B per token A) as a Q64.64 number. Ticks
+// are 0.01% price steps: the price at tick i is 1.0001^i.
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = 443636;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048017;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579062;
+
+// Position bounds must be multiples of this
+pub const TICK_SPACING: i32 = 10;
+
+const Q64: u128 = 1 << 64;
+
+// 1 / sqrt(1.0001^(2^i)) as Q128 numbers, for building any tick's price
+// out of the bits of its index
+const TICK_FACTORS: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e2139,
+    0xfff2e50f5f656932ef12357cf3c7fdcb,
+    0xffe5caca7e10e4e61c3624eaa0941ccf,
+    0xffcb9843d60f6159c9db58835c926643,
+    0xff973b41fa98c081472e6896dfb254bf,
+    0xff2ea16466c96a3843ec78b326b52860,
+    0xfe5dee046a99a2a811c461f1969c3052,
+    0xfcbe86c7900a88aedcffc83b479aa3a3,
+    0xf987a7253ac413176f2b074cf7815e53,
+    0xf3392b0822b70005940c7a398e4b70f2,
+    0xe7159475a2c29b7443b29c7fa6e889d8,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e4,
+    0x70d869a156d2a1b890bb3df62baf32f6,
+    0x31be135f97d08fd981231505542fcfa5,
+    0x9aa508b5b7a84e1c677de54f3e99bc8,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe97,
+];
+
+// Square root price at `tick`, rounded up. None outside MIN_TICK..=MAX_TICK.
+pub fn sqrt_price_at_tick(tick: i32) -> Option<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = U256::one() << 128;
+    for (bit, factor) in TICK_FACTORS.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    let round_up = if ratio.low_u64() == 0 { 0 } else { 1 };
+    Some(((ratio >> 64) + round_up).as_u128())
+}
+
+// Highest tick whose price is at or below `sqrt_price_x64`
+pub fn tick_at_sqrt_price(sqrt_price_x64: u128) -> i32 {
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid).is_some_and(|price| price <= sqrt_price_x64) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+// Square root of reserve_b / reserve_a, the price a pool's reserves imply,
+// kept inside the tick range
+fn sqrt_price_from_reserves(reserve_a: u64, reserve_b: u64) -> Option<u128> {
+    let ratio = (U256::from(reserve_b) << 128).checked_div(U256::from(reserve_a))?;
+    Some(ratio.integer_sqrt().as_u128().clamp(MIN_SQRT_PRICE_X64, MAX_SQRT_PRICE_X64))
+}
+
+// a * b / denominator with a 512-bit intermediate product. None if the
+// denominator is zero or the result doesn't fit in 256 bits.
+fn mul_div(a: U256, b: U256, denominator: U256, round_up: bool) -> Option<U256> {
+    let widen = |x: U256| {
+        let mut words = [0u64; 8];
+        words[..4].copy_from_slice(&x.0);
+        U512(words)
+    };
+    if denominator.is_zero() {
+        return None;
+    }
+    let (quotient, remainder) = (widen(a) * widen(b)).div_mod(widen(denominator));
+    let quotient = if round_up && !remainder.is_zero() { quotient + 1 } else { quotient };
+    if quotient.0[4..].iter().any(|word| *word != 0) {
+        return None;
+    }
+    Some(U256([quotient.0[0], quotient.0[1], quotient.0[2], quotient.0[3]]))
+}
+
+// Token A that `liquidity` holds between two prices: L * (1/lower - 1/upper)
+fn amount_a_delta(sqrt_price_a: u128, sqrt_price_b: u128, liquidity: u128, round_up: bool) -> Option<u64> {
+    let (lower, upper) = (sqrt_price_a.min(sqrt_price_b), sqrt_price_a.max(sqrt_price_b));
+    let amount = mul_div(
+        U256::from(liquidity) << 64,
+        U256::from(upper - lower),
+        U256::from(lower) * U256::from(upper),
+        round_up,
+    )?;
+    u64::try_from(amount).ok()
+}
+
+// Token B that `liquidity` holds between two prices: L * (upper - lower)
+fn amount_b_delta(sqrt_price_a: u128, sqrt_price_b: u128, liquidity: u128, round_up: bool) -> Option<u64> {
+    let (lower, upper) = (sqrt_price_a.min(sqrt_price_b), sqrt_price_a.max(sqrt_price_b));
+    let amount = mul_div(U256::from(liquidity), U256::from(upper - lower), U256::from(Q64), round_up)?;
+    u64::try_from(amount).ok()
+}
+
+// Input a swap needs to move the price between two points, rounded up
+fn input_delta(sqrt_price_a: u128, sqrt_price_b: u128, liquidity: u128, a_to_b: bool) -> Option<u64> {
+    if a_to_b {
+        amount_a_delta(sqrt_price_a, sqrt_price_b, liquidity, true)
+    } else {
+        amount_b_delta(sqrt_price_a, sqrt_price_b, liquidity, true)
+    }
+}
+
+// Output a swap gets for moving the price between two points, rounded down
+fn output_delta(sqrt_price_a: u128, sqrt_price_b: u128, liquidity: u128, a_to_b: bool) -> Option<u64> {
+    if a_to_b {
+        amount_b_delta(sqrt_price_a, sqrt_price_b, liquidity, false)
+    } else {
+        amount_a_delta(sqrt_price_a, sqrt_price_b, liquidity, false)
+    }
+}
+
+// Price after swapping `amount_in` into `liquidity`, rounded so the pool
+// never gives out more than the input paid for
+fn next_sqrt_price(sqrt_price_x64: u128, liquidity: u128, amount_in: u64, a_to_b: bool) -> Option<u128> {
+    if liquidity == 0 {
+        return None;
+    }
+    let next = if a_to_b {
+        // L * P / (L + amount * P), with L scaled up to Q64
+        let scaled_liquidity = U256::from(liquidity) << 64;
+        let denominator = scaled_liquidity + U256::from(amount_in) * U256::from(sqrt_price_x64);
+        mul_div(scaled_liquidity, U256::from(sqrt_price_x64), denominator, true)?
+    } else {
+        U256::from(sqrt_price_x64) + (U256::from(amount_in) << 64) / U256::from(liquidity)
+    };
+    u128::try_from(next).ok()
+}
+
+// Most liquidity the amounts can fund between two tick prices at the
+// current price
+fn liquidity_for_amounts(
+    sqrt_price_x64: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    amount_a: u64,
+    amount_b: u64,
+) -> Option<u128> {
+    let current = sqrt_price_x64.clamp(sqrt_price_lower, sqrt_price_upper);
+    let from_a = (current < sqrt_price_upper)
+        .then(|| {
+            mul_div(
+                U256::from(amount_a) * U256::from(current),
+                U256::from(sqrt_price_upper),
+                U256::from(sqrt_price_upper - current) << 64,
+                false,
+            )
+        })
+        .flatten();
+    let from_b = (current > sqrt_price_lower)
+        .then(|| mul_div(U256::from(amount_b), U256::from(Q64), U256::from(current - sqrt_price_lower), false))
+        .flatten();
+    let liquidity = match (from_a, from_b) {
+        (Some(a), Some(b)) => a.min(b),
+        (Some(a), None) => a,
+        (None, Some(b)) => b,
+        (None, None) => return None,
+    };
+    u128::try_from(liquidity).ok()
+}
+
+// Tokens `liquidity` holds between two tick prices at the current price:
+// all token A below the range, all token B above it
+fn position_amounts(
+    sqrt_price_x64: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Option<(u64, u64)> {
+    let current = sqrt_price_x64.clamp(sqrt_price_lower, sqrt_price_upper);
+    let amount_a = amount_a_delta(current, sqrt_price_upper, liquidity, round_up)?;
+    let amount_b = amount_b_delta(sqrt_price_lower, current, liquidity, round_up)?;
+    Some((amount_a, amount_b))
+}
+
+// Position bounds must sit on the tick spacing, inside the tick range, in order
+fn validate_tick_range(tick_lower: i32, tick_upper: i32) -> Result<()> {
+    require!(
+        tick_lower < tick_upper
+            && tick_lower >= MIN_TICK
+            && tick_upper <= MAX_TICK
+            && tick_lower % TICK_SPACING == 0
+            && tick_upper % TICK_SPACING == 0,
+        SwapError::InvalidTickRange
+    );
+    Ok(())
+}
+
+// What a swap through a concentrated pool's ticks does to the full-range
+// reserves and to the ranged positions' tokens
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConcentratedSwap {
+    pub amount_in: u64, // Less than asked for if the swap stopped at its price limit
+    pub amount_out: u64,
+    pub fee: u64,
+    pub creator_fee: u64,
+    pub referral_fee: u64,
+    pub base_in: u64,    // Input net of fees, LP fees and output of the
+    pub base_fee: u64,   // fungible reserves...
+    pub base_out: u64,
+    pub ranged_in: u64,  // ...and of the ranged positions
+    pub ranged_fee: u64,
+    pub ranged_out: u64,
+    pub sqrt_price_x64: u128, // Price, tick and active ranged liquidity after the swap
+    pub tick_current: i32,
+    pub liquidity: u128,
+    pub order_liquidity: u128,
+    pub fee_growth_global_x64: u128,     // Input token's fee growth after the swap...
+    pub crossed_ticks: Vec<(i32, u128)>, // ...and when the price crossed each tick
+}
+
+impl PoolState {
+    // Concentrated pools switch to tick pricing when their first ranged
+    // position opens
+    pub fn uses_ticks(&self) -> bool {
+        self.pool_type == 2 && self.sqrt_price_x64 != 0
+    }
+
+    // Sets the price ranged liquidity trades from, the price the reserves
+    // imply, unless it's already set
+    fn start_tick_pricing(&mut self) -> Result<()> {
+        if self.sqrt_price_x64 == 0 {
+            require!(self.reserve_a > 0 && self.reserve_b > 0, SwapError::InsufficientLiquidity);
+            self.sqrt_price_x64 =
+                sqrt_price_from_reserves(self.reserve_a, self.reserve_b).ok_or(SwapError::MathOverflow)?;
+            self.tick_current = tick_at_sqrt_price(self.sqrt_price_x64);
+        }
+        Ok(())
+    }
+
+    // Liquidity the fungible reserves provide over the full price range at
+    // the current price
+    fn base_liquidity(&self) -> Result<u128> {
+        let price = U256::from(self.sqrt_price_x64);
+        let from_a = (U256::from(self.reserve_a) * price) >> 64;
+        let from_b = (U256::from(self.reserve_b) << 64) / price;
+        u128::try_from(from_a.min(from_b)).map_err(|_| SwapError::MathOverflow.into())
+    }
+
+    // Prices a swap like the swap instruction would, walking the pool's
+    // ticks once it has ranged positions
+    pub fn quote_swap_with_ticks(&self, ticks: &PoolTicks, amount_in: u64, a_to_b: bool) -> Result<SwapQuote> {
+        if !self.uses_ticks() {
+            return self.quote_swap(amount_in, a_to_b);
+        }
+        let swap = self.swap_through_ticks(ticks, amount_in, a_to_b, 0, None)?;
+        Ok(SwapQuote { amount_out: swap.amount_out, fee: swap.fee, creator_fee: swap.creator_fee })
+    }
+
+    // Walks the price from tick to tick until the input is used up, or until
+    // the price reaches `sqrt_price_limit_x64`. A swap that stops at its limit
+    // only takes (and is only charged fees on) the input it used.
+    pub fn swap_through_ticks(
+        &self,
+        ticks: &PoolTicks,
+        amount_in: u64,
+        a_to_b: bool,
+        referral_fee_bps: u16,
+        sqrt_price_limit_x64: Option<u128>,
+    ) -> Result<ConcentratedSwap> {
+        if let Some(limit) = sqrt_price_limit_x64 {
+            let valid = if a_to_b {
+                limit < self.sqrt_price_x64 && limit >= MIN_SQRT_PRICE_X64
+            } else {
+                limit > self.sqrt_price_x64 && limit <= MAX_SQRT_PRICE_X64
+            };
+            require!(valid, SwapError::InvalidPriceLimit);
+        }
+        let mut amount_in = amount_in;
+        let (mut swap, mut unused_in) = self.walk_ticks(ticks, amount_in, a_to_b, referral_fee_bps, sqrt_price_limit_x64)?;
+
+        // Price the input that reached the limit again, grossed up for the
+        // fee and rounded down so it can't carry the price past the limit,
+        // until a walk uses all it's given. Each try takes less input.
+        while unused_in > 0 {
+            let used_in = amount_in - calculate_fee(amount_in, self.fee_rate) - unused_in;
+            let regrossed = u64::try_from(used_in as u128 * FEE_DENOMINATOR / (FEE_DENOMINATOR - self.fee_rate as u128))
+                .map_err(|_| SwapError::MathOverflow)?;
+            amount_in = regrossed.min(amount_in - 1);
+            require!(amount_in > 0, SwapError::InvalidPriceLimit);
+            (swap, unused_in) = self.walk_ticks(ticks, amount_in, a_to_b, referral_fee_bps, sqrt_price_limit_x64)?;
+        }
+        Ok(swap)
+    }
+
+    // Between initialized ticks the full-range reserves and the ranged
+    // positions in range trade along the same curve, each in proportion to its
+    // liquidity. The referrer's and creator's cuts of the fee are left out of
+    // the LPs' share. Also returns the input, net of fees, left over at the
+    // price limit.
+    fn walk_ticks(
+        &self,
+        ticks: &PoolTicks,
+        amount_in: u64,
+        a_to_b: bool,
+        referral_fee_bps: u16,
+        sqrt_price_limit_x64: Option<u128>,
+    ) -> Result<(ConcentratedSwap, u64)> {
+        let fee = calculate_fee(amount_in, self.fee_rate);
+        let creator_fee = self.creator_fee(fee);
+        let referral_fee = calculate_referral_fee(fee, referral_fee_bps);
+        let lp_fee = fee - creator_fee - referral_fee;
+        let net_in = amount_in - fee;
+        let base_liquidity = self.base_liquidity()?;
+
+        let mut swap = ConcentratedSwap {
+            amount_in,
+            fee,
+            creator_fee,
+            referral_fee,
+            sqrt_price_x64: self.sqrt_price_x64,
+            tick_current: self.tick_current,
+            liquidity: self.liquidity,
+            order_liquidity: self.order_liquidity,
+            fee_growth_global_x64: if a_to_b { self.fee_growth_global_a_x64 } else { self.fee_growth_global_b_x64 },
+            ..Default::default()
+        };
+        let mut remaining = net_in;
+        let mut fee_remaining = lp_fee;
+
+        while remaining > 0 && Some(swap.sqrt_price_x64) != sqrt_price_limit_x64 {
+            let next_tick = ticks.next_initialized(swap.tick_current, a_to_b).copied();
+            let tick_price = match next_tick {
+                Some(tick) => sqrt_price_at_tick(tick.index).ok_or(SwapError::MathOverflow)?,
+                None if a_to_b => MIN_SQRT_PRICE_X64,
+                None => MAX_SQRT_PRICE_X64,
+            };
+            let target = match sqrt_price_limit_x64 {
+                Some(limit) if a_to_b => tick_price.max(limit),
+                Some(limit) => tick_price.min(limit),
+                None => tick_price,
+            };
+            let liquidity = base_liquidity.checked_add(swap.liquidity).ok_or(SwapError::MathOverflow)?;
+
+            if liquidity > 0 {
+                let (step_in, next_price) = match input_delta(swap.sqrt_price_x64, target, liquidity, a_to_b) {
+                    Some(max_in) if max_in <= remaining => (max_in, target),
+                    _ => {
+                        let next = next_sqrt_price(swap.sqrt_price_x64, liquidity, remaining, a_to_b)
+                            .ok_or(SwapError::MathOverflow)?;
+                        // Rounding can't carry the price past the target
+                        let next = if a_to_b { next.max(target) } else { next.min(target) };
+                        (remaining, next)
+                    }
+                };
+
+                let ranged_in = input_delta(swap.sqrt_price_x64, next_price, swap.liquidity, a_to_b)
+                    .ok_or(SwapError::MathOverflow)?
+                    .min(step_in);
+                let ranged_out = output_delta(swap.sqrt_price_x64, next_price, swap.liquidity, a_to_b)
+                    .ok_or(SwapError::MathOverflow)?;
+                let base_out = output_delta(swap.sqrt_price_x64, next_price, base_liquidity, a_to_b)
+                    .ok_or(SwapError::MathOverflow)?;
+
+                // The LP fee is shared out by input, the last step taking the rounding
+                let step_fee = if step_in == remaining {
+                    fee_remaining
+                } else {
+                    (lp_fee as u128 * step_in as u128 / net_in as u128) as u64
+                };
+                // Limit orders earn no fees: their share goes to the reserves
+                let position_liquidity = swap.liquidity - swap.order_liquidity;
+                let position_in = input_delta(swap.sqrt_price_x64, next_price, position_liquidity, a_to_b)
+                    .ok_or(SwapError::MathOverflow)?
+                    .min(step_in);
+                let ranged_fee = (step_fee as u128 * position_in as u128)
+                    .checked_div(step_in as u128)
+                    .unwrap_or(0) as u64;
+
+                swap.ranged_in += ranged_in;
+                swap.base_in += step_in - ranged_in;
+                swap.ranged_fee += ranged_fee;
+                swap.base_fee += step_fee - ranged_fee;
+                if let Some(growth) = ((ranged_fee as u128) << 64).checked_div(position_liquidity) {
+                    swap.fee_growth_global_x64 = swap.fee_growth_global_x64.wrapping_add(growth);
+                }
+                swap.ranged_out += ranged_out;
+                swap.base_out += base_out;
+                remaining -= step_in;
+                fee_remaining -= step_fee;
+                swap.sqrt_price_x64 = next_price;
+            } else {
+                // Nothing to trade against until the next position's range
+                require!(next_tick.is_some(), SwapError::InsufficientLiquidity);
+                swap.sqrt_price_x64 = target;
+            }
+
+            if swap.sqrt_price_x64 == tick_price {
+                let Some(tick) = next_tick else {
+                    require!(remaining == 0, SwapError::InsufficientLiquidity);
+                    swap.tick_current = tick_at_sqrt_price(tick_price);
+                    break;
+                };
+                // Crossing a tick brings positions into range or takes them out
+                swap.crossed_ticks.push((tick.index, swap.fee_growth_global_x64));
+                let liquidity = if a_to_b {
+                    swap.tick_current = tick.index - 1;
+                    swap.liquidity.checked_add_signed(-tick.liquidity_net)
+                } else {
+                    swap.tick_current = tick.index;
+                    swap.liquidity.checked_add_signed(tick.liquidity_net)
+                };
+                swap.liquidity = liquidity.ok_or(SwapError::MathOverflow)?;
+                // Limit orders span one tick spacing from their tick
+                let (entering, leaving) = if a_to_b {
+                    (tick.index - TICK_SPACING, tick.index)
+                } else {
+                    (tick.index, tick.index - TICK_SPACING)
+                };
+                swap.order_liquidity = swap.order_liquidity
+                    .checked_sub(ticks.order_liquidity_at(leaving))
+                    .and_then(|liquidity| liquidity.checked_add(ticks.order_liquidity_at(entering)))
+                    .ok_or(SwapError::MathOverflow)?;
+            } else {
+                swap.tick_current = tick_at_sqrt_price(swap.sqrt_price_x64);
+            }
+        }
+
+        // Hand back the leftover fee rounding so every token is accounted for.
+        // Ranged fees below what the fee growth can credit stay with the
+        // positions' fees, unclaimed.
+        swap.base_fee += fee_remaining;
+        swap.amount_out = swap.base_out + swap.ranged_out;
+        let (reserve_out, position_reserve_out) = if a_to_b {
+            (self.reserve_b, self.position_reserve_b)
+        } else {
+            (self.reserve_a, self.position_reserve_a)
+        };
+        require!(
+            swap.base_out <= reserve_out && swap.ranged_out <= position_reserve_out,
+            SwapError::InsufficientLiquidity
+        );
+        Ok((swap, remaining))
+    }
+
+    // Books a swap computed by swap_through_ticks. The ticks it crossed are
+    // the caller's to update, see PoolTicks::cross.
+    fn apply_concentrated_swap(&mut self, a_to_b: bool, swap: &ConcentratedSwap) -> Result<()> {
+        let (reserve_in, reserve_out) = self.reserves(a_to_b);
+        let reserve_in = reserve_in
+            .checked_add(swap.base_in + swap.base_fee)
+            .ok_or(SwapError::MathOverflow)?;
+        self.set_reserves(a_to_b, reserve_in, reserve_out - swap.base_out);
+
+        let (position_in, position_out, fees_in, fee_growth) = if a_to_b {
+            (
+                &mut self.position_reserve_a,
+                &mut self.position_reserve_b,
+                &mut self.position_fees_a,
+                &mut self.fee_growth_global_a_x64,
+            )
+        } else {
+            (
+                &mut self.position_reserve_b,
+                &mut self.position_reserve_a,
+                &mut self.position_fees_b,
+                &mut self.fee_growth_global_b_x64,
+            )
+        };
+        *position_in = position_in.checked_add(swap.ranged_in).ok_or(SwapError::MathOverflow)?;
+        *position_out -= swap.ranged_out;
+        // Ranged fees wait for collect_fees instead of compounding
+        *fees_in = fees_in.checked_add(swap.ranged_fee).ok_or(SwapError::MathOverflow)?;
+        *fee_growth = swap.fee_growth_global_x64;
+
+        self.accrue_creator_fee(a_to_b, swap.creator_fee)?;
+        self.sqrt_price_x64 = swap.sqrt_price_x64;
+        self.tick_current = swap.tick_current;
+        self.liquidity = swap.liquidity;
+        self.order_liquidity = swap.order_liquidity;
+        Ok(())
+    }
+}
+
+// ========== FARM REWARDS ==========
+
+// Farms track rewards per staked LP token since the start (scaled by this),
+// so each stake's earnings are its size times the growth since it last
+// settled
+pub const REWARD_PER_SHARE_SCALE: u128 = 1_000_000_000_000;
+
+impl Farm {
+    // Emissions scheduled in [from, to), clipped to the farm's run
+    fn emissions_between(&self, from: i64, to: i64) -> Option<u64> {
+        let from = from.max(self.start_time);
+        let to = to.min(self.end_time);
+        if to <= from {
+            return Some(0);
+        }
+        self.emission_rate.checked_mul((to - from) as u64)
+    }
+
+    // Credits emissions up to `now` to current stakers. Emissions while
+    // nothing is staked, and what reward_per_share rounds off, go to no one;
+    // they stay in the reward vault for reclaim_farm_rewards. The amount
+    // credited rounds up, so stakers can never claim more than it.
+    fn update(&mut self, now: i64) -> Result<()> {
+        if now <= self.last_update_time {
+            return Ok(());
+        }
+        if self.total_staked > 0 {
+            let rewards = self.emissions_between(self.last_update_time, now).ok_or(SwapError::MathOverflow)?;
+            let growth = rewards as u128 * REWARD_PER_SHARE_SCALE / self.total_staked as u128;
+            let credited = (growth * self.total_staked as u128).div_ceil(REWARD_PER_SHARE_SCALE) as u64;
+            self.reward_per_share = self.reward_per_share.checked_add(growth).ok_or(SwapError::MathOverflow)?;
+            self.rewards_emitted = self.rewards_emitted.checked_add(credited).ok_or(SwapError::MathOverflow)?;
+        }
+        self.last_update_time = now;
+        Ok(())
+    }
+
+    // Rewards the vault must still pay: credited but unclaimed, plus
+    // everything scheduled after `now`
+    fn obligations(&self, now: i64) -> Option<u64> {
+        let unclaimed = self.rewards_emitted.checked_sub(self.rewards_claimed)?;
+        unclaimed.checked_add(self.emissions_between(now, self.end_time)?)
+    }
+}
+
+impl StakePosition {
+    // Rewards a stake of this size has earned at `reward_per_share`
+    fn accrued(&self, reward_per_share: u128) -> Result<u128> {
+        let accrued = U256::from(self.amount) * U256::from(reward_per_share) / U256::from(REWARD_PER_SHARE_SCALE);
+        require!(accrued <= U256::from(u128::MAX), SwapError::MathOverflow);
+        Ok(accrued.as_u128())
+    }
+
+    // Moves what the stake earned since it last settled into pending_rewards
+    fn settle(&mut self, reward_per_share: u128) -> Result<()> {
+        let earned = self.accrued(reward_per_share)?.saturating_sub(self.reward_debt);
+        let earned = u64::try_from(earned).map_err(|_| SwapError::MathOverflow)?;
+        self.pending_rewards = self.pending_rewards.checked_add(earned).ok_or(SwapError::MathOverflow)?;
+        self.reward_debt = self.accrued(reward_per_share)?;
+        Ok(())
+    }
+
+    // Marks everything up to `reward_per_share` as paid, after the stake changed size
+    fn reset_reward_debt(&mut self, reward_per_share: u128) -> Result<()> {
+        self.reward_debt = self.accrued(reward_per_share)?;
+        Ok(())
+    }
+}
+
+// ========== EVENTS ==========
+
+// Emitted through `sol_log_data` so indexers can rebuild pool history from
+// transaction logs. Reserves are the pool's values after the instruction.
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct PoolInitialized {
+    pub pool: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp_mint: Pubkey,
+    pub pool_type: u8,
+    pub fee_rate: u32,
+    pub creator: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_amount: u64, // LP tokens minted
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_amount: u64, // LP tokens burned
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct SwapExecuted {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64, // For flash swaps, what was actually repaid
+    pub amount_out: u64,
+    pub fee: u64,
+    pub creator_fee: u64,
+    pub referrer: Option<Pubkey>, // Token account paid referral_fee, if any
+    pub referral_fee: u64,
+    pub flash: bool,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct ReservesSynced {
+    pub pool: Pubkey,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+// Logged by the swap it rejects, so it only shows up in failed transactions
+#[event]
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerTripped {
+    pub pool: Pubkey,
+    pub slot: u64,
+    pub slot_price_x64: u128, // Spot price before the slot's first swap...
+    pub price_x64: u128,      // ...and where the rejected swap would have left it
+    pub circuit_breaker_bps: u16,
+}
+
+#[event]
+#[derive(Clone, Debug)]
+pub struct LimitOrdersFilled {
+    pub pool: Pubkey,
+    pub tick: i32,      // The orders spanned tick to tick + TICK_SPACING
+    pub sells_a: bool,
+    pub epoch: u64,     // Batch that filled; its orders can now be claimed
+    pub liquidity: u128,
+    pub amount_out: u64, // What the batch received, shared by liquidity
+}
+
+// ========== ACCOUNTS & STATE ==========
+
+// Mint settings that let a third party interfere with pooled tokens. Only
+// SPL Token mints can be pooled for now (the vaults are SPL Token
+// accounts), so a freeze authority is the only one a pool can have. The
+// Token-2022 extensions with the same risk have their bits assigned
+// already: should Token-2022 mints be accepted, pools record them in the
+// same token_*_flags and configs reject them through the same
+// rejected_mint_flags, with no layout change or migration. Configs may set
+// those bits today so the rejection applies from the first such mint.
+pub const MINT_FLAG_FREEZE_AUTHORITY: u8 = 1 << 0;
+pub const MINT_FLAG_PERMANENT_DELEGATE: u8 = 1 << 1; // Token-2022 permanent delegate
+pub const MINT_FLAG_TRANSFER_HOOK: u8 = 1 << 2;      // Token-2022 transfer hook
+pub const MINT_FLAGS_KNOWN: u8 = MINT_FLAG_FREEZE_AUTHORITY | MINT_FLAG_PERMANENT_DELEGATE | MINT_FLAG_TRANSFER_HOOK;
+
+fn mint_risk_flags(mint: &Mint) -> u8 {
+    let mut flags = 0;
+    if mint.freeze_authority.is_some() {
+        flags |= MINT_FLAG_FREEZE_AUTHORITY;
+    }
+    flags
+}
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// Pool creators can be given at most half of a pool's swap fees
+pub const MAX_CREATOR_FEE_SHARE: u16 = 5_000;
+
+// Nor can a referrer take more than half of a swap's fee
+pub const MAX_REFERRAL_FEE_BPS: u16 = 5_000;
+
+#[account]
+#[derive(InitSpace)]
+pub struct AmmConfig {
+    pub admin: Pubkey,
+    pub rejected_mint_flags: u8, // MINT_FLAG_* bits that block pool creation; others are only recorded
+    pub bump: u8,
+    pub permissionless_pool_creation: bool, // If false, creators need a PoolCreator entry
+    pub pool_creation_fee: u64,  // Lamports paid to the treasury per pool
+    pub treasury: Pubkey,
+    pub creator_fee_share: u16,  // Basis points of swap fees new pools pay their creator
+    pub max_referral_fee_bps: u16, // Largest share of a swap's fee a referrer can be paid
+    pub circuit_breaker_bps: u16, // Most a pool's price may move within one slot; 0 turns it off
+    pub reserved: [u8; 81],      // Space for future settings
+}
+
+impl AmmConfig {
+    fn apply(&mut self, params: &ConfigParams) -> Result<()> {
+        require!(params.creator_fee_share <= MAX_CREATOR_FEE_SHARE, SwapError::InvalidConfig);
+        require!(params.max_referral_fee_bps <= MAX_REFERRAL_FEE_BPS, SwapError::InvalidConfig);
+        // Unassigned bits are kept free for later flags
+        require!(params.rejected_mint_flags & !MINT_FLAGS_KNOWN == 0, SwapError::InvalidConfig);
+        self.rejected_mint_flags = params.rejected_mint_flags;
+        self.permissionless_pool_creation = params.permissionless_pool_creation;
+        self.pool_creation_fee = params.pool_creation_fee;
+        self.treasury = params.treasury;
+        self.creator_fee_share = params.creator_fee_share;
+        self.max_referral_fee_bps = params.max_referral_fee_bps;
+        self.circuit_breaker_bps = params.circuit_breaker_bps;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct ConfigParams {
+    pub rejected_mint_flags: u8,
+    pub permissionless_pool_creation: bool,
+    pub pool_creation_fee: u64,
+    pub treasury: Pubkey,
+    pub creator_fee_share: u16,
+    pub max_referral_fee_bps: u16,
+    pub circuit_breaker_bps: u16,
+}
+
+// Allowlist entry for permissioned pool creation
+#[account]
+#[derive(InitSpace)]
+pub struct PoolCreator {
+    pub creator: Pubkey,
+    pub bump: u8,
+}
+
+// LP tokens track the more precise of the two pool tokens, capped so LP
+// amounts for high-precision mints still fit comfortably in a u64
+pub const MAX_LP_DECIMALS: u8 = 9;
+
+fn lp_mint_decimals(token_a_decimals: u8, token_b_decimals: u8) -> u8 {
+    std::cmp::min(std::cmp::max(token_a_decimals, token_b_decimals), MAX_LP_DECIMALS)
+}
+
+// Rate multipliers that scale both tokens to the larger of their decimals
+fn token_rates(token_a_decimals: u8, token_b_decimals: u8) -> Result<(u64, u64)> {
+    let common_decimals = std::cmp::max(token_a_decimals, token_b_decimals);
+    let rate_a = decimals_rate(token_a_decimals, common_decimals).ok_or(SwapError::UnsupportedMintDecimals)?;
+    let rate_b = decimals_rate(token_b_decimals, common_decimals).ok_or(SwapError::UnsupportedMintDecimals)?;
+    Ok((rate_a, rate_b))
+}
+
+// Current PoolState layout. Bump it whenever a field is added and take the
+// field's bytes out of `reserved`. A field whose default is zero needs
+// nothing more: pools written before it read it back as zero. A field with
+// any other default also raises MIN_POOL_STATE_VERSION to the new version
+// and gives migrate_pool a step that fills it in.
+pub const POOL_STATE_VERSION: u8 = 10;
+
+// Oldest layout instructions accept as it is. Versions 6 to 10 only added
+// fields that start at zero.
+pub const MIN_POOL_STATE_VERSION: u8 = 5;
+
+// Size of pool accounts created before PoolState was versioned:
+// discriminator + token_a + token_b + lp_mint + fee_rate + pool_type + bump
+const LEGACY_POOL_STATE_LEN: usize = 8 + 32 + 32 + 32 + 4 + 1 + 1;
+
+#[account]
+#[derive(InitSpace)]
+pub struct PoolState {
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp_mint: Pubkey,    // Added LP mint address
+    pub fee_rate: u32,      // Fee rate in basis points (300 = 0.3%)
+    pub pool_type: u8,      // 0=standard, 1=stable, 2=concentrated
+    pub bump: u8,
+    pub version: u8,        // Layout version, see POOL_STATE_VERSION
+    pub token_a_decimals: u8,
+    pub token_b_decimals: u8,
+    pub token_a_rate: u64,  // Multipliers that bring both tokens to the same decimals,
+    pub token_b_rate: u64,  // e.g. 1000 for a 6-decimal token paired with a 9-decimal one
+    pub reserve_a: u64,     // Pool's own record of its token balances; vault tokens
+    pub reserve_b: u64,     // beyond these are donations that sync or skim can settle
+    pub token_a_flags: u8,  // MINT_FLAG_* bits describing risky mint settings
+    pub token_b_flags: u8,
+    pub creator: Pubkey,        // Payer of initialize_pool
+    pub creator_fee_share: u16, // Creator's cut of swap fees in basis points, fixed at creation
+    pub creator_fees_a: u64,    // Creator fees held in the vaults until collected;
+    pub creator_fees_b: u64,    // not part of the reserves
+    pub sqrt_price_x64: u128,   // Concentrated pools with ranged positions: price as a Q64.64
+    pub tick_current: i32,      // square root, its tick, and the liquidity of the
+    pub liquidity: u128,        // positions in range. Zero until the first position opens.
+    pub position_reserve_a: u64, // Tokens held for ranged positions; not part of
+    pub position_reserve_b: u64, // the reserves
+    pub fee_growth_global_a_x64: u128, // Ranged positions' fees per unit of liquidity
+    pub fee_growth_global_b_x64: u128, // ever earned, as Q64.64. Only differences matter.
+    pub position_fees_a: u64,    // Ranged positions' fees held until collected;
+    pub position_fees_b: u64,    // not part of the reserves
+    pub order_liquidity: u128,   // Part of `liquidity` that is limit orders, which earn no fees
+    pub breaker_slot: u64,       // Slot of the first swap the circuit breaker saw, and the
+    pub breaker_price_x64: u128, // spot price before it; see AmmConfig::circuit_breaker_bps
+    pub reserved: [u8; 30], // Zeroed space for future fields, so adding one needs no realloc
+}
+
+// What a swap would pay out at a pool's current reserves
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_out: u64,
+    pub fee: u64,         // Part of amount_in charged as the swap fee
+    pub creator_fee: u64, // Part of the fee set aside for the pool creator
+}
+
+impl PoolState {
+    // Prices a swap of `amount_in` exactly as the swap instruction would
+    pub fn quote_swap(&self, amount_in: u64, a_to_b: bool) -> Result<SwapQuote> {
+        // Ranged positions move the price too, see quote_swap_with_ticks
+        require!(!self.uses_ticks(), SwapError::MissingPoolTicks);
+        let (input_balance, output_balance) = self.reserves(a_to_b);
+        require!(input_balance > 0 && output_balance > 0, SwapError::InsufficientLiquidity);
+
+        let fee = calculate_fee(amount_in, self.fee_rate);
+        let amount_out = calculate_swap_output(self, a_to_b, input_balance, output_balance, amount_in - fee)
+            .ok_or(SwapError::MathOverflow)?;
+        require!(amount_out <= output_balance, SwapError::InsufficientLiquidity);
+
+        Ok(SwapQuote { amount_out, fee, creator_fee: self.creator_fee(fee) })
+    }
+
+    // Marginal price of token A in token B, in raw token units, as a Q64.64.
+    // None for an empty pool.
+    pub fn spot_price_x64(&self) -> Option<U256> {
+        if self.uses_ticks() {
+            let sqrt_price = U256::from(self.sqrt_price_x64);
+            return Some((sqrt_price * sqrt_price) >> 64);
+        }
+        if self.reserve_a == 0 || self.reserve_b == 0 {
+            return None;
+        }
+        match self.pool_type {
+            1 => stable_spot_price_x64(self),
+            _ => Some((U256::from(self.reserve_b) << 64) / U256::from(self.reserve_a)),
+        }
+    }
+
+    // Remembers the spot price at the pool's first swap in `slot`, which the
+    // circuit breaker measures every swap in that slot against
+    fn note_slot_price(&mut self, slot: u64) {
+        if self.breaker_slot != slot || self.breaker_price_x64 == 0 {
+            if let Some(price) = self.spot_price_x64() {
+                self.breaker_slot = slot;
+                self.breaker_price_x64 = u128::try_from(price).unwrap_or(u128::MAX);
+            }
+        }
+    }
+
+    // (input, output) reserves for a swap in the given direction
+    pub fn reserves(&self, a_to_b: bool) -> (u64, u64) {
+        if a_to_b {
+            (self.reserve_a, self.reserve_b)
+        } else {
+            (self.reserve_b, self.reserve_a)
+        }
+    }
+
+    fn creator_fee(&self, fee_amount: u64) -> u64 {
+        (fee_amount as u128 * self.creator_fee_share as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    fn accrue_creator_fee(&mut self, a_to_b: bool, amount: u64) -> Result<()> {
+        let owed = if a_to_b { &mut self.creator_fees_a } else { &mut self.creator_fees_b };
+        *owed = owed.checked_add(amount).ok_or(SwapError::MathOverflow)?;
+        Ok(())
+    }
+
+    fn set_reserves(&mut self, a_to_b: bool, input_reserve: u64, output_reserve: u64) {
+        if a_to_b {
+            self.reserve_a = input_reserve;
+            self.reserve_b = output_reserve;
+        } else {
+            self.reserve_b = input_reserve;
+            self.reserve_a = output_reserve;
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AmmConfig::INIT_SPACE,
+        seeds = [b"amm_config"],
+        bump
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    // Must be the program's upgrade authority
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ SwapError::Unauthorized)]
+    pub program: Program<'info, crate::program::SolanaAmmEducationalTemplate>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ SwapError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"amm_config"],
+        bump = amm_config.bump,
+        has_one = admin @ SwapError::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(creator: Pubkey)]
+pub struct AddPoolCreator<'info> {
+    #[account(
+        seeds = [b"amm_config"],
+        bump = amm_config.bump,
+        has_one = admin @ SwapError::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PoolCreator::INIT_SPACE,
+        seeds = [b"pool_creator", creator.as_ref()],
+        bump
+    )]
+    pub pool_creator: Account<'info, PoolCreator>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemovePoolCreator<'info> {
+    #[account(
+        seeds = [b"amm_config"],
+        bump = amm_config.bump,
+        has_one = admin @ SwapError::Unauthorized
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"pool_creator", pool_creator.creator.as_ref()],
+        bump = pool_creator.bump
+    )]
+    pub pool_creator: Account<'info, PoolCreator>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    // Required while pool creation is permissioned
+    #[account(seeds = [b"pool_creator", payer.key().as_ref()], bump = pool_creator.bump)]
+    pub pool_creator: Option<Account<'info, PoolCreator>>,
+
+    /// CHECK: Receives the pool creation fee; must be the configured treasury
+    #[account(mut, address = amm_config.treasury @ SwapError::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PoolState::INIT_SPACE,
+        seeds = [b"pool", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub pool_state: Account<'info, PoolState>,
+
+    pub token_a_mint: Account<'info, Mint>,
+    pub token_b_mint: Account<'info, Mint>,
+
+    // LP token mint - owned and controlled by the pool authority. A closed
+    // pool leaves it behind, so re-creating the pair takes it over
+    #[account(
+        init_if_needed,
+        payer = payer,
+        mint::decimals = lp_mint_decimals(token_a_mint.decimals, token_b_mint.decimals),
+        mint::authority = pool_authority,
+        seeds = [b"lp_mint", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub lp_mint: Account<'info, Mint>,
+
+    // FIXED: Vault A as PDA - no signer required
+    #[account(
+        init,
+        payer = payer,
+        token::mint = token_a_mint,
+        token::authority = pool_authority,
+        seeds = [b"vault_a", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub token_a_vault: Account<'info, TokenAccount>,
+    
+    // FIXED: Vault B as PDA - no signer required
+    #[account(
+        init,
+        payer = payer,
+        token::mint = token_b_mint,
+        token::authority = pool_authority,
+        seeds = [b"vault_b", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub token_b_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA used as authority for token vaults and LP mint
+    #[account(
+        seeds = [b"pool_authority", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// CHECK: May still hold an older PoolState layout that Account<PoolState>
+    /// can't deserialize; migrate_pool checks the discriminator itself
+    #[account(mut, owner = crate::ID)]
+    pub pool_state: UncheckedAccount<'info>,
+
+    // Read to fill in the decimals stored since version 2 and mint flags since version 5
+    pub token_a_mint: Account<'info, Mint>,
+    pub token_b_mint: Account<'info, Mint>,
+
+    // Read to seed the reserves tracked since version 4
+    #[account(
+        seeds = [b"vault_a", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub token_a_vault: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"vault_b", token_a_mint.key().as_ref(), token_b_mint.key().as_ref()],
+        bump
+    )]
+    pub token_b_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump, has_one = admin @ SwapError::Unauthorized)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
+    )]
+    pub pool_state: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"lp_mint", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub lp_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA authority for vaults
+    #[account(
+        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    // Only exists once the pool has had a ranged position or limit order
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [b"ticks", pool_state.key().as_ref()],
+        bump = pool_ticks.bump
+    )]
+    pub pool_ticks: Option<Account<'info, PoolTicks>>,
+
+    /// CHECK: The pool's farm address; close_pool checks nothing lives there
+    #[account(seeds = [b"farm", pool_state.key().as_ref()], bump)]
+    pub farm: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+
+    /// CHECK: Only receives the closed accounts' rent
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
+    )]
+    pub pool_state: Account<'info, PoolState>,
+
+    // Either one may be left out to use native SOL for that side instead
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_a: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_b: Option<Account<'info, TokenAccount>>,
+
+    // User's LP token account - where minted LP tokens will be sent
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == pool_state.lp_mint @ SwapError::InvalidTokenMint
+    )]
+    pub user_lp_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = pool_token_a_vault.mint == pool_state.token_a @ SwapError::InvalidTokenMint,
+        constraint = pool_token_a_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = pool_token_b_vault.mint == pool_state.token_b @ SwapError::InvalidTokenMint,
+        constraint = pool_token_b_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = lp_mint.key() == pool_state.lp_mint @ SwapError::InvalidTokenMint
+    )]
+    pub lp_mint: Account<'info, Mint>,
+
+    /// CHECK: PDA authority for vaults and LP mint
+    #[account(
+        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    // Writable so it can pay and be paid native SOL
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    // Only needed to use native SOL. The wSOL account is created and closed
+    // within the instruction.
+    /// CHECK: The user's temporary wSOL account, created by the program
+    #[account(mut, seeds = [b"wsol", user_authority.key().as_ref()], bump)]
+    pub wsol_account: Option<UncheckedAccount<'info>>,
+    #[account(address = token::spl_token::native_mint::ID @ SwapError::InvalidNativeSol)]
+    pub native_mint: Option<Account<'info, Mint>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+impl<'info> AddLiquidity<'info> {
+    fn native_sol(&self, wsol_bump: Option<u8>) -> NativeSol<'_, 'info> {
+        NativeSol {
+            wsol_account: self.wsol_account.as_ref(),
+            wsol_bump,
+            native_mint: self.native_mint.as_ref(),
+            system_program: self.system_program.as_ref(),
+            user: &self.user_authority,
+            token_program: &self.token_program,
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
+    )]
+    pub pool_state: Account<'info, PoolState>,
+
+    // Either one may be left out to use native SOL for that side instead
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_a: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_b: Option<Account<'info, TokenAccount>>,
+
+    // User's LP token account - LP tokens will be burned from here
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == pool_state.lp_mint @ SwapError::InvalidTokenMint
+    )]
+    pub user_lp_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = pool_token_a_vault.mint == pool_state.token_a @ SwapError::InvalidTokenMint,
+        constraint = pool_token_a_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = pool_token_b_vault.mint == pool_state.token_b @ SwapError::InvalidTokenMint,
+        constraint = pool_token_b_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = lp_mint.key() == pool_state.lp_mint @ SwapError::InvalidTokenMint
+    )]
+    pub lp_mint: Account<'info, Mint>,
+
+    /// CHECK: PDA authority for vaults
+    #[account(
+        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    // Writable so it can pay and be paid native SOL
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    // Only needed to use native SOL. The wSOL account is created and closed
+    // within the instruction.
+    /// CHECK: The user's temporary wSOL account, created by the program
+    #[account(mut, seeds = [b"wsol", user_authority.key().as_ref()], bump)]
+    pub wsol_account: Option<UncheckedAccount<'info>>,
+    #[account(address = token::spl_token::native_mint::ID @ SwapError::InvalidNativeSol)]
+    pub native_mint: Option<Account<'info, Mint>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+impl<'info> RemoveLiquidity<'info> {
+    fn native_sol(&self, wsol_bump: Option<u8>) -> NativeSol<'_, 'info> {
+        NativeSol {
+            wsol_account: self.wsol_account.as_ref(),
+            wsol_bump,
+            native_mint: self.native_mint.as_ref(),
+            system_program: self.system_program.as_ref(),
+            user: &self.user_authority,
+            token_program: &self.token_program,
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
+    )]
+    pub pool_state: Account<'info, PoolState>,
+
+    // Either one may be left out to use native SOL for that side instead
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_a: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_b: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = pool_token_a_vault.mint == pool_state.token_a @ SwapError::InvalidTokenMint,
+        constraint = pool_token_a_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = pool_token_b_vault.mint == pool_state.token_b @ SwapError::InvalidTokenMint,
+        constraint = pool_token_b_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA authority for vaults
+    #[account(
+        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    // Writable so it can pay and be paid native SOL
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: The pool's PoolTicks, only used once a concentrated pool has
+    /// ranged positions; until then it may not exist
+    #[account(mut, seeds = [b"ticks", pool_state.key().as_ref()], bump)]
+    pub pool_ticks: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    // Paid the referral fee, in the input token
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+    // Only needed to use native SOL. The wSOL account is created and closed
+    // within the instruction.
+    /// CHECK: The user's temporary wSOL account, created by the program
+    #[account(mut, seeds = [b"wsol", user_authority.key().as_ref()], bump)]
+    pub wsol_account: Option<UncheckedAccount<'info>>,
+    #[account(address = token::spl_token::native_mint::ID @ SwapError::InvalidNativeSol)]
+    pub native_mint: Option<Account<'info, Mint>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+// One swap of a batch_swap, on the pool at `pool_index` among the batch's
+// pools
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct SwapLeg {
+    pub pool_index: u8,
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+    pub a_to_b: bool,
+}
+
+// Accounts each batch_swap pool takes in remaining_accounts, in Swap's
+// order: pool_state, user_token_a, user_token_b, pool_token_a_vault,
+// pool_token_b_vault, pool_authority and pool_ticks
+pub const BATCH_SWAP_POOL_ACCOUNTS: usize = 7;
+
+#[derive(Accounts)]
+pub struct BatchSwap<'info> {
+    #[account(mut)]
+    pub user_authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    // Each leg is a swap this program calls on itself
+    pub amm_program: Program<'info, crate::program::SolanaAmmEducationalTemplate>,
+}
+
+impl<'info> Swap<'info> {
+    fn native_sol(&self, wsol_bump: Option<u8>) -> NativeSol<'_, 'info> {
+        NativeSol {
+            wsol_account: self.wsol_account.as_ref(),
+            wsol_bump,
+            native_mint: self.native_mint.as_ref(),
+            system_program: self.system_program.as_ref(),
+            user: &self.user_authority,
+            token_program: &self.token_program,
+        }
+    }
+
+    // (input, output) vault balances for a swap in the given direction
+    fn vault_balances(&self, a_to_b: bool) -> (u64, u64) {
+        if a_to_b {
+            (self.pool_token_a_vault.amount, self.pool_token_b_vault.amount)
+        } else {
+            (self.pool_token_b_vault.amount, self.pool_token_a_vault.amount)
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct CollectCreatorFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        has_one = creator @ SwapError::Unauthorized,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
+    )]
+    pub pool_state: Account<'info, PoolState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = creator_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+    )]
+    pub creator_token_a: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = creator_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+    )]
+    pub creator_token_b: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA authority for vaults
+    #[account(
+        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub creator: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SyncReserves<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
+    )]
+    pub pool_state: Account<'info, PoolState>,
+
+    #[account(
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct Skim<'info> {
+    #[account(
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
+    )]
+    pub pool_state: Account<'info, PoolState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+    )]
+    pub recipient_token_a: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+    )]
+    pub recipient_token_b: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA authority for vaults
+    #[account(
+        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// Most initialized ticks one concentrated pool can have, which bounds the
+// pool's tick account and the work a swap can do crossing them
+pub const MAX_POOL_TICKS: usize = 64;
+
+// Least liquidity a position or limit order can hold while open. Every
+// initialized tick has at least this much behind it, so taking up all
+// MAX_POOL_TICKS ticks ties up real capital rather than dust.
+pub const MIN_TICK_LIQUIDITY: u128 = 1_000_000_000;
+
+// A price where ranged positions start or stop
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct Tick {
+    pub index: i32,
+    pub liquidity_net: i128,   // Change in active liquidity when the price crosses upwards
+    pub liquidity_gross: u128, // Liquidity of the positions bounded here; the tick goes when it's zero
+    pub fee_growth_outside_a_x64: u128, // Fee growth on the far side of the tick from the
+    pub fee_growth_outside_b_x64: u128, // current price, flipped each time the price crosses
+    pub order_liquidity: u128, // Unfilled limit orders from here to the next tick spacing,
+    pub order_epoch: u64,      // which all sell the same token and fill together
+    pub order_sells_a: bool,
+}
+
+// A concentrated pool's initialized ticks, sorted by index
+#[account]
+#[derive(InitSpace)]
+pub struct PoolTicks {
+    pub pool: Pubkey,
+    pub bump: u8,
+    pub order_epochs: u64, // Limit order batches so far; each gets the next number
+    #[max_len(MAX_POOL_TICKS)]
+    pub ticks: Vec<Tick>,
+}
+
+impl PoolTicks {
+    // Next initialized tick the price reaches from `tick`: the highest at or
+    // below it moving down, the lowest above it moving up
+    pub fn next_initialized(&self, tick: i32, down: bool) -> Option<&Tick> {
+        if down {
+            self.ticks.iter().rev().find(|t| t.index <= tick)
+        } else {
+            self.ticks.iter().find(|t| t.index > tick)
+        }
+    }
+
+    fn find(&self, index: i32) -> Option<&Tick> {
+        self.ticks.binary_search_by_key(&index, |tick| tick.index).ok().map(|position| &self.ticks[position])
+    }
+
+    // Fee growth per unit of liquidity between two initialized ticks, for
+    // token A and token B
+    pub fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32, pool: &PoolState) -> Option<(u128, u128)> {
+        let (lower, upper) = (self.find(tick_lower)?, self.find(tick_upper)?);
+        let inside = |global: u128, lower_outside: u128, upper_outside: u128| {
+            let below = if pool.tick_current >= tick_lower { lower_outside } else { global.wrapping_sub(lower_outside) };
+            let above = if pool.tick_current < tick_upper { upper_outside } else { global.wrapping_sub(upper_outside) };
+            global.wrapping_sub(below).wrapping_sub(above)
+        };
+        Some((
+            inside(pool.fee_growth_global_a_x64, lower.fee_growth_outside_a_x64, upper.fee_growth_outside_a_x64),
+            inside(pool.fee_growth_global_b_x64, lower.fee_growth_outside_b_x64, upper.fee_growth_outside_b_x64),
+        ))
+    }
+
+    // Unfilled limit order liquidity starting at `index`
+    pub fn order_liquidity_at(&self, index: i32) -> u128 {
+        self.find(index).map_or(0, |tick| tick.order_liquidity)
+    }
+
+    // Whether the batch an order joined has filled. A filled batch leaves
+    // its tick, so a tick that's gone or has moved on to a newer batch
+    // means it filled.
+    pub fn order_filled(&self, tick: i32, epoch: u64) -> bool {
+        self.find(tick).is_none_or(|tick| tick.order_epoch != epoch)
+    }
+
+    // Adds a limit order to the batch at `tick`, returning the batch's epoch
+    fn place_order(&mut self, tick: i32, sells_a: bool, liquidity: u128, pool: &PoolState) -> Result<u64> {
+        let liquidity_delta = i128::try_from(liquidity).map_err(|_| SwapError::MathOverflow)?;
+        self.update_range(tick, tick + TICK_SPACING, liquidity_delta, pool)?;
+        let next_epoch = self.order_epochs + 1;
+        let position = self.ticks.binary_search_by_key(&tick, |t| t.index).map_err(|_| SwapError::InvalidTickRange)?;
+        let order_tick = &mut self.ticks[position];
+        let new_batch = order_tick.order_liquidity == 0;
+        if new_batch {
+            order_tick.order_epoch = next_epoch;
+            order_tick.order_sells_a = sells_a;
+        }
+        require!(order_tick.order_sells_a == sells_a, SwapError::InvalidOrderPrice);
+        order_tick.order_liquidity = order_tick.order_liquidity.checked_add(liquidity).ok_or(SwapError::MathOverflow)?;
+        let epoch = order_tick.order_epoch;
+        if new_batch {
+            self.order_epochs = next_epoch;
+        }
+        Ok(epoch)
+    }
+
+    // Takes an unfilled order out of its batch
+    fn cancel_order(&mut self, tick: i32, liquidity: u128, pool: &PoolState) -> Result<()> {
+        let position = self.ticks.binary_search_by_key(&tick, |t| t.index).map_err(|_| SwapError::OrderAlreadyFilled)?;
+        let order_tick = &mut self.ticks[position];
+        order_tick.order_liquidity = order_tick.order_liquidity.checked_sub(liquidity).ok_or(SwapError::MathOverflow)?;
+        if order_tick.order_liquidity == 0 {
+            order_tick.order_epoch = 0;
+        }
+        let liquidity_delta = i128::try_from(liquidity).map_err(|_| SwapError::MathOverflow)?;
+        self.update_range(tick, tick + TICK_SPACING, -liquidity_delta, pool)
+    }
+
+    // Fills the batch at `tick` if the price just crossed its far side in
+    // the direction that sells its token. The batch's liquidity leaves the
+    // pool, so crossing back can't turn it into the token it sold.
+    fn fill_orders(&mut self, tick: i32, a_to_b: bool, pool: &PoolState) -> Result<Option<(u128, u64, bool)>> {
+        let Ok(position) = self.ticks.binary_search_by_key(&tick, |t| t.index) else {
+            return Ok(None);
+        };
+        let order_tick = &mut self.ticks[position];
+        // Selling A fills on the way up, selling B on the way down
+        if order_tick.order_liquidity == 0 || order_tick.order_sells_a == a_to_b {
+            return Ok(None);
+        }
+        let filled = (order_tick.order_liquidity, order_tick.order_epoch, order_tick.order_sells_a);
+        order_tick.order_liquidity = 0;
+        order_tick.order_epoch = 0;
+        let liquidity_delta = i128::try_from(filled.0).map_err(|_| SwapError::MathOverflow)?;
+        self.update_range(tick, tick + TICK_SPACING, -liquidity_delta, pool)?;
+        Ok(Some(filled))
+    }
+
+    // The price crossed `index`, so what was outside the tick is now inside
+    pub fn cross(&mut self, index: i32, fee_growth_global_a_x64: u128, fee_growth_global_b_x64: u128) {
+        if let Ok(position) = self.ticks.binary_search_by_key(&index, |tick| tick.index) {
+            let tick = &mut self.ticks[position];
+            tick.fee_growth_outside_a_x64 = fee_growth_global_a_x64.wrapping_sub(tick.fee_growth_outside_a_x64);
+            tick.fee_growth_outside_b_x64 = fee_growth_global_b_x64.wrapping_sub(tick.fee_growth_outside_b_x64);
+        }
+    }
+
+    // Adds `liquidity_delta` to a position range's bounds
+    fn update_range(&mut self, tick_lower: i32, tick_upper: i32, liquidity_delta: i128, pool: &PoolState) -> Result<()> {
+        self.update_tick(tick_lower, liquidity_delta, liquidity_delta, pool)?;
+        self.update_tick(tick_upper, liquidity_delta, -liquidity_delta, pool)
+    }
+
+    fn update_tick(&mut self, index: i32, gross_delta: i128, net_delta: i128, pool: &PoolState) -> Result<()> {
+        let position = match self.ticks.binary_search_by_key(&index, |tick| tick.index) {
+            Ok(position) => position,
+            Err(position) => {
+                require!(self.ticks.len() < MAX_POOL_TICKS, SwapError::TooManyTicks);
+                // By convention all fees so far were earned below a new tick
+                let (outside_a, outside_b) = if index <= pool.tick_current {
+                    (pool.fee_growth_global_a_x64, pool.fee_growth_global_b_x64)
+                } else {
+                    (0, 0)
+                };
+                self.ticks.insert(position, Tick {
+                    index,
+                    fee_growth_outside_a_x64: outside_a,
+                    fee_growth_outside_b_x64: outside_b,
+                    ..Default::default()
+                });
+                position
+            }
+        };
+        let tick = &mut self.ticks[position];
+        tick.liquidity_gross = tick.liquidity_gross.checked_add_signed(gross_delta).ok_or(SwapError::MathOverflow)?;
+        tick.liquidity_net = tick.liquidity_net.checked_add(net_delta).ok_or(SwapError::MathOverflow)?;
+        if tick.liquidity_gross == 0 {
+            self.ticks.remove(position);
+        }
+        Ok(())
+    }
+}
+
+// Reads a pool's ticks for a swap. The account only exists once the pool's
+// first ranged position is open.
+fn load_pool_ticks(pool_ticks: &AccountInfo) -> Result<PoolTicks> {
+    require_keys_eq!(*pool_ticks.owner, crate::ID, SwapError::MissingPoolTicks);
+    PoolTicks::try_deserialize(&mut &pool_ticks.try_borrow_data()?[..])
+}
+
+// Writes back a swap's tick crossings
+fn store_pool_ticks(pool_ticks: &AccountInfo, ticks: &PoolTicks) -> Result<()> {
+    ticks.try_serialize(&mut &mut pool_ticks.try_borrow_mut_data()?[..])
+}
+
+// Liquidity in one price range of a concentrated pool. Whoever holds the
+// position's NFT controls it.
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub pool: Pubkey,
+    pub position_mint: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub bump: u8,
+    pub fee_growth_inside_last_a_x64: u128, // Fee growth inside the range when the
+    pub fee_growth_inside_last_b_x64: u128, // position's fees were last worked out
+    pub tokens_owed_a: u64, // Fees earned and not yet collected
+    pub tokens_owed_b: u64,
+    pub reserved: [u8; 16], // Space for future fields
+}
+
+impl Position {
+    // Credits the fees earned since they were last worked out
+    fn accrue_fees(&mut self, (inside_a, inside_b): (u128, u128)) -> Result<()> {
+        let earned = |inside: u128, last: u128| {
+            let earned = (U256::from(self.liquidity) * U256::from(inside.wrapping_sub(last))) >> 64;
+            u64::try_from(earned).map_err(|_| error!(SwapError::MathOverflow))
+        };
+        let earned_a = earned(inside_a, self.fee_growth_inside_last_a_x64)?;
+        let earned_b = earned(inside_b, self.fee_growth_inside_last_b_x64)?;
+        self.tokens_owed_a = self.tokens_owed_a.checked_add(earned_a).ok_or(SwapError::MathOverflow)?;
+        self.tokens_owed_b = self.tokens_owed_b.checked_add(earned_b).ok_or(SwapError::MathOverflow)?;
+        self.fee_growth_inside_last_a_x64 = inside_a;
+        self.fee_growth_inside_last_b_x64 = inside_b;
+        Ok(())
+    }
+}
+
+// Name and symbol of position NFTs
+pub const POSITION_NFT_NAME: &str = "AMM Position";
+pub const POSITION_NFT_SYMBOL: &str = "AMM-POS";
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated,
+        constraint = pool_state.pool_type == 2 @ SwapError::InvalidPoolType
+    )]
+    pub pool_state: Box<Account<'info, PoolState>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + PoolTicks::INIT_SPACE,
+        seeds = [b"ticks", pool_state.key().as_ref()],
+        bump
+    )]
+    pub pool_ticks: Box<Account<'info, PoolTicks>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    // The position NFT. Its metadata lives in the mint itself.
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = pool_authority,
+        mint::token_program = token_2022_program,
+        extensions::metadata_pointer::authority = pool_authority,
+        extensions::metadata_pointer::metadata_address = position_mint,
+    )]
+    pub position_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = position_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_2022_program,
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    /// CHECK: PDA authority for vaults; mints the position NFT
+    #[account(
+        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+// Shared by increase_liquidity, decrease_liquidity and collect_fees: the
+// signer must hold the position's NFT
+#[derive(Accounts)]
+pub struct ModifyPosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
+    )]
+    pub pool_state: Box<Account<'info, PoolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"ticks", pool_state.key().as_ref()],
+        bump = pool_ticks.bump
+    )]
+    pub pool_ticks: Box<Account<'info, PoolTicks>>,
+
+    #[account(
+        mut,
+        seeds = [b"position", position.position_mint.as_ref()],
+        bump = position.bump,
+        constraint = position.pool == pool_state.key() @ SwapError::InvalidPoolAccount
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    #[account(
+        constraint = position_token_account.mint == position.position_mint @ SwapError::NotPositionHolder,
+        constraint = position_token_account.owner == owner.key() @ SwapError::NotPositionHolder,
+        constraint = position_token_account.amount == 1 @ SwapError::NotPositionHolder
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: PDA authority for vaults
+    #[account(
+        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+// Tokens left to sell at one price in a concentrated pool. The order is
+// liquidity over a single tick spacing that fills, batched with every other
+// order there, once the price crosses to the far side. Its address comes
+// from the pool, owner and tick, so a wallet has at most one order per tick.
+#[account]
+#[derive(InitSpace)]
+pub struct LimitOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick: i32,     // The order spans tick to tick + TICK_SPACING
+    pub sells_a: bool, // Sells token A for B above the price, or B for A below it
+    pub liquidity: u128,
+    pub epoch: u64,    // Batch the order joined, see PoolTicks::order_filled
+    pub bump: u8,
+    pub reserved: [u8; 32], // Space for future fields
+}
+
+// What `liquidity` of filled orders at `tick` received, rounded down
+fn filled_order_amount(tick: i32, sells_a: bool, liquidity: u128) -> Result<u64> {
+    let sqrt_price_lower = sqrt_price_at_tick(tick).ok_or(SwapError::InvalidTickRange)?;
+    let sqrt_price_upper = sqrt_price_at_tick(tick + TICK_SPACING).ok_or(SwapError::InvalidTickRange)?;
+    let amount = if sells_a {
+        amount_b_delta(sqrt_price_lower, sqrt_price_upper, liquidity, false)
+    } else {
+        amount_a_delta(sqrt_price_lower, sqrt_price_upper, liquidity, false)
+    };
+    amount.ok_or(SwapError::MathOverflow.into())
+}
+
+#[derive(Accounts)]
+#[instruction(tick: i32)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated,
+        constraint = pool_state.pool_type == 2 @ SwapError::InvalidPoolType
+    )]
+    pub pool_state: Box<Account<'info, PoolState>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + PoolTicks::INIT_SPACE,
+        seeds = [b"ticks", pool_state.key().as_ref()],
+        bump
+    )]
+    pub pool_ticks: Box<Account<'info, PoolTicks>>,
+
+    // One per wallet and tick; opened here so a second order gets a clear error
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + LimitOrder::INIT_SPACE,
+        seeds = [b"limit_order", pool_state.key().as_ref(), owner.key().as_ref(), &tick.to_le_bytes()],
+        bump
     )]
-    pub pool_authority: UncheckedAccount<'info>,
+    pub limit_order: Box<Account<'info, LimitOrder>>,
 
-    pub user_authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+    )]
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_a_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
+    )]
+    pub pool_token_b_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
 }
 
+// Shared by claim_filled_order and cancel_limit_order, which close the order
 #[derive(Accounts)]
-pub struct RemoveLiquidity<'info> {
+pub struct SettleLimitOrder<'info> {
     #[account(
+        mut,
         seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump = pool_state.bump
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
     )]
-    pub pool_state: Account<'info, PoolState>,
+    pub pool_state: Box<Account<'info, PoolState>>,
 
     #[account(
         mut,
-        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+        seeds = [b"ticks", pool_state.key().as_ref()],
+        bump = pool_ticks.bump
     )]
-    pub user_token_a: Account<'info, TokenAccount>,
-    
+    pub pool_ticks: Box<Account<'info, PoolTicks>>,
+
     #[account(
         mut,
-        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+        close = owner,
+        seeds = [b"limit_order", pool_state.key().as_ref(), owner.key().as_ref(), &limit_order.tick.to_le_bytes()],
+        bump = limit_order.bump,
+        has_one = owner @ SwapError::Unauthorized
     )]
-    pub user_token_b: Account<'info, TokenAccount>,
+    pub limit_order: Box<Account<'info, LimitOrder>>,
 
-    // User's LP token account - LP tokens will be burned from here
     #[account(
         mut,
-        constraint = user_lp_token.mint == pool_state.lp_mint @ SwapError::InvalidTokenMint
+        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
     )]
-    pub user_lp_token: Account<'info, TokenAccount>,
+    pub user_token_a: Box<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
-        constraint = pool_token_a_vault.mint == pool_state.token_a @ SwapError::InvalidTokenMint,
-        constraint = pool_token_a_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
-        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump
+        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
     )]
-    pub pool_token_a_vault: Account<'info, TokenAccount>,
-    
+    pub user_token_b: Box<Account<'info, TokenAccount>>,
+
     #[account(
         mut,
-        constraint = pool_token_b_vault.mint == pool_state.token_b @ SwapError::InvalidTokenMint,
-        constraint = pool_token_b_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
-        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
         bump
     )]
-    pub pool_token_b_vault: Account<'info, TokenAccount>,
+    pub pool_token_a_vault: Box<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
-        constraint = lp_mint.key() == pool_state.lp_mint @ SwapError::InvalidTokenMint
+        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        bump
     )]
-    pub lp_mint: Account<'info, Mint>,
+    pub pool_token_b_vault: Box<Account<'info, TokenAccount>>,
 
     /// CHECK: PDA authority for vaults
     #[account(
@@ -519,56 +4288,269 @@ pub struct RemoveLiquidity<'info> {
     )]
     pub pool_authority: UncheckedAccount<'info>,
 
-    pub user_authority: Signer<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
+// LP staking farm, one per pool
+#[account]
+#[derive(InitSpace)]
+pub struct Farm {
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub emission_rate: u64,     // Reward tokens per second, shared by all stakers
+    pub start_time: i64,
+    pub end_time: i64,
+    pub last_update_time: i64,  // When reward_per_share was last brought up to date
+    pub reward_per_share: u128, // Rewards per staked LP token so far, times REWARD_PER_SHARE_SCALE
+    pub total_staked: u64,
+    pub rewards_emitted: u64,   // Credited to stakers so far
+    pub rewards_claimed: u64,
+    pub bump: u8,
+    pub reserved: [u8; 64],     // Space for future fields
+}
+
+// A wallet's stake in a farm
+#[account]
+#[derive(InitSpace)]
+pub struct StakePosition {
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub reward_debt: u128,    // Rewards accrued by `amount` when it last settled
+    pub pending_rewards: u64, // Settled but not yet claimed
+    pub bump: u8,
+}
+
 #[derive(Accounts)]
-pub struct Swap<'info> {
+pub struct CreateFarm<'info> {
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump, has_one = admin @ SwapError::Unauthorized)]
+    pub amm_config: Account<'info, AmmConfig>,
+
     #[account(
         seeds = [b"pool", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump = pool_state.bump
+        bump = pool_state.bump,
+        constraint = pool_state.version >= MIN_POOL_STATE_VERSION @ SwapError::PoolNotMigrated
     )]
     pub pool_state: Account<'info, PoolState>,
 
+    #[account(address = pool_state.lp_mint @ SwapError::InvalidTokenMint)]
+    pub lp_mint: Account<'info, Mint>,
+    pub reward_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Farm::INIT_SPACE,
+        seeds = [b"farm", pool_state.key().as_ref()],
+        bump
+    )]
+    pub farm: Account<'info, Farm>,
+
+    // Holds staked LP tokens
+    #[account(
+        init,
+        payer = admin,
+        token::mint = lp_mint,
+        token::authority = farm,
+        seeds = [b"farm_stake_vault", farm.key().as_ref()],
+        bump
+    )]
+    pub stake_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = reward_mint,
+        token::authority = farm,
+        seeds = [b"farm_reward_vault", farm.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
     #[account(
         mut,
-        constraint = user_token_a.mint == pool_state.token_a @ SwapError::InvalidTokenMint
+        constraint = admin_reward_token.mint == reward_mint.key() @ SwapError::InvalidTokenMint
     )]
-    pub user_token_a: Account<'info, TokenAccount>,
-    
+    pub admin_reward_token: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FundFarm<'info> {
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump, has_one = admin @ SwapError::Unauthorized)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(seeds = [b"farm", farm.pool.as_ref()], bump = farm.bump)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(mut, seeds = [b"farm_reward_vault", farm.key().as_ref()], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+
     #[account(
         mut,
-        constraint = user_token_b.mint == pool_state.token_b @ SwapError::InvalidTokenMint
+        constraint = admin_reward_token.mint == farm.reward_mint @ SwapError::InvalidTokenMint
     )]
-    pub user_token_b: Account<'info, TokenAccount>,
+    pub admin_reward_token: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendFarm<'info> {
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump, has_one = admin @ SwapError::Unauthorized)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(mut, seeds = [b"farm", farm.pool.as_ref()], bump = farm.bump)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(seeds = [b"farm_reward_vault", farm.key().as_ref()], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimFarmRewards<'info> {
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump, has_one = admin @ SwapError::Unauthorized)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(mut, seeds = [b"farm", farm.pool.as_ref()], bump = farm.bump)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(mut, seeds = [b"farm_reward_vault", farm.key().as_ref()], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
 
     #[account(
         mut,
-        constraint = pool_token_a_vault.mint == pool_state.token_a @ SwapError::InvalidTokenMint,
-        constraint = pool_token_a_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
-        seeds = [b"vault_a", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump
+        constraint = admin_reward_token.mint == farm.reward_mint @ SwapError::InvalidTokenMint
     )]
-    pub pool_token_a_vault: Account<'info, TokenAccount>,
-    
+    pub admin_reward_token: Account<'info, TokenAccount>,
+
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseFarm<'info> {
+    #[account(seeds = [b"amm_config"], bump = amm_config.bump, has_one = admin @ SwapError::Unauthorized)]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    #[account(mut, close = recipient, seeds = [b"farm", farm.pool.as_ref()], bump = farm.bump)]
+    pub farm: Account<'info, Farm>,
+
+    // Burns whatever was sent to the stake vault without staking
+    #[account(mut, address = farm.lp_mint @ SwapError::InvalidTokenMint)]
+    pub lp_mint: Account<'info, Mint>,
+
+    #[account(mut, seeds = [b"farm_stake_vault", farm.key().as_ref()], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, seeds = [b"farm_reward_vault", farm.key().as_ref()], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+
     #[account(
         mut,
-        constraint = pool_token_b_vault.mint == pool_state.token_b @ SwapError::InvalidTokenMint,
-        constraint = pool_token_b_vault.owner == pool_authority.key() @ SwapError::InvalidVaultAuthority,
-        seeds = [b"vault_b", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
-        bump
+        constraint = admin_reward_token.mint == farm.reward_mint @ SwapError::InvalidTokenMint
     )]
-    pub pool_token_b_vault: Account<'info, TokenAccount>,
+    pub admin_reward_token: Account<'info, TokenAccount>,
 
-    /// CHECK: PDA authority for vaults
+    pub admin: Signer<'info>,
+
+    /// CHECK: Only receives the closed accounts' rent
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StakeLp<'info> {
+    #[account(mut, seeds = [b"farm", farm.pool.as_ref()], bump = farm.bump)]
+    pub farm: Account<'info, Farm>,
+
+    // Opened on the wallet's first stake
     #[account(
-        seeds = [b"pool_authority", pool_state.token_a.as_ref(), pool_state.token_b.as_ref()],
+        init_if_needed,
+        payer = owner,
+        space = 8 + StakePosition::INIT_SPACE,
+        seeds = [b"stake_position", farm.key().as_ref(), owner.key().as_ref()],
         bump
     )]
-    pub pool_authority: UncheckedAccount<'info>,
+    pub stake_position: Account<'info, StakePosition>,
 
-    pub user_authority: Signer<'info>,
+    #[account(mut, seeds = [b"farm_stake_vault", farm.key().as_ref()], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == farm.lp_mint @ SwapError::InvalidTokenMint
+    )]
+    pub user_lp_token: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeLp<'info> {
+    #[account(mut, seeds = [b"farm", farm.pool.as_ref()], bump = farm.bump)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump = stake_position.bump,
+        has_one = owner @ SwapError::Unauthorized
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+
+    #[account(mut, seeds = [b"farm_stake_vault", farm.key().as_ref()], bump)]
+    pub stake_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_lp_token.mint == farm.lp_mint @ SwapError::InvalidTokenMint
+    )]
+    pub user_lp_token: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut, seeds = [b"farm", farm.pool.as_ref()], bump = farm.bump)]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_position", farm.key().as_ref(), owner.key().as_ref()],
+        bump = stake_position.bump,
+        has_one = owner @ SwapError::Unauthorized
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+
+    #[account(mut, seeds = [b"farm_reward_vault", farm.key().as_ref()], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_reward_token.mint == farm.reward_mint @ SwapError::InvalidTokenMint
+    )]
+    pub user_reward_token: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
@@ -588,4 +4570,422 @@ pub enum SwapError {
     InsufficientLpBalance,
     #[msg("Invalid pool type")]
     InvalidPoolType,
-}
\ No newline at end of file
+    #[msg("Flash swap callback program missing from remaining accounts")]
+    MissingCallbackProgram,
+    #[msg("Invalid flash swap callback program")]
+    InvalidCallbackProgram,
+    #[msg("Flash swap did not repay the pool")]
+    FlashSwapNotRepaid,
+    #[msg("Transaction deadline exceeded")]
+    DeadlineExceeded,
+    #[msg("Account is not a pool state account")]
+    InvalidPoolAccount,
+    #[msg("Pool account uses an old layout, run migrate_pool first")]
+    PoolNotMigrated,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Token decimals are too far apart to pool")]
+    UnsupportedMintDecimals,
+    #[msg("Pool tokens must use two different mints")]
+    IdenticalMints,
+    #[msg("Mint configuration is not allowed by the AMM config")]
+    MintRejectedByPolicy,
+    #[msg("Signer is not allowed to perform this action")]
+    Unauthorized,
+    #[msg("Invalid AMM config parameters")]
+    InvalidConfig,
+    #[msg("Treasury account does not match the AMM config")]
+    InvalidTreasury,
+    #[msg("Farm start/end times or emission rate are invalid")]
+    InvalidFarmSchedule,
+    #[msg("Farm reward vault does not cover the scheduled rewards")]
+    FarmUnderfunded,
+    #[msg("Unstake amount exceeds the staked balance")]
+    InsufficientStake,
+    #[msg("Position ticks must be ordered, in range and multiples of the tick spacing")]
+    InvalidTickRange,
+    #[msg("Pool has no room for another initialized tick")]
+    TooManyTicks,
+    #[msg("Position does not hold that much liquidity")]
+    InsufficientPositionLiquidity,
+    #[msg("Signer does not hold the position NFT")]
+    NotPositionHolder,
+    #[msg("Pool ticks account missing or invalid")]
+    MissingPoolTicks,
+    #[msg("Limit orders must sit entirely on the side of the price they sell into")]
+    InvalidOrderPrice,
+    #[msg("Limit order has not filled yet")]
+    OrderNotFilled,
+    #[msg("Limit order has already filled")]
+    OrderAlreadyFilled,
+    #[msg("Referral fee exceeds the configured maximum")]
+    ReferralFeeTooHigh,
+    #[msg("A referral fee needs a referrer token account")]
+    MissingReferrer,
+    #[msg("Swap moved the price more than allowed")]
+    PriceImpactExceeded,
+    #[msg("Swap would move the price past its limit")]
+    PriceLimitExceeded,
+    #[msg("Price limit must lie between the current price and the end of the range, in the swap's direction")]
+    InvalidPriceLimit,
+    #[msg("Swaps in this slot have moved the price more than the circuit breaker allows")]
+    CircuitBreakerTripped,
+    #[msg("Pool still has LP tokens or tokens in its vaults")]
+    PoolNotEmpty,
+    #[msg("Native SOL needs a wrapped SOL side and the wSOL, native mint and system program accounts")]
+    InvalidNativeSol,
+    #[msg("Batch swap needs at least one leg, each on a pool passed in remaining accounts")]
+    InvalidBatchSwap,
+    #[msg("Farm is still running, has LP tokens staked or owes rewards")]
+    FarmNotFinished,
+    #[msg("Position or limit order would hold less than the minimum liquidity")]
+    LiquidityBelowMinimum,
+    #[msg("Wallet already has a limit order at this tick; claim or cancel it first")]
+    LimitOrderExists,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(pool_type: u8, token_a_decimals: u8, token_b_decimals: u8) -> PoolState {
+        let (token_a_rate, token_b_rate) = token_rates(token_a_decimals, token_b_decimals).unwrap();
+        PoolState {
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            fee_rate: 300,
+            pool_type,
+            bump: 255,
+            version: POOL_STATE_VERSION,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_rate,
+            token_b_rate,
+            reserve_a: 0,
+            reserve_b: 0,
+            token_a_flags: 0,
+            token_b_flags: 0,
+            creator: Pubkey::default(),
+            creator_fee_share: 0,
+            creator_fees_a: 0,
+            creator_fees_b: 0,
+            sqrt_price_x64: 0,
+            tick_current: 0,
+            liquidity: 0,
+            position_reserve_a: 0,
+            position_reserve_b: 0,
+            fee_growth_global_a_x64: 0,
+            fee_growth_global_b_x64: 0,
+            position_fees_a: 0,
+            position_fees_b: 0,
+            order_liquidity: 0,
+            breaker_slot: 0,
+            breaker_price_x64: 0,
+            reserved: [0; 30],
+        }
+    }
+
+    #[test]
+    fn fee_rounds_down() {
+        assert_eq!(calculate_fee(1_000_000, 300), 3_000);
+        assert_eq!(calculate_fee(333, 300), 0);
+        assert_eq!(calculate_fee(u64::MAX, 100_000), u64::MAX);
+    }
+
+    #[test]
+    fn standard_swap_keeps_product() {
+        assert_eq!(calculate_standard_swap(1_000, 1_000, 1_000), 500);
+        assert_eq!(calculate_standard_swap(1_000_000, 2_000_000, 0), 0);
+
+        let (x, y, dx) = (1_000_000_u128, 3_000_000_u128, 12_345_u64);
+        let dy = calculate_standard_swap(x as u64, y as u64, dx) as u128;
+        assert!((x + dx as u128) * (y - dy) >= x * y);
+    }
+
+    #[test]
+    fn stable_invariant_of_balanced_pool_is_its_sum() {
+        let d = stable_invariant(U256::from(1_000_000), U256::from(1_000_000), STABLE_AMP).unwrap();
+        assert_eq!(d, U256::from(2_000_000));
+        assert_eq!(stable_invariant(U256::zero(), U256::zero(), STABLE_AMP), Some(U256::zero()));
+        assert_eq!(stable_invariant(U256::zero(), U256::one(), STABLE_AMP), None);
+    }
+
+    #[test]
+    fn stable_invariant_converges_on_imbalanced_pools() {
+        for (x, y) in [(1_000_000u128, 1u128), (14_676_469_000, 1), (1, 10u128.pow(25))] {
+            assert!(stable_invariant(U256::from(x), U256::from(y), STABLE_AMP).is_some(), "{x} / {y}");
+        }
+    }
+
+    #[test]
+    fn stable_swap_is_near_one_to_one_when_balanced() {
+        let out = calculate_stable_swap(1_000_000_000, 1_000_000_000, 1_000_000, 1, 1).unwrap();
+        assert!((999_900..1_000_000).contains(&out), "got {out}");
+        // Never better than 1:1 on a balanced pool
+        assert!(out < 1_000_000);
+    }
+
+    #[test]
+    fn stable_swap_scales_by_rates() {
+        // 6-decimal token in, 9-decimal token out, both holding 1000 tokens
+        let out = calculate_stable_swap(1_000_000_000, 1_000_000_000_000, 1_000_000, 1_000, 1).unwrap();
+        assert!((999_900_000..1_000_000_000).contains(&out), "got {out}");
+
+        let back = calculate_stable_swap(1_000_000_000_000, 1_000_000_000, 1_000_000_000, 1, 1_000).unwrap();
+        assert!((999_900..1_000_000).contains(&back), "got {back}");
+    }
+
+    #[test]
+    fn spot_price_follows_each_curve() {
+        let one = U256::one() << 64;
+        let mut standard = pool(0, 6, 6);
+        (standard.reserve_a, standard.reserve_b) = (1_000_000, 4_000_000);
+        assert_eq!(standard.spot_price_x64(), Some(one * 4));
+
+        // Balanced stable pools trade 1:1 in whole tokens, whatever the decimals
+        let mut stable = pool(1, 6, 9);
+        (stable.reserve_a, stable.reserve_b) = (1_000_000_000, 1_000_000_000_000);
+        let price = stable.spot_price_x64().unwrap();
+        assert!(price / 1_000 <= one + 1 && price / 1_000 + 1 >= one, "got {price}");
+
+        // Off balance the stable price moves, but far less than x * y would
+        (stable.reserve_a, stable.reserve_b) = (2_000_000_000, 500_000_000_000);
+        let price = stable.spot_price_x64().unwrap() / 1_000;
+        assert!(price < one && price > one * 9 / 10, "got {price}");
+
+        stable.reserve_a = 0;
+        assert_eq!(stable.spot_price_x64(), None);
+    }
+
+    #[test]
+    fn input_to_price_limit_stops_short_of_the_limit() {
+        let mut standard = pool(0, 6, 6);
+        for (a_to_b, tick) in [(true, -500), (false, 500)] {
+            (standard.reserve_a, standard.reserve_b) = (1_000_000_000, 4_000_000_000);
+            let limit = sqrt_price_at_tick(tick_at_sqrt_price(sqrt_price_from_reserves(1, 4).unwrap()) + tick).unwrap();
+            let limit_price = (U256::from(limit) * U256::from(limit)) >> 64;
+            let (input_balance, output_balance) = standard.reserves(a_to_b);
+            let max_in = input_to_price_limit(input_balance, output_balance, standard.fee_rate, limit, a_to_b);
+            assert!(max_in > 0);
+
+            // The whole input, fee included, joins the input reserve
+            let quote = standard.quote_swap(max_in, a_to_b).unwrap();
+            let (input_after, output_after) = (input_balance + max_in, output_balance - quote.amount_out);
+            (standard.reserve_a, standard.reserve_b) =
+                if a_to_b { (input_after, output_after) } else { (output_after, input_after) };
+            let price = standard.spot_price_x64().unwrap();
+            assert!(if a_to_b { price >= limit_price } else { price <= limit_price }, "got {price}");
+            let gap = if a_to_b { price - limit_price } else { limit_price - price };
+            assert!(gap < limit_price / 1_000_000, "got {price}");
+        }
+    }
+
+    #[test]
+    fn stable_swap_output_is_bounded_by_reserve() {
+        let out = calculate_stable_swap(1_000, 1_000, u64::MAX / 2, 1, 1).unwrap();
+        assert!(out < 1_000);
+    }
+
+    #[test]
+    fn token_rates_lift_to_common_decimals() {
+        assert_eq!(token_rates(6, 6).unwrap(), (1, 1));
+        assert_eq!(token_rates(6, 9).unwrap(), (1_000, 1));
+        assert_eq!(token_rates(9, 2).unwrap(), (1, 10_000_000));
+        assert!(token_rates(0, 20).is_err());
+    }
+
+    #[test]
+    fn lp_decimals_follow_the_more_precise_token() {
+        assert_eq!(lp_mint_decimals(6, 9), 9);
+        assert_eq!(lp_mint_decimals(2, 0), 2);
+        assert_eq!(lp_mint_decimals(18, 6), MAX_LP_DECIMALS);
+    }
+
+    #[test]
+    fn flash_swap_invariant_requires_full_payment() {
+        let pool = pool(0, 6, 6);
+        let amount_in = 10_000;
+        let amount_out = calculate_standard_swap(1_000_000, 1_000_000, amount_in - calculate_fee(amount_in, 300));
+
+        assert!(flash_swap_invariant_holds(&pool, true, 1_000_000, 1_000_000, 1_000_000 + amount_in, 1_000_000 - amount_out));
+        assert!(!flash_swap_invariant_holds(&pool, true, 1_000_000, 1_000_000, 1_000_000 + amount_in / 2, 1_000_000 - amount_out));
+        // Returning the borrowed tokens is always fine
+        assert!(flash_swap_invariant_holds(&pool, true, 1_000_000, 1_000_000, 1_000_000, 1_000_000));
+        // Taking input tokens out never is
+        assert!(!flash_swap_invariant_holds(&pool, true, 1_000_000, 1_000_000, 999_999, 1_000_000));
+    }
+
+    #[test]
+    fn creator_fee_is_a_share_of_the_fee() {
+        let mut pool = pool(0, 6, 6);
+        assert_eq!(pool.creator_fee(3_000), 0);
+        pool.creator_fee_share = MAX_CREATOR_FEE_SHARE;
+        assert_eq!(pool.creator_fee(3_000), 1_500);
+        pool.accrue_creator_fee(false, 1_500).unwrap();
+        assert_eq!((pool.creator_fees_a, pool.creator_fees_b), (0, 1_500));
+    }
+
+    #[test]
+    fn tick_prices_span_the_range() {
+        assert_eq!(sqrt_price_at_tick(0), Some(Q64));
+        assert_eq!(sqrt_price_at_tick(MIN_TICK), Some(MIN_SQRT_PRICE_X64));
+        assert_eq!(sqrt_price_at_tick(MAX_TICK), Some(MAX_SQRT_PRICE_X64));
+        assert_eq!(sqrt_price_at_tick(MAX_TICK + 1), None);
+        // 1.0001^(10000/2) is about 1.6487
+        let price = sqrt_price_at_tick(10_000).unwrap() as f64 / Q64 as f64;
+        assert!((price - 1.0001f64.powi(5_000)).abs() < 1e-9, "got {price}");
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_inverts_tick_prices() {
+        for tick in [MIN_TICK, -100_000, -1, 0, 1, 777, 100_000, MAX_TICK] {
+            let price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(price), tick);
+            if tick > MIN_TICK {
+                assert_eq!(tick_at_sqrt_price(price - 1), tick - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn position_amounts_round_towards_the_pool() {
+        let (lower, upper) = (sqrt_price_at_tick(-1_000).unwrap(), sqrt_price_at_tick(1_000).unwrap());
+        let liquidity = liquidity_for_amounts(Q64, lower, upper, 1_000_000, 1_000_000).unwrap();
+        let (in_a, in_b) = position_amounts(Q64, lower, upper, liquidity, true).unwrap();
+        let (out_a, out_b) = position_amounts(Q64, lower, upper, liquidity, false).unwrap();
+        assert!(in_a <= 1_000_000 && in_b <= 1_000_000);
+        assert!(out_a <= in_a && out_b <= in_b && in_a - out_a <= 1 && in_b - out_b <= 1);
+
+        // Out of range, a position is all one token
+        let below = position_amounts(lower - 1, lower, upper, liquidity, false).unwrap();
+        let above = position_amounts(upper + 1, lower, upper, liquidity, false).unwrap();
+        assert!(below.0 > 0 && below.1 == 0);
+        assert!(above.0 == 0 && above.1 > 0);
+    }
+
+    #[test]
+    fn mul_div_handles_wide_products() {
+        let big = U256::MAX >> 1;
+        assert_eq!(mul_div(big, U256::from(4), U256::from(4), false), Some(big));
+        assert_eq!(mul_div(U256::from(7), U256::from(1), U256::from(2), true), Some(U256::from(4)));
+        assert_eq!(mul_div(U256::MAX, U256::MAX, U256::one(), false), None);
+        assert_eq!(mul_div(U256::one(), U256::one(), U256::zero(), false), None);
+    }
+
+    #[test]
+    fn fee_growth_inside_counts_only_the_range() {
+        let mut pool = pool(2, 6, 6);
+        let mut ticks = PoolTicks { pool: Pubkey::new_unique(), bump: 255, order_epochs: 0, ticks: vec![] };
+        pool.fee_growth_global_a_x64 = 100;
+        ticks.update_range(-10, 10, 1_000, &pool).unwrap();
+        // Fees earned before the range opened aren't inside it
+        assert_eq!(ticks.fee_growth_inside(-10, 10, &pool), Some((0, 0)));
+
+        pool.fee_growth_global_a_x64 = 150;
+        assert_eq!(ticks.fee_growth_inside(-10, 10, &pool), Some((50, 0)));
+
+        // Leaving the range upwards freezes what it earned
+        ticks.cross(10, 150, 0);
+        pool.tick_current = 10;
+        pool.fee_growth_global_a_x64 = 400;
+        assert_eq!(ticks.fee_growth_inside(-10, 10, &pool), Some((50, 0)));
+        assert_eq!(ticks.fee_growth_inside(-10, 20, &pool), None);
+    }
+
+    // ---------- invariants over random pools ----------
+
+    use proptest::prelude::*;
+
+    fn pool_type() -> impl Strategy<Value = u8> {
+        0u8..3
+    }
+
+    fn decimals() -> impl Strategy<Value = (u8, u8)> {
+        prop_oneof![Just((6, 6)), Just((6, 9)), Just((9, 6)), Just((2, 9)), Just((9, 0))]
+    }
+
+    fn balance() -> impl Strategy<Value = u64> {
+        1_000u64..1_000_000_000_000_000
+    }
+
+    proptest! {
+        #[test]
+        fn swap_output_stays_below_output_balance(
+            pool_type in pool_type(),
+            (decimals_a, decimals_b) in decimals(),
+            reserve_a in balance(),
+            reserve_b in balance(),
+            amount_in in 1u64..u64::MAX / 2,
+            a_to_b: bool,
+        ) {
+            let pool = pool(pool_type, decimals_a, decimals_b);
+            let (input_balance, output_balance) = if a_to_b { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+
+            if let Some(out) = calculate_swap_output(&pool, a_to_b, input_balance, output_balance, amount_in) {
+                prop_assert!(out < output_balance);
+            }
+        }
+
+        #[test]
+        fn swap_never_decreases_curve_invariant(
+            pool_type in pool_type(),
+            (decimals_a, decimals_b) in decimals(),
+            reserve_a in balance(),
+            reserve_b in balance(),
+            amount_in in 1u64..1_000_000_000_000_000,
+            a_to_b: bool,
+        ) {
+            let mut pool = pool(pool_type, decimals_a, decimals_b);
+            pool.fee_rate = [300, 50, 500][pool_type as usize];
+            let (input_balance, output_balance) = if a_to_b { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+
+            let amount_in_after_fee = amount_in - calculate_fee(amount_in, pool.fee_rate);
+            let out = calculate_swap_output(&pool, a_to_b, input_balance, output_balance, amount_in_after_fee);
+            prop_assume!(out.is_some());
+
+            // Even with only the post-fee input counted, the pool must not lose value
+            let (new_input, new_output) = (input_balance + amount_in_after_fee, output_balance - out.unwrap());
+            let (before, after) = if a_to_b {
+                (curve_invariant(&pool, input_balance, output_balance), curve_invariant(&pool, new_input, new_output))
+            } else {
+                (curve_invariant(&pool, output_balance, input_balance), curve_invariant(&pool, new_output, new_input))
+            };
+            prop_assume!(before.is_some() && after.is_some());
+            prop_assert!(after.unwrap() >= before.unwrap());
+        }
+
+        #[test]
+        fn add_then_remove_never_returns_more_than_deposited(
+            pool_type in pool_type(),
+            (decimals_a, decimals_b) in decimals(),
+            reserve_a in balance(),
+            reserve_b in balance(),
+            lp_supply in 1_000u64..1_000_000_000_000,
+            amount_a in 1u64..1_000_000_000_000_000,
+            amount_b in 1u64..1_000_000_000_000_000,
+        ) {
+            let mut pool = pool(pool_type, decimals_a, decimals_b);
+            pool.fee_rate = [300, 50, 500][pool_type as usize];
+
+            let lp = calculate_deposit_lp(&pool, reserve_a, reserve_b, amount_a, amount_b, lp_supply);
+            prop_assume!(lp.is_some_and(|lp| lp > 0));
+            let lp = lp.unwrap();
+
+            let (pooled_a, pooled_b, supply) = (reserve_a + amount_a, reserve_b + amount_b, lp_supply + lp);
+            let out_a = calculate_withdrawal(pooled_a, lp, supply).unwrap();
+            let out_b = calculate_withdrawal(pooled_b, lp, supply).unwrap();
+
+            // Constant product deposits are credited for their smaller side, so
+            // neither token comes back with a profit. Stable deposits may come
+            // back in a different mix, but never worth more: the other LPs'
+            // share of the invariant can't shrink.
+            if pool_type != 1 {
+                prop_assert!(out_a <= amount_a && out_b <= amount_b);
+            }
+            let before = curve_invariant(&pool, reserve_a, reserve_b);
+            let after = curve_invariant(&pool, pooled_a - out_a, pooled_b - out_b);
+            prop_assume!(before.is_some() && after.is_some());
+            prop_assert!(after.unwrap() >= before.unwrap());
+        }
+    }
+}
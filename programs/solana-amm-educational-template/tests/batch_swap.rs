@@ -0,0 +1,77 @@
+mod common;
+
+use anchor_lang::AnchorDeserialize;
+use common::*;
+use solana_amm_educational_template::{SwapError, SwapExecuted, SwapLeg};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+
+// Two funded standard pools and a trader holding 10_000_000 of each of their tokens
+fn setup() -> (TestEnv, [(Pool, User); 2]) {
+    let env = TestEnv::new();
+    let (first, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let (second, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&first, 10_000_000, 10_000_000);
+    let second_accounts = User {
+        token_a: env.create_token_account(&second.token_a_mint, &trader.key, 10_000_000),
+        token_b: env.create_token_account(&second.token_b_mint, &trader.key, 10_000_000),
+        lp: env.create_token_account(&second.lp_mint, &trader.key, 0),
+        ..trader
+    };
+    (env, [(first, trader), (second, second_accounts)])
+}
+
+fn leg(pool_index: u8, amount_in: u64, minimum_amount_out: u64, a_to_b: bool) -> SwapLeg {
+    SwapLeg { pool_index, amount_in, minimum_amount_out, a_to_b }
+}
+
+#[test]
+fn legs_run_in_order_and_return_their_outputs() {
+    let (env, [(first, first_user), (second, second_user)]) = setup();
+    let pools = [(&first, &first_user), (&second, &second_user)];
+
+    // The last leg sells what the first one bought
+    env.batch_swap(&pools, vec![leg(0, 1_000_000, 0, true), leg(1, 2_000_000, 0, false), leg(0, 500_000, 0, false)])
+        .unwrap();
+
+    let (program, data) = env.svm.return_data().expect("batch swap returns its outputs");
+    assert_eq!(program, PROGRAM_ID);
+    let amounts_out = Vec::<u64>::deserialize(&mut data.as_slice()).unwrap();
+    let executed = env.events::<SwapExecuted>();
+    assert_eq!(executed.len(), 3);
+    assert_eq!(amounts_out, executed.iter().map(|swap| swap.amount_out).collect::<Vec<_>>());
+    assert_eq!(
+        [executed[0].pool, executed[1].pool, executed[2].pool],
+        [first.state, second.state, first.state]
+    );
+
+    assert_eq!(env.token_balance(&first_user.token_a), 10_000_000 - 1_000_000 + amounts_out[2]);
+    assert_eq!(env.token_balance(&first_user.token_b), 10_000_000 + amounts_out[0] - 500_000);
+    assert_eq!(env.token_balance(&second_user.token_a), 10_000_000 + amounts_out[1]);
+    assert_eq!(env.token_balance(&second_user.token_b), 10_000_000 - 2_000_000);
+}
+
+#[test]
+fn one_failing_leg_reverts_the_batch() {
+    let (env, [(first, first_user), (second, second_user)]) = setup();
+    let pools = [(&first, &first_user), (&second, &second_user)];
+    let reserves_before = env.pool_state(&first).reserve_a;
+
+    assert_swap_error(
+        env.batch_swap(&pools, vec![leg(0, 1_000_000, 0, true), leg(1, 1_000_000, 1_000_000, true)]),
+        SwapError::SlippageExceeded,
+    );
+    assert_eq!(env.pool_state(&first).reserve_a, reserves_before);
+    assert_eq!(env.token_balance(&first_user.token_a), 10_000_000);
+}
+
+#[test]
+fn legs_must_name_a_passed_pool() {
+    let (env, [(first, first_user), _]) = setup();
+
+    assert_swap_error(env.batch_swap(&[(&first, &first_user)], vec![]), SwapError::InvalidBatchSwap);
+    assert_swap_error(
+        env.batch_swap(&[(&first, &first_user)], vec![leg(1, 1_000_000, 0, true)]),
+        SwapError::InvalidBatchSwap,
+    );
+}
@@ -0,0 +1,1115 @@
+// Shared setup for the integration tests: a solana-program-test bank with the
+// AMM deployed, plus helpers to create mints, users and pools and to build
+// instructions.
+
+#![allow(dead_code)]
+
+pub mod svm;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{instruction::Instruction, program_error::ProgramError, program_option::COption, program_pack::Pack};
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use solana_amm_educational_template::{accounts, instruction, AmmConfig, ConfigParams, PoolState, SwapError, SwapLeg};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use spl_token_2022::extension::StateWithExtensions;
+
+pub use svm::{Account, BuiltinFunctionWithContext, Svm};
+
+pub const PROGRAM_ID: Pubkey = solana_amm_educational_template::ID;
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"amm_config"], &PROGRAM_ID).0
+}
+
+pub fn pool_creator_address(creator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool_creator", creator.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(
+        &[PROGRAM_ID.as_ref()],
+        &anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+    )
+    .0
+}
+
+// Addresses of one pool's accounts
+#[derive(Clone, Copy, Debug)]
+pub struct Pool {
+    pub state: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub authority: Pubkey,
+    pub ticks: Pubkey,
+}
+
+impl Pool {
+    pub fn new(token_a_mint: Pubkey, token_b_mint: Pubkey) -> Self {
+        let pda = |prefix: &[u8]| {
+            Pubkey::find_program_address(&[prefix, token_a_mint.as_ref(), token_b_mint.as_ref()], &PROGRAM_ID).0
+        };
+        let state = pda(b"pool");
+        Self {
+            state,
+            token_a_mint,
+            token_b_mint,
+            lp_mint: pda(b"lp_mint"),
+            vault_a: pda(b"vault_a"),
+            vault_b: pda(b"vault_b"),
+            authority: pda(b"pool_authority"),
+            ticks: Pubkey::find_program_address(&[b"ticks", state.as_ref()], &PROGRAM_ID).0,
+        }
+    }
+
+    pub fn limit_order(&self, owner: &Pubkey, tick: i32) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"limit_order", self.state.as_ref(), owner.as_ref(), &tick.to_le_bytes()],
+            &PROGRAM_ID,
+        )
+        .0
+    }
+}
+
+// Addresses of a ranged position and its NFT mint
+#[derive(Clone, Copy, Debug)]
+pub struct PositionAccounts {
+    pub mint: Pubkey,
+    pub address: Pubkey,
+}
+
+impl PositionAccounts {
+    // `mint` signs for its own creation when the position is opened
+    pub fn new(mint: Pubkey) -> Self {
+        let address = Pubkey::find_program_address(&[b"position", mint.as_ref()], &PROGRAM_ID).0;
+        Self { mint, address }
+    }
+
+    // The owner's associated account for the position NFT
+    pub fn token_account(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &spl_token_2022::ID)
+    }
+}
+
+// Addresses of a pool's LP farm
+#[derive(Clone, Copy, Debug)]
+pub struct FarmAccounts {
+    pub pool: Pubkey,
+    pub lp_mint: Pubkey,
+    pub address: Pubkey,
+    pub reward_mint: Pubkey,
+    pub stake_vault: Pubkey,
+    pub reward_vault: Pubkey,
+}
+
+impl FarmAccounts {
+    pub fn new(pool: &Pool, reward_mint: Pubkey) -> Self {
+        let address = Pubkey::find_program_address(&[b"farm", pool.state.as_ref()], &PROGRAM_ID).0;
+        let pda = |prefix: &[u8]| Pubkey::find_program_address(&[prefix, address.as_ref()], &PROGRAM_ID).0;
+        Self {
+            pool: pool.state,
+            lp_mint: pool.lp_mint,
+            address,
+            reward_mint,
+            stake_vault: pda(b"farm_stake_vault"),
+            reward_vault: pda(b"farm_reward_vault"),
+        }
+    }
+
+    pub fn stake_position(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"stake_position", self.address.as_ref(), owner.as_ref()], &PROGRAM_ID).0
+    }
+}
+
+// A wallet with token accounts for one pool
+#[derive(Clone, Copy, Debug)]
+pub struct User {
+    pub key: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    pub lp: Pubkey,
+}
+
+// A user's token account as the program takes it: the wallet itself in its
+// place means native SOL
+pub fn user_token(account: Pubkey, user: &User) -> Option<Pubkey> {
+    (account != user.key).then_some(account)
+}
+
+pub fn uses_native_sol(user: &User) -> bool {
+    user.token_a == user.key || user.token_b == user.key
+}
+
+// The temporary wSOL account the program wraps a user's native SOL in
+pub fn wsol_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"wsol", owner.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn anchor_error(error: anchor_lang::error::ErrorCode) -> ProgramError {
+    ProgramError::Custom(error as u32)
+}
+
+pub fn assert_swap_error(result: std::result::Result<(), ProgramError>, error: SwapError) {
+    let expected = ProgramError::Custom(anchor_lang::error::ERROR_CODE_OFFSET + error as u32);
+    assert_eq!(result, Err(expected), "expected {:?}", error);
+}
+
+pub fn default_config_params(treasury: Pubkey) -> ConfigParams {
+    ConfigParams {
+        rejected_mint_flags: 0,
+        permissionless_pool_creation: true,
+        pool_creation_fee: 0,
+        treasury,
+        creator_fee_share: 0,
+        max_referral_fee_bps: 0,
+        circuit_breaker_bps: 0,
+    }
+}
+
+pub struct TestEnv {
+    pub svm: Svm,
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub mint_authority: Pubkey,
+}
+
+impl TestEnv {
+    // Runtime with the AMM deployed and a permissionless, fee-free config
+    pub fn new() -> Self {
+        Self::with_programs(&[])
+    }
+
+    // Runtime with the AMM and `programs` deployed, and a permissionless,
+    // fee-free config
+    pub fn with_programs(programs: &[(Pubkey, Option<BuiltinFunctionWithContext>)]) -> Self {
+        let env = Self::start(programs);
+        env.initialize_config(env.admin, default_config_params(env.treasury)).unwrap();
+        env
+    }
+
+    // Runtime with the AMM deployed, upgrade authority `admin`, and no config yet
+    pub fn without_config() -> Self {
+        Self::start(&[])
+    }
+
+    fn start(programs: &[(Pubkey, Option<BuiltinFunctionWithContext>)]) -> Self {
+        let svm = Svm::new(programs);
+        let admin = svm.upgrade_authority();
+        svm.airdrop(&admin, 100 * LAMPORTS_PER_SOL);
+
+        let treasury = Pubkey::new_unique();
+        svm.airdrop(&treasury, LAMPORTS_PER_SOL);
+
+        Self { svm, admin, treasury, mint_authority: Pubkey::new_unique() }
+    }
+
+    pub fn wallet(&self) -> Pubkey {
+        let wallet = self.svm.new_signer();
+        self.svm.airdrop(&wallet, 10 * LAMPORTS_PER_SOL);
+        wallet
+    }
+
+    // Addresses for a new ranged position, with a mint the runtime can sign for
+    pub fn position(&self) -> PositionAccounts {
+        PositionAccounts::new(self.svm.new_signer())
+    }
+
+    // ---------- accounts ----------
+
+    pub fn create_mint(&self, decimals: u8) -> Pubkey {
+        self.create_mint_with_freeze_authority(decimals, None)
+    }
+
+    pub fn create_mint_with_freeze_authority(&self, decimals: u8, freeze_authority: Option<Pubkey>) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        self.set_mint(
+            mint,
+            Mint {
+                mint_authority: COption::Some(self.mint_authority),
+                supply: 0,
+                decimals,
+                is_initialized: true,
+                freeze_authority: freeze_authority.into(),
+            },
+        );
+        mint
+    }
+
+    // Wrapped SOL's mint, which lives at a fixed address
+    pub fn create_native_mint(&self) -> Pubkey {
+        self.set_mint(
+            spl_token::native_mint::ID,
+            Mint {
+                mint_authority: COption::None,
+                supply: 0,
+                decimals: 9,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+        );
+        spl_token::native_mint::ID
+    }
+
+    fn set_mint(&self, address: Pubkey, mint: Mint) {
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(mint, &mut data).unwrap();
+        self.svm.set_account(
+            address,
+            Account {
+                lamports: self.svm.rent().minimum_balance(Mint::LEN),
+                data,
+                owner: spl_token::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    pub fn mint(&self, address: &Pubkey) -> Mint {
+        Mint::unpack(&self.svm.account(address).expect("mint account").data).unwrap()
+    }
+
+    // Creates a token account and mints `amount` into it
+    pub fn create_token_account(&self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let address = Pubkey::new_unique();
+        let mut data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint: *mint,
+                owner: *owner,
+                amount: 0,
+                delegate: COption::None,
+                state: AccountState::Initialized,
+                is_native: COption::None,
+                delegated_amount: 0,
+                close_authority: COption::None,
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.svm.set_account(
+            address,
+            Account {
+                lamports: self.svm.rent().minimum_balance(TokenAccount::LEN),
+                data,
+                owner: spl_token::ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        self.mint_to(&address, amount);
+        address
+    }
+
+    // Mints tokens straight into a token account, keeping the mint supply in step
+    pub fn mint_to(&self, token_account: &Pubkey, amount: u64) {
+        let mut account = self.svm.account(token_account).expect("token account");
+        let mut state = TokenAccount::unpack(&account.data).unwrap();
+        state.amount += amount;
+        TokenAccount::pack(state, &mut account.data).unwrap();
+        self.svm.set_account(*token_account, account);
+
+        let mut mint = self.mint(&state.mint);
+        mint.supply += amount;
+        self.set_mint(state.mint, mint);
+    }
+
+    // Balance of a Token-2022 account, such as a position NFT holding
+    pub fn token_2022_balance(&self, token_account: &Pubkey) -> u64 {
+        self.svm.account(token_account).map_or(0, |account| {
+            StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                .unwrap()
+                .base
+                .amount
+        })
+    }
+
+    pub fn token_balance(&self, token_account: &Pubkey) -> u64 {
+        TokenAccount::unpack(&self.svm.account(token_account).expect("token account").data)
+            .unwrap()
+            .amount
+    }
+
+    pub fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self.svm.account(address).expect("account exists");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub fn pool_state(&self, pool: &Pool) -> PoolState {
+        self.fetch(&pool.state)
+    }
+
+    pub fn config(&self) -> AmmConfig {
+        self.fetch(&config_address())
+    }
+
+    // Events of type T the last transaction emitted, in order
+    pub fn events<T: AnchorDeserialize + Discriminator>(&self) -> Vec<T> {
+        self.svm
+            .data_logs()
+            .iter()
+            .filter_map(|fields| fields.first())
+            .filter_map(|data| data.strip_prefix(T::DISCRIMINATOR))
+            .map(|mut data| T::deserialize(&mut data).unwrap())
+            .collect()
+    }
+
+    // A funded wallet holding `amount_a`/`amount_b` of the pool's tokens and an empty LP account
+    pub fn user(&self, pool: &Pool, amount_a: u64, amount_b: u64) -> User {
+        let key = self.wallet();
+        User {
+            key,
+            token_a: self.create_token_account(&pool.token_a_mint, &key, amount_a),
+            token_b: self.create_token_account(&pool.token_b_mint, &key, amount_b),
+            lp: self.create_token_account(&pool.lp_mint, &key, 0),
+        }
+    }
+
+    // A funded wallet that pays and is paid in native SOL on the pool's
+    // wrapped SOL side, holding `amount` of the other token and an empty LP account
+    pub fn native_sol_user(&self, pool: &Pool, amount: u64) -> User {
+        let key = self.wallet();
+        let token_account = |mint: &Pubkey| {
+            if *mint == spl_token::native_mint::ID { key } else { self.create_token_account(mint, &key, amount) }
+        };
+        User {
+            key,
+            token_a: token_account(&pool.token_a_mint),
+            token_b: token_account(&pool.token_b_mint),
+            lp: self.create_token_account(&pool.lp_mint, &key, 0),
+        }
+    }
+
+    // ---------- config ----------
+
+    pub fn initialize_config(&self, admin: Pubkey, params: ConfigParams) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::InitializeConfig {
+                amm_config: config_address(),
+                admin,
+                program: PROGRAM_ID,
+                program_data: program_data_address(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeConfig { params }.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn update_config(&self, admin: Pubkey, params: ConfigParams) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::UpdateConfig { amm_config: config_address(), admin }.to_account_metas(None),
+            data: instruction::UpdateConfig { params }.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn add_pool_creator(&self, admin: Pubkey, creator: Pubkey) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::AddPoolCreator {
+                amm_config: config_address(),
+                pool_creator: pool_creator_address(&creator),
+                admin,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::AddPoolCreator { creator }.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn remove_pool_creator(&self, admin: Pubkey, creator: Pubkey) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::RemovePoolCreator {
+                amm_config: config_address(),
+                pool_creator: pool_creator_address(&creator),
+                admin,
+            }
+            .to_account_metas(None),
+            data: instruction::RemovePoolCreator {}.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    // ---------- pools ----------
+
+    pub fn initialize_pool_ix(&self, pool: &Pool, pool_type: u8, payer: Pubkey, allowlisted: bool) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::InitializePool {
+                amm_config: config_address(),
+                pool_creator: allowlisted.then(|| pool_creator_address(&payer)),
+                treasury: self.config().treasury,
+                pool_state: pool.state,
+                token_a_mint: pool.token_a_mint,
+                token_b_mint: pool.token_b_mint,
+                lp_mint: pool.lp_mint,
+                token_a_vault: pool.vault_a,
+                token_b_vault: pool.vault_b,
+                pool_authority: pool.authority,
+                payer,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: anchor_lang::solana_program::sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializePool { pool_type }.data(),
+        }
+    }
+
+    pub fn initialize_pool(&self, pool: &Pool, pool_type: u8, payer: Pubkey) -> std::result::Result<(), ProgramError> {
+        self.svm.process_instruction(self.initialize_pool_ix(pool, pool_type, payer, false), &[payer])
+    }
+
+    // Creates two mints and an empty pool for them, paid for by the admin
+    pub fn create_pool(&self, pool_type: u8, token_a_decimals: u8, token_b_decimals: u8) -> Pool {
+        let pool = Pool::new(self.create_mint(token_a_decimals), self.create_mint(token_b_decimals));
+        self.initialize_pool(&pool, pool_type, self.admin).unwrap();
+        pool
+    }
+
+    // Creates a pool and seeds it with a first deposit; returns the pool and its first LP
+    pub fn create_funded_pool(&self, pool_type: u8, amount_a: u64, amount_b: u64) -> (Pool, User) {
+        let pool = self.create_pool(pool_type, 6, 6);
+        let provider = self.user(&pool, amount_a, amount_b);
+        self.add_liquidity(&pool, &provider, amount_a, amount_b, None).unwrap();
+        (pool, provider)
+    }
+
+    // Passes the pool's tick account only if `with_ticks`
+    pub fn close_pool(&self, pool: &Pool, admin: Pubkey, recipient: Pubkey, with_ticks: bool) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::ClosePool {
+                amm_config: config_address(),
+                pool_state: pool.state,
+                lp_mint: pool.lp_mint,
+                pool_token_a_vault: pool.vault_a,
+                pool_token_b_vault: pool.vault_b,
+                pool_authority: pool.authority,
+                pool_ticks: with_ticks.then_some(pool.ticks),
+                farm: Pubkey::find_program_address(&[b"farm", pool.state.as_ref()], &PROGRAM_ID).0,
+                admin,
+                recipient,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ClosePool {}.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn add_liquidity_ix(&self, pool: &Pool, user: &User, amount_a: u64, amount_b: u64, deadline: Option<i64>) -> Instruction {
+        let native_sol = uses_native_sol(user);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::AddLiquidity {
+                pool_state: pool.state,
+                user_token_a: user_token(user.token_a, user),
+                user_token_b: user_token(user.token_b, user),
+                user_lp_token: user.lp,
+                pool_token_a_vault: pool.vault_a,
+                pool_token_b_vault: pool.vault_b,
+                lp_mint: pool.lp_mint,
+                pool_authority: pool.authority,
+                user_authority: user.key,
+                token_program: spl_token::ID,
+                wsol_account: native_sol.then_some(wsol_address(&user.key)),
+                native_mint: native_sol.then_some(spl_token::native_mint::ID),
+                system_program: native_sol.then_some(system_program::ID),
+            }
+            .to_account_metas(None),
+            data: instruction::AddLiquidity { amount_a, amount_b, deadline }.data(),
+        }
+    }
+
+    pub fn add_liquidity(&self, pool: &Pool, user: &User, amount_a: u64, amount_b: u64, deadline: Option<i64>) -> std::result::Result<(), ProgramError> {
+        self.svm.process_instruction(self.add_liquidity_ix(pool, user, amount_a, amount_b, deadline), &[user.key])
+    }
+
+    pub fn remove_liquidity_ix(&self, pool: &Pool, user: &User, lp_amount: u64, minimum_a_out: u64, minimum_b_out: u64, deadline: Option<i64>) -> Instruction {
+        let native_sol = uses_native_sol(user);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::RemoveLiquidity {
+                pool_state: pool.state,
+                user_token_a: user_token(user.token_a, user),
+                user_token_b: user_token(user.token_b, user),
+                user_lp_token: user.lp,
+                pool_token_a_vault: pool.vault_a,
+                pool_token_b_vault: pool.vault_b,
+                lp_mint: pool.lp_mint,
+                pool_authority: pool.authority,
+                user_authority: user.key,
+                token_program: spl_token::ID,
+                wsol_account: native_sol.then_some(wsol_address(&user.key)),
+                native_mint: native_sol.then_some(spl_token::native_mint::ID),
+                system_program: native_sol.then_some(system_program::ID),
+            }
+            .to_account_metas(None),
+            data: instruction::RemoveLiquidity { lp_amount, minimum_a_out, minimum_b_out, deadline }.data(),
+        }
+    }
+
+    pub fn remove_liquidity(&self, pool: &Pool, user: &User, lp_amount: u64, minimum_a_out: u64, minimum_b_out: u64, deadline: Option<i64>) -> std::result::Result<(), ProgramError> {
+        self.svm.process_instruction(
+            self.remove_liquidity_ix(pool, user, lp_amount, minimum_a_out, minimum_b_out, deadline),
+            &[user.key],
+        )
+    }
+
+    fn swap_accounts(pool: &Pool, user: &User, referrer: Option<Pubkey>) -> Vec<AccountMeta> {
+        let native_sol = uses_native_sol(user);
+        accounts::Swap {
+            pool_state: pool.state,
+            user_token_a: user_token(user.token_a, user),
+            user_token_b: user_token(user.token_b, user),
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            pool_authority: pool.authority,
+            user_authority: user.key,
+            token_program: spl_token::ID,
+            pool_ticks: pool.ticks,
+            amm_config: config_address(),
+            referrer_token_account: referrer,
+            wsol_account: native_sol.then_some(wsol_address(&user.key)),
+            native_mint: native_sol.then_some(spl_token::native_mint::ID),
+            system_program: native_sol.then_some(system_program::ID),
+        }
+        .to_account_metas(None)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap_ix(
+        &self,
+        pool: &Pool,
+        user: &User,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        a_to_b: bool,
+        callback_data: Option<Vec<u8>>,
+        deadline: Option<i64>,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: Self::swap_accounts(pool, user, None),
+            data: instruction::Swap {
+                amount_in,
+                minimum_amount_out,
+                a_to_b,
+                callback_data,
+                deadline,
+                referral_fee_bps: 0,
+                max_price_impact_bps: None,
+                sqrt_price_limit_x64: None,
+            }
+            .data(),
+        }
+    }
+
+    pub fn swap(&self, pool: &Pool, user: &User, amount_in: u64, minimum_amount_out: u64, a_to_b: bool) -> std::result::Result<(), ProgramError> {
+        self.svm.process_instruction(
+            self.swap_ix(pool, user, amount_in, minimum_amount_out, a_to_b, None, None),
+            &[user.key],
+        )
+    }
+
+    // A swap paying `referral_fee_bps` of its fee to the `referrer` token account
+    pub fn swap_with_referral(
+        &self,
+        pool: &Pool,
+        user: &User,
+        amount_in: u64,
+        a_to_b: bool,
+        referrer: Option<Pubkey>,
+        referral_fee_bps: u16,
+    ) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: Self::swap_accounts(pool, user, referrer),
+            data: instruction::Swap {
+                amount_in,
+                minimum_amount_out: 0,
+                a_to_b,
+                callback_data: None,
+                deadline: None,
+                referral_fee_bps,
+                max_price_impact_bps: None,
+                sqrt_price_limit_x64: None,
+            }
+            .data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    // A swap guarded by a maximum price impact and a price limit
+    pub fn swap_with_limits(
+        &self,
+        pool: &Pool,
+        user: &User,
+        amount_in: u64,
+        a_to_b: bool,
+        max_price_impact_bps: Option<u16>,
+        sqrt_price_limit_x64: Option<u128>,
+    ) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: Self::swap_accounts(pool, user, None),
+            data: instruction::Swap {
+                amount_in,
+                minimum_amount_out: 0,
+                a_to_b,
+                callback_data: None,
+                deadline: None,
+                referral_fee_bps: 0,
+                max_price_impact_bps,
+                sqrt_price_limit_x64,
+            }
+            .data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    // A batch swap over `pools`, each with the trader's token accounts for
+    // it; every User must share the same wallet
+    pub fn batch_swap(&self, pools: &[(&Pool, &User)], legs: Vec<SwapLeg>) -> std::result::Result<(), ProgramError> {
+        let trader = pools[0].1.key;
+        let mut accounts = accounts::BatchSwap {
+            user_authority: trader,
+            token_program: spl_token::ID,
+            amm_config: config_address(),
+            amm_program: PROGRAM_ID,
+        }
+        .to_account_metas(None);
+        for (pool, user) in pools {
+            accounts.extend([
+                AccountMeta::new(pool.state, false),
+                AccountMeta::new(user.token_a, false),
+                AccountMeta::new(user.token_b, false),
+                AccountMeta::new(pool.vault_a, false),
+                AccountMeta::new(pool.vault_b, false),
+                AccountMeta::new_readonly(pool.authority, false),
+                AccountMeta::new(pool.ticks, false),
+            ]);
+        }
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: instruction::BatchSwap { legs, deadline: None }.data(),
+        };
+        self.svm.process_instruction(ix, &[trader])
+    }
+
+    pub fn sync(&self, pool: &Pool) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::SyncReserves {
+                pool_state: pool.state,
+                pool_token_a_vault: pool.vault_a,
+                pool_token_b_vault: pool.vault_b,
+            }
+            .to_account_metas(None),
+            data: instruction::Sync {}.data(),
+        };
+        self.svm.process_instruction(ix, &[])
+    }
+
+    pub fn skim(&self, pool: &Pool, recipient: &User) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::Skim {
+                pool_state: pool.state,
+                pool_token_a_vault: pool.vault_a,
+                pool_token_b_vault: pool.vault_b,
+                recipient_token_a: recipient.token_a,
+                recipient_token_b: recipient.token_b,
+                pool_authority: pool.authority,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::Skim {}.data(),
+        };
+        self.svm.process_instruction(ix, &[])
+    }
+
+    pub fn collect_creator_fees(&self, pool: &Pool, creator: &User) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::CollectCreatorFees {
+                pool_state: pool.state,
+                pool_token_a_vault: pool.vault_a,
+                pool_token_b_vault: pool.vault_b,
+                creator_token_a: creator.token_a,
+                creator_token_b: creator.token_b,
+                pool_authority: pool.authority,
+                creator: creator.key,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CollectCreatorFees {}.data(),
+        };
+        self.svm.process_instruction(ix, &[creator.key])
+    }
+
+    // ---------- ranged positions ----------
+
+    pub fn open_position(
+        &self,
+        pool: &Pool,
+        position: &PositionAccounts,
+        owner: Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::OpenPosition {
+                pool_state: pool.state,
+                pool_ticks: pool.ticks,
+                position: position.address,
+                position_mint: position.mint,
+                position_token_account: position.token_account(&owner),
+                pool_authority: pool.authority,
+                owner,
+                system_program: system_program::ID,
+                token_2022_program: spl_token_2022::ID,
+                associated_token_program: spl_associated_token_account::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::OpenPosition { tick_lower, tick_upper }.data(),
+        };
+        self.svm.process_instruction(ix, &[owner, position.mint])
+    }
+
+    fn modify_position_accounts(&self, pool: &Pool, position: &PositionAccounts, user: &User) -> Vec<AccountMeta> {
+        accounts::ModifyPosition {
+            pool_state: pool.state,
+            pool_ticks: pool.ticks,
+            position: position.address,
+            position_token_account: position.token_account(&user.key),
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            pool_authority: pool.authority,
+            owner: user.key,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None)
+    }
+
+    pub fn increase_liquidity(
+        &self,
+        pool: &Pool,
+        position: &PositionAccounts,
+        user: &User,
+        amount_a_max: u64,
+        amount_b_max: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: self.modify_position_accounts(pool, position, user),
+            data: instruction::IncreaseLiquidity { amount_a_max, amount_b_max, deadline: None }.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    pub fn decrease_liquidity(
+        &self,
+        pool: &Pool,
+        position: &PositionAccounts,
+        user: &User,
+        liquidity: u128,
+        minimum_a_out: u64,
+        minimum_b_out: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: self.modify_position_accounts(pool, position, user),
+            data: instruction::DecreaseLiquidity { liquidity, minimum_a_out, minimum_b_out, deadline: None }.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    pub fn collect_fees(&self, pool: &Pool, position: &PositionAccounts, user: &User) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: self.modify_position_accounts(pool, position, user),
+            data: instruction::CollectFees {}.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    // ---------- limit orders ----------
+
+    pub fn place_limit_order(
+        &self,
+        pool: &Pool,
+        user: &User,
+        tick: i32,
+        sells_a: bool,
+        amount: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::PlaceLimitOrder {
+                pool_state: pool.state,
+                pool_ticks: pool.ticks,
+                limit_order: pool.limit_order(&user.key, tick),
+                user_token_a: user.token_a,
+                user_token_b: user.token_b,
+                pool_token_a_vault: pool.vault_a,
+                pool_token_b_vault: pool.vault_b,
+                owner: user.key,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::PlaceLimitOrder { tick, sells_a, amount }.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    fn settle_limit_order_accounts(&self, pool: &Pool, user: &User, tick: i32) -> Vec<AccountMeta> {
+        accounts::SettleLimitOrder {
+            pool_state: pool.state,
+            pool_ticks: pool.ticks,
+            limit_order: pool.limit_order(&user.key, tick),
+            user_token_a: user.token_a,
+            user_token_b: user.token_b,
+            pool_token_a_vault: pool.vault_a,
+            pool_token_b_vault: pool.vault_b,
+            pool_authority: pool.authority,
+            owner: user.key,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None)
+    }
+
+    pub fn claim_filled_order(&self, pool: &Pool, user: &User, tick: i32) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: self.settle_limit_order_accounts(pool, user, tick),
+            data: instruction::ClaimFilledOrder {}.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    pub fn cancel_limit_order(&self, pool: &Pool, user: &User, tick: i32) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: self.settle_limit_order_accounts(pool, user, tick),
+            data: instruction::CancelLimitOrder {}.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    // Moves a position NFT to another wallet's associated account
+    pub fn transfer_position(&self, position: &PositionAccounts, from: Pubkey, to: Pubkey) -> std::result::Result<(), ProgramError> {
+        let create_account = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &from,
+            &to,
+            &position.mint,
+            &spl_token_2022::ID,
+        );
+        let transfer = spl_token_2022::instruction::transfer_checked(
+            &spl_token_2022::ID,
+            &position.token_account(&from),
+            &position.mint,
+            &position.token_account(&to),
+            &from,
+            &[],
+            1,
+            0,
+        )?;
+        self.svm.process_transaction(&[create_account, transfer], &[from])
+    }
+
+    // ---------- farms ----------
+
+    pub fn create_farm(
+        &self,
+        farm: &FarmAccounts,
+        admin: Pubkey,
+        admin_reward_token: Pubkey,
+        start_time: i64,
+        end_time: i64,
+        emission_rate: u64,
+    ) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::CreateFarm {
+                amm_config: config_address(),
+                pool_state: farm.pool,
+                lp_mint: farm.lp_mint,
+                reward_mint: farm.reward_mint,
+                farm: farm.address,
+                stake_vault: farm.stake_vault,
+                reward_vault: farm.reward_vault,
+                admin_reward_token,
+                admin,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: anchor_lang::solana_program::sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CreateFarm { start_time, end_time, emission_rate }.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn fund_farm(&self, farm: &FarmAccounts, admin: Pubkey, admin_reward_token: Pubkey, amount: u64) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::FundFarm {
+                amm_config: config_address(),
+                farm: farm.address,
+                reward_vault: farm.reward_vault,
+                admin_reward_token,
+                admin,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::FundFarm { amount }.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn extend_farm(&self, farm: &FarmAccounts, admin: Pubkey, end_time: i64, emission_rate: u64) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::ExtendFarm {
+                amm_config: config_address(),
+                farm: farm.address,
+                reward_vault: farm.reward_vault,
+                admin,
+            }
+            .to_account_metas(None),
+            data: instruction::ExtendFarm { end_time, emission_rate }.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn reclaim_farm_rewards(&self, farm: &FarmAccounts, admin: Pubkey, admin_reward_token: Pubkey) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::ReclaimFarmRewards {
+                amm_config: config_address(),
+                farm: farm.address,
+                reward_vault: farm.reward_vault,
+                admin_reward_token,
+                admin,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ReclaimFarmRewards {}.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn close_farm(&self, farm: &FarmAccounts, admin: Pubkey, admin_reward_token: Pubkey, recipient: Pubkey) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::CloseFarm {
+                amm_config: config_address(),
+                farm: farm.address,
+                lp_mint: farm.lp_mint,
+                stake_vault: farm.stake_vault,
+                reward_vault: farm.reward_vault,
+                admin_reward_token,
+                admin,
+                recipient,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::CloseFarm {}.data(),
+        };
+        self.svm.process_instruction(ix, &[admin])
+    }
+
+    pub fn stake_lp(&self, farm: &FarmAccounts, user: &User, amount: u64) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::StakeLp {
+                farm: farm.address,
+                stake_position: farm.stake_position(&user.key),
+                stake_vault: farm.stake_vault,
+                user_lp_token: user.lp,
+                owner: user.key,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::StakeLp { amount }.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    pub fn unstake_lp(&self, farm: &FarmAccounts, user: &User, amount: u64) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::UnstakeLp {
+                farm: farm.address,
+                stake_position: farm.stake_position(&user.key),
+                stake_vault: farm.stake_vault,
+                user_lp_token: user.lp,
+                owner: user.key,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::UnstakeLp { amount }.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    pub fn claim_rewards(&self, farm: &FarmAccounts, user: &User, user_reward_token: Pubkey) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::ClaimRewards {
+                farm: farm.address,
+                stake_position: farm.stake_position(&user.key),
+                reward_vault: farm.reward_vault,
+                user_reward_token,
+                owner: user.key,
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::ClaimRewards {}.data(),
+        };
+        self.svm.process_instruction(ix, &[user.key])
+    }
+
+    pub fn migrate_pool(&self, pool: &Pool, payer: Pubkey) -> std::result::Result<(), ProgramError> {
+        let ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts::MigratePool {
+                pool_state: pool.state,
+                token_a_mint: pool.token_a_mint,
+                token_b_mint: pool.token_b_mint,
+                token_a_vault: pool.vault_a,
+                token_b_vault: pool.vault_b,
+                payer,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::MigratePool {}.data(),
+        };
+        self.svm.process_instruction(ix, &[payer])
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,395 @@
+// The integration tests' runtime: a solana-program-test bank with the AMM
+// deployed through the upgradeable loader.
+//
+// Under `cargo test-sbf` the bank runs the built
+// `solana_amm_educational_template.so`, with the SBF compute, stack and heap
+// limits. Under plain `cargo test` it runs the program's entrypoint natively
+// instead. Either way transactions go through the real runtime: signatures,
+// account ownership and privileges, CPI and re-entrancy rules, rent and
+// rollback of failed transactions.
+
+// solana_program 2.x deprecates its loader and system program modules in
+// favour of the interface crates, which anchor_lang doesn't re-export
+#![allow(deprecated)]
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{self, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_program_test::{processor, tokio::runtime::Runtime, ProgramTest, ProgramTestContext};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+pub use solana_program_runtime::invoke_context::BuiltinFunctionWithContext;
+pub use solana_sdk::account::Account;
+
+const PROGRAM_NAME: &str = "solana_amm_educational_template";
+const PROGRAM_ID: Pubkey = solana_amm_educational_template::ID;
+
+// Transactions ask for the most compute a transaction can have
+const COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+pub struct Svm {
+    runtime: Runtime,
+    context: RefCell<ProgramTestContext>,
+    signers: RefCell<HashMap<Pubkey, Keypair>>,
+    upgrade_authority: Pubkey,
+    logs: RefCell<Vec<String>>,
+    return_data: RefCell<Option<(Pubkey, Vec<u8>)>>,
+    transactions: Cell<u64>,
+}
+
+impl Svm {
+    // Starts a bank with the AMM deployed, a fresh upgrade authority for it,
+    // and `programs` as extra native programs, given as `processor!(entrypoint)`
+    pub fn new(programs: &[(Pubkey, Option<BuiltinFunctionWithContext>)]) -> Self {
+        let upgrade_authority = Keypair::new();
+        let mut program_test = ProgramTest::default();
+        // ProgramTest turns on the runtime's debug logs; RUST_LOG still can
+        solana_logger::setup_with_default("error");
+
+        let elf = if std::env::var_os("BPF_OUT_DIR").is_some() || std::env::var_os("SBF_OUT_DIR").is_some() {
+            let path = solana_program_test::find_file(&format!("{PROGRAM_NAME}.so"))
+                .unwrap_or_else(|| panic!("{PROGRAM_NAME}.so not found, build it with `cargo build-sbf`"));
+            solana_program_test::read_file(path)
+        } else {
+            let builtin: Option<BuiltinFunctionWithContext> = processor!(upgradeable_programs);
+            program_test.add_builtin_program("upgradeable_programs", bpf_loader_upgradeable::ID, builtin.unwrap());
+            Vec::new()
+        };
+        add_upgradeable_program(&mut program_test, elf, upgrade_authority.pubkey());
+        for (program_id, builtin) in programs {
+            program_test.add_builtin_program("test_program", *program_id, builtin.unwrap());
+        }
+
+        let runtime = solana_program_test::tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let context = runtime.block_on(program_test.start_with_context());
+
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            let inner = program_stubs::set_syscall_stubs(Box::new(Unset));
+            program_stubs::set_syscall_stubs(Box::new(LogDataStubs(inner)));
+        });
+
+        let upgrade_authority_key = upgrade_authority.pubkey();
+        Self {
+            runtime,
+            context: RefCell::new(context),
+            signers: RefCell::new(HashMap::from([(upgrade_authority_key, upgrade_authority)])),
+            upgrade_authority: upgrade_authority_key,
+            logs: RefCell::default(),
+            return_data: RefCell::default(),
+            transactions: Cell::new(0),
+        }
+    }
+
+    // Signs for the AMM's upgrades, which is what makes a wallet its admin
+    pub fn upgrade_authority(&self) -> Pubkey {
+        self.upgrade_authority
+    }
+
+    // A new keypair the runtime can sign transactions with
+    pub fn new_signer(&self) -> Pubkey {
+        let keypair = Keypair::new();
+        let key = keypair.pubkey();
+        self.signers.borrow_mut().insert(key, keypair);
+        key
+    }
+
+    pub fn set_account(&self, address: Pubkey, account: Account) {
+        self.context.borrow_mut().set_account(&address, &account.into());
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Option<Account> {
+        let banks_client = self.context.borrow().banks_client.clone();
+        self.runtime.block_on(banks_client.get_account(*address)).unwrap()
+    }
+
+    pub fn lamports(&self, address: &Pubkey) -> u64 {
+        self.account(address).map_or(0, |account| account.lamports)
+    }
+
+    pub fn airdrop(&self, address: &Pubkey, lamports: u64) {
+        let mut account = self
+            .account(address)
+            .unwrap_or_else(|| Account { owner: system_program::ID, ..Account::default() });
+        account.lamports += lamports;
+        self.set_account(*address, account);
+    }
+
+    pub fn rent(&self) -> Rent {
+        let banks_client = self.context.borrow().banks_client.clone();
+        self.runtime.block_on(banks_client.get_rent()).unwrap()
+    }
+
+    // The warps only move the Clock sysvar the program reads. Warping the
+    // bank itself would also hash every account.
+    pub fn warp_to_timestamp(&self, unix_timestamp: i64) {
+        self.set_clock(Clock { unix_timestamp, ..self.clock() });
+    }
+
+    pub fn warp_to_slot(&self, slot: u64) {
+        self.set_clock(Clock { slot, ..self.clock() });
+    }
+
+    pub fn clock(&self) -> Clock {
+        let banks_client = self.context.borrow().banks_client.clone();
+        self.runtime.block_on(banks_client.get_sysvar::<Clock>()).unwrap()
+    }
+
+    pub fn set_clock(&self, clock: Clock) {
+        self.context.borrow().set_sysvar(&clock);
+    }
+
+    // Program logs of the last transaction
+    pub fn logs(&self) -> Vec<String> {
+        self.logs.borrow().clone()
+    }
+
+    // `sol_log_data` payloads (Anchor events) of the last transaction
+    pub fn data_logs(&self) -> Vec<Vec<Vec<u8>>> {
+        self.logs
+            .borrow()
+            .iter()
+            .filter_map(|line| line.strip_prefix("Program data: "))
+            .map(|fields| fields.split(' ').map(|field| STANDARD.decode(field).unwrap()).collect())
+            .collect()
+    }
+
+    // Return data of the last transaction
+    pub fn return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.return_data.borrow().clone()
+    }
+
+    // Runs the instructions in order as one transaction, paid for by the
+    // bank's payer and signed by `signers`
+    pub fn process_transaction(&self, instructions: &[Instruction], signers: &[Pubkey]) -> Result<(), ProgramError> {
+        self.logs.borrow_mut().clear();
+        *self.return_data.borrow_mut() = None;
+
+        // A transaction without one of its signatures never gets to run
+        let known_signers = self.signers.borrow();
+        let mut keypairs = Vec::new();
+        for key in instructions.iter().flat_map(|ix| &ix.accounts).filter(|meta| meta.is_signer).map(|meta| meta.pubkey) {
+            match known_signers.get(&key).filter(|_| signers.contains(&key)) {
+                Some(keypair) if !keypairs.iter().any(|signer: &&Keypair| signer.pubkey() == key) => keypairs.push(keypair),
+                Some(_) => {}
+                None => return Err(ProgramError::MissingRequiredSignature),
+            }
+        }
+
+        // The increasing compute unit price keeps otherwise identical
+        // transactions from sharing a signature
+        let transactions = self.transactions.get() + 1;
+        self.transactions.set(transactions);
+        let compute_budget = [
+            ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT),
+            ComputeBudgetInstruction::set_compute_unit_price(transactions),
+        ];
+
+        let context = self.context.borrow();
+        let banks_client = context.banks_client.clone();
+        let blockhash = self.runtime.block_on(banks_client.get_latest_blockhash()).unwrap();
+        keypairs.insert(0, &context.payer);
+        let transaction = Transaction::new_signed_with_payer(
+            &[&compute_budget[..], instructions].concat(),
+            Some(&context.payer.pubkey()),
+            &keypairs,
+            blockhash,
+        );
+        let outcome = self.runtime.block_on(banks_client.process_transaction_with_metadata(transaction)).unwrap();
+
+        if let Some(metadata) = outcome.metadata {
+            *self.logs.borrow_mut() = metadata
+                .log_messages
+                .into_iter()
+                .map(|line| match line.strip_prefix("Program log: ") {
+                    Some(data) if data.starts_with("Program data: ") => data.to_owned(),
+                    _ => line,
+                })
+                .collect();
+            *self.return_data.borrow_mut() = metadata.return_data.map(|data| (data.program_id, data.data));
+        }
+        if std::env::var_os("SVM_LOG").is_some() {
+            for line in self.logs() {
+                println!("{line}");
+            }
+        }
+
+        match outcome.result {
+            Ok(()) => Ok(()),
+            Err(TransactionError::InstructionError(_, error)) => {
+                Err(ProgramError::try_from(error.clone()).unwrap_or_else(|_| panic!("transaction failed: {error}")))
+            }
+            Err(error) => panic!("transaction failed: {error}"),
+        }
+    }
+
+    pub fn process_instruction(&self, ix: Instruction, signers: &[Pubkey]) -> Result<(), ProgramError> {
+        self.process_transaction(&[ix], signers)
+    }
+}
+
+impl Default for Svm {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+// Deploys the AMM the way `solana program deploy` does: a program account
+// pointing at a program data account that holds the ELF and the upgrade
+// authority
+fn add_upgradeable_program(program_test: &mut ProgramTest, elf: Vec<u8>, upgrade_authority: Pubkey) {
+    let rent = Rent::default();
+    let (programdata_address, _) = Pubkey::find_program_address(&[PROGRAM_ID.as_ref()], &bpf_loader_upgradeable::ID);
+
+    let program = bincode::serialize(&UpgradeableLoaderState::Program { programdata_address }).unwrap();
+    let mut program_data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(upgrade_authority),
+    })
+    .unwrap();
+    program_data.extend_from_slice(&elf);
+
+    program_test.add_account(
+        PROGRAM_ID,
+        Account {
+            lamports: rent.minimum_balance(program.len()),
+            data: program,
+            owner: bpf_loader_upgradeable::ID,
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+    program_test.add_account(
+        programdata_address,
+        Account {
+            lamports: rent.minimum_balance(program_data.len()),
+            data: program_data,
+            owner: bpf_loader_upgradeable::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+// ========== NATIVE EXECUTION ==========
+
+// The runtime hands programs owned by the upgradeable loader to the loader's
+// builtin. Natively there is no ELF for it to run, so in its place this runs
+// the native entrypoints of the upgradeable programs the tests use: the AMM
+// and Token-2022.
+fn upgradeable_programs(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if *program_id == PROGRAM_ID {
+        // Anchor's entrypoint wants the slice to live as long as the accounts
+        let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+        solana_amm_educational_template::entry(program_id, accounts, data)
+    } else if *program_id == spl_token_2022::ID {
+        spl_token_2022::processor::Processor::process(program_id, accounts, data)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
+
+// solana-program-test's syscall stubs don't put `sol_log_data` in the
+// transaction logs, so natively run programs log their events through
+// `sol_log` instead, which `process_transaction` unwraps again
+struct LogDataStubs(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for LogDataStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+
+    fn sol_log_compute_units(&self) {
+        self.0.sol_log_compute_units()
+    }
+
+    fn sol_remaining_compute_units(&self) -> u64 {
+        self.0.sol_remaining_compute_units()
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0.sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+
+    fn sol_get_sysvar(&self, sysvar_id_addr: *const u8, var_addr: *mut u8, offset: u64, length: u64) -> u64 {
+        self.0.sol_get_sysvar(sysvar_id_addr, var_addr, offset, length)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_last_restart_slot(var_addr)
+    }
+
+    fn sol_get_epoch_stake(&self, vote_address: *const u8) -> u64 {
+        self.0.sol_get_epoch_stake(vote_address)
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields = fields.iter().map(|field| STANDARD.encode(field)).collect::<Vec<_>>();
+        self.0.sol_log(&format!("Program data: {}", fields.join(" ")))
+    }
+
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+// Installed for the moment it takes to wrap program-test's stubs
+struct Unset;
+
+impl SyscallStubs for Unset {}
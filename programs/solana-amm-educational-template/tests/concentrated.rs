@@ -0,0 +1,412 @@
+mod common;
+
+use common::*;
+use anchor_spl::token_interface::spl_token_metadata_interface::state::TokenMetadata;
+use solana_amm_educational_template::{
+    sqrt_price_at_tick, ConfigParams, PoolTicks, Position, SwapError, SwapExecuted, MAX_POOL_TICKS,
+    MIN_TICK_LIQUIDITY, POSITION_NFT_NAME, POSITION_NFT_SYMBOL,
+};
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+const DEPOSIT: u64 = 100_000_000;
+
+// A concentrated pool at price 1 and a wallet holding DEPOSIT of each token
+fn setup() -> (TestEnv, Pool, User) {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(2, LIQUIDITY, LIQUIDITY);
+    let owner = env.user(&pool, DEPOSIT, DEPOSIT);
+    (env, pool, owner)
+}
+
+// Opens a position over [tick_lower, tick_upper) and funds it with up to DEPOSIT of each token
+fn open_funded(env: &TestEnv, pool: &Pool, owner: &User, tick_lower: i32, tick_upper: i32) -> PositionAccounts {
+    let position = env.position();
+    env.open_position(pool, &position, owner.key, tick_lower, tick_upper).unwrap();
+    env.increase_liquidity(pool, &position, owner, DEPOSIT, DEPOSIT).unwrap();
+    position
+}
+
+// Vaults hold exactly the reserves, the positions' tokens and fees and the
+// creator fees
+fn assert_vaults_balance(env: &TestEnv, pool: &Pool) {
+    let state = env.pool_state(pool);
+    assert_eq!(
+        env.token_balance(&pool.vault_a),
+        state.reserve_a + state.position_reserve_a + state.position_fees_a + state.creator_fees_a
+    );
+    assert_eq!(
+        env.token_balance(&pool.vault_b),
+        state.reserve_b + state.position_reserve_b + state.position_fees_b + state.creator_fees_b
+    );
+}
+
+#[test]
+fn open_position_mints_a_one_of_one_nft() {
+    let (env, pool, owner) = setup();
+    let position = env.position();
+    env.open_position(&pool, &position, owner.key, -1_000, 1_000).unwrap();
+
+    assert_eq!(env.token_2022_balance(&position.token_account(&owner.key)), 1);
+    let account = env.svm.account(&position.mint).unwrap();
+    assert_eq!(account.owner, spl_token_2022::ID);
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).unwrap();
+    assert_eq!((mint.base.supply, mint.base.decimals), (1, 0));
+    assert!(mint.base.mint_authority.is_none());
+    let metadata = mint.get_variable_len_extension::<TokenMetadata>().unwrap();
+    assert_eq!((metadata.name.as_str(), metadata.symbol.as_str()), (POSITION_NFT_NAME, POSITION_NFT_SYMBOL));
+    assert_eq!(metadata.mint, position.mint);
+
+    let state: Position = env.fetch(&position.address);
+    assert_eq!((state.pool, state.position_mint), (pool.state, position.mint));
+    assert_eq!((state.tick_lower, state.tick_upper, state.liquidity), (-1_000, 1_000, 0));
+
+    // The first position starts tick pricing at the reserves' price
+    let pool_state = env.pool_state(&pool);
+    assert_eq!((pool_state.sqrt_price_x64, pool_state.tick_current), (1 << 64, 0));
+    let ticks: PoolTicks = env.fetch(&pool.ticks);
+    assert_eq!(ticks.pool, pool.state);
+    assert!(ticks.ticks.is_empty());
+}
+
+#[test]
+fn open_position_checks_the_range() {
+    let (env, pool, owner) = setup();
+    for (lower, upper) in [(-1_005, 1_000), (1_000, 1_000), (1_000, -1_000), (-443_640, 0), (0, 443_640)] {
+        assert_swap_error(
+            env.open_position(&pool, &env.position(), owner.key, lower, upper),
+            SwapError::InvalidTickRange,
+        );
+    }
+
+    let (standard, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    assert_swap_error(
+        env.open_position(&standard, &env.position(), owner.key, -1_000, 1_000),
+        SwapError::InvalidPoolType,
+    );
+}
+
+#[test]
+fn ranged_liquidity_deepens_the_pool_in_range() {
+    let (env, pool, owner) = setup();
+    let (plain, _) = env.create_funded_pool(2, LIQUIDITY, LIQUIDITY);
+    let position = open_funded(&env, &pool, &owner, -1_000, 1_000);
+
+    // In range at price 1, the position takes equal amounts of both tokens
+    let state = env.pool_state(&pool);
+    assert_eq!(state.position_reserve_a, state.position_reserve_b);
+    assert_eq!(state.liquidity, env.fetch::<Position>(&position.address).liquidity);
+    assert!(state.position_reserve_a > DEPOSIT - 2);
+
+    let trader = env.user(&pool, 10_000_000, 0);
+    let plain_trader = env.user(&plain, 10_000_000, 0);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+    env.swap(&plain, &plain_trader, 10_000_000, 0, true).unwrap();
+
+    // Same trade, less slippage with the position's depth behind it
+    let ranged_out = env.token_balance(&trader.token_b);
+    let plain_out = env.token_balance(&plain_trader.token_b);
+    assert!(ranged_out > plain_out, "{ranged_out} <= {plain_out}");
+    assert!(ranged_out < 10_000_000);
+
+    let state = env.pool_state(&pool);
+    assert!(state.tick_current < 0 && state.tick_current > -1_000);
+    assert!(state.position_reserve_a > DEPOSIT && state.position_reserve_b < DEPOSIT);
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn crossing_a_range_leaves_the_position_in_one_token() {
+    let (env, pool, owner) = setup();
+    // Above the current price the position holds only token A
+    let position = open_funded(&env, &pool, &owner, 100, 1_000);
+    let state = env.pool_state(&pool);
+    assert_eq!(state.position_reserve_b, 0);
+    assert_eq!(state.liquidity, 0);
+
+    // Buying token A pushes the price through the whole range
+    let trader = env.user(&pool, 0, 300_000_000);
+    env.swap(&pool, &trader, 300_000_000, 0, false).unwrap();
+    let state = env.pool_state(&pool);
+    assert!(state.tick_current >= 1_000, "tick {}", state.tick_current);
+    assert_eq!(state.liquidity, 0);
+    assert_vaults_balance(&env, &pool);
+
+    // Now the position is all token B
+    let liquidity = env.fetch::<Position>(&position.address).liquidity;
+    let (a_before, b_before) = (env.token_balance(&owner.token_a), env.token_balance(&owner.token_b));
+    env.decrease_liquidity(&pool, &position, &owner, liquidity, 0, 0).unwrap();
+    assert_eq!(env.token_balance(&owner.token_a), a_before);
+    assert!(env.token_balance(&owner.token_b) > b_before + DEPOSIT);
+    assert!(env.fetch::<PoolTicks>(&pool.ticks).ticks.is_empty());
+}
+
+#[test]
+fn only_the_nft_holder_controls_the_position() {
+    let (env, pool, alice) = setup();
+    let position = open_funded(&env, &pool, &alice, -1_000, 1_000);
+    let bob = env.user(&pool, DEPOSIT, DEPOSIT);
+
+    // Without the NFT, bob's own (empty) account doesn't count
+    assert!(env.decrease_liquidity(&pool, &position, &bob, 1, 0, 0).is_err());
+
+    env.transfer_position(&position, alice.key, bob.key).unwrap();
+    assert_eq!(env.token_2022_balance(&position.token_account(&alice.key)), 0);
+    assert_eq!(env.token_2022_balance(&position.token_account(&bob.key)), 1);
+
+    // The original owner lost control along with the NFT
+    assert_swap_error(
+        env.increase_liquidity(&pool, &position, &alice, DEPOSIT, DEPOSIT),
+        SwapError::NotPositionHolder,
+    );
+    assert_swap_error(
+        env.decrease_liquidity(&pool, &position, &alice, 1, 0, 0),
+        SwapError::NotPositionHolder,
+    );
+    assert_swap_error(env.collect_fees(&pool, &position, &alice), SwapError::NotPositionHolder);
+
+    let liquidity = env.fetch::<Position>(&position.address).liquidity;
+    env.decrease_liquidity(&pool, &position, &bob, liquidity, 0, 0).unwrap();
+    assert!(env.token_balance(&bob.token_a) > DEPOSIT);
+    assert!(env.token_balance(&bob.token_b) > DEPOSIT);
+}
+
+#[test]
+fn decrease_returns_the_deposit() {
+    let (env, pool, owner) = setup();
+    let position = open_funded(&env, &pool, &owner, -2_000, 500);
+    let liquidity = env.fetch::<Position>(&position.address).liquidity;
+    let (paid_a, paid_b) = (DEPOSIT - env.token_balance(&owner.token_a), DEPOSIT - env.token_balance(&owner.token_b));
+
+    assert_swap_error(
+        env.decrease_liquidity(&pool, &position, &owner, liquidity + 1, 0, 0),
+        SwapError::InsufficientPositionLiquidity,
+    );
+    assert_swap_error(
+        env.decrease_liquidity(&pool, &position, &owner, liquidity, paid_a + 1, 0),
+        SwapError::SlippageExceeded,
+    );
+
+    env.decrease_liquidity(&pool, &position, &owner, liquidity, 0, 0).unwrap();
+    // Rounding only ever favours the pool
+    let (got_a, got_b) = (env.token_balance(&owner.token_a) + paid_a - DEPOSIT, env.token_balance(&owner.token_b) + paid_b - DEPOSIT);
+    assert!(got_a <= paid_a && got_a + 1 >= paid_a);
+    assert!(got_b <= paid_b && got_b + 1 >= paid_b);
+    assert_eq!(env.pool_state(&pool).liquidity, 0);
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn sync_and_skim_leave_position_tokens_alone() {
+    let (env, pool, owner) = setup();
+    open_funded(&env, &pool, &owner, -1_000, 1_000);
+    let before = env.pool_state(&pool);
+
+    let recipient = env.user(&pool, 0, 0);
+    env.skim(&pool, &recipient).unwrap();
+    assert_eq!((env.token_balance(&recipient.token_a), env.token_balance(&recipient.token_b)), (0, 0));
+    env.sync(&pool).unwrap();
+    let after = env.pool_state(&pool);
+    assert_eq!((after.reserve_a, after.reserve_b), (before.reserve_a, before.reserve_b));
+}
+
+#[test]
+fn collect_fees_pays_the_range_its_share() {
+    let (env, pool, owner) = setup();
+    let position = open_funded(&env, &pool, &owner, -1_000, 1_000);
+    let liquidity = env.fetch::<Position>(&position.address).liquidity;
+    let trader = env.user(&pool, 10_000_000, 10_000_000);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+    env.swap(&pool, &trader, 10_000_000, 0, false).unwrap();
+
+    // Both directions paid fees, shared between the reserves and the range
+    let state = env.pool_state(&pool);
+    assert!(state.position_fees_a > 0 && state.position_fees_a < 50_000);
+    assert!(state.position_fees_b > 0 && state.position_fees_b < 50_000);
+    assert_vaults_balance(&env, &pool);
+
+    let (a_before, b_before) = (env.token_balance(&owner.token_a), env.token_balance(&owner.token_b));
+    env.collect_fees(&pool, &position, &owner).unwrap();
+    let (fees_a, fees_b) = (env.token_balance(&owner.token_a) - a_before, env.token_balance(&owner.token_b) - b_before);
+    // The only position gets everything but the rounding
+    assert!(fees_a <= state.position_fees_a && fees_a + 2 >= state.position_fees_a);
+    assert!(fees_b <= state.position_fees_b && fees_b + 2 >= state.position_fees_b);
+
+    let after: Position = env.fetch(&position.address);
+    assert_eq!((after.liquidity, after.tokens_owed_a, after.tokens_owed_b), (liquidity, 0, 0));
+    assert_vaults_balance(&env, &pool);
+
+    // Nothing more until the next swap
+    env.collect_fees(&pool, &position, &owner).unwrap();
+    assert_eq!(env.token_balance(&owner.token_a), a_before + fees_a);
+    assert_eq!(env.token_balance(&owner.token_b), b_before + fees_b);
+}
+
+#[test]
+fn referral_fee_comes_out_of_the_lp_fee() {
+    let (env, pool, owner) = setup();
+    let params = ConfigParams { max_referral_fee_bps: 5_000, ..default_config_params(env.treasury) };
+    env.update_config(env.admin, params).unwrap();
+    let other = env.user(&pool, DEPOSIT, DEPOSIT);
+    let (plain, _) = env.create_funded_pool(2, LIQUIDITY, LIQUIDITY);
+    let plain_owner = env.user(&plain, DEPOSIT, DEPOSIT);
+    open_funded(&env, &pool, &other, -1_000, 1_000);
+    open_funded(&env, &plain, &plain_owner, -1_000, 1_000);
+
+    let trader = env.user(&pool, 10_000_000, 0);
+    let referrer = env.create_token_account(&pool.token_a_mint, &owner.key, 0);
+    env.swap_with_referral(&pool, &trader, 10_000_000, true, Some(referrer), 5_000).unwrap();
+    let plain_trader = env.user(&plain, 10_000_000, 0);
+    env.swap(&plain, &plain_trader, 10_000_000, 0, true).unwrap();
+
+    // Same price; half of the 50_000 fee goes to the referrer instead of the LPs
+    assert_eq!(env.token_balance(&trader.token_b), env.token_balance(&plain_trader.token_b));
+    assert_eq!(env.token_balance(&referrer), 25_000);
+    let (state, plain_state) = (env.pool_state(&pool), env.pool_state(&plain));
+    assert!(state.position_fees_a <= plain_state.position_fees_a / 2 + 1);
+    assert!(state.position_fees_a + 1 >= plain_state.position_fees_a / 2);
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn swap_stops_at_its_price_limit() {
+    let (env, pool, owner) = setup();
+    open_funded(&env, &pool, &owner, -1_000, 1_000);
+    let trader = env.user(&pool, 500_000_000, 0);
+    let limit = sqrt_price_at_tick(-200).unwrap();
+
+    // The limit has to lie ahead of the price
+    for wrong in [sqrt_price_at_tick(200).unwrap(), env.pool_state(&pool).sqrt_price_x64] {
+        assert_swap_error(env.swap_with_limits(&pool, &trader, 1_000_000, true, None, Some(wrong)), SwapError::InvalidPriceLimit);
+    }
+
+    env.swap_with_limits(&pool, &trader, 500_000_000, true, None, Some(limit)).unwrap();
+    let state = env.pool_state(&pool);
+    assert!(state.sqrt_price_x64 >= limit && state.sqrt_price_x64 - limit < 1 << 40);
+    assert_eq!(state.tick_current, -200);
+
+    // Only the input that got the price to the limit was taken, and charged fees
+    let event = env.events::<SwapExecuted>().pop().unwrap();
+    let spent = 500_000_000 - env.token_balance(&trader.token_a);
+    assert_eq!(event.amount_in, spent);
+    assert!(spent > 10_000_000 && spent < 100_000_000);
+    assert_eq!(event.fee, spent * 500 / 100_000);
+    assert_eq!(env.token_balance(&trader.token_b), event.amount_out);
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn swap_stops_at_a_price_limit_past_a_crossed_tick() {
+    let (env, pool, owner) = setup();
+    let other = env.user(&pool, DEPOSIT, DEPOSIT);
+    open_funded(&env, &pool, &owner, -1_000, 1_000);
+    open_funded(&env, &pool, &other, -2_000, -1_000);
+    let below = env.pool_state(&pool);
+    let trader = env.user(&pool, 500_000_000, 0);
+    let limit = sqrt_price_at_tick(-1_500).unwrap();
+
+    env.swap_with_limits(&pool, &trader, 500_000_000, true, None, Some(limit)).unwrap();
+    let state = env.pool_state(&pool);
+    assert!(state.sqrt_price_x64 >= limit && state.sqrt_price_x64 - limit < 1 << 40);
+    assert_eq!(state.tick_current, -1_500);
+    assert!(state.liquidity > 0 && state.liquidity != below.liquidity);
+
+    // Whatever the walk didn't use stays with the trader, uncharged
+    let event = env.events::<SwapExecuted>().pop().unwrap();
+    let spent = 500_000_000 - env.token_balance(&trader.token_a);
+    assert_eq!(event.amount_in, spent);
+    assert!(spent > 100_000_000 && spent < 400_000_000, "spent {spent}");
+    assert_eq!(event.fee, spent * 500 / 100_000);
+    assert_eq!(env.token_balance(&trader.token_b), event.amount_out);
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn fees_accrue_only_while_in_range() {
+    let (env, pool, owner) = setup();
+    let other = env.user(&pool, DEPOSIT, DEPOSIT);
+    let inside = open_funded(&env, &pool, &owner, -1_000, 1_000);
+    let above = open_funded(&env, &pool, &other, 1_000, 2_000);
+
+    let trader = env.user(&pool, 1_000_000, 200_000_000);
+    env.swap(&pool, &trader, 1_000_000, 0, true).unwrap();
+    let a_before = env.token_balance(&other.token_a);
+    env.collect_fees(&pool, &above, &other).unwrap();
+    assert_eq!(env.token_balance(&other.token_a), a_before);
+    let a_before = env.token_balance(&owner.token_a);
+    env.collect_fees(&pool, &inside, &owner).unwrap();
+    assert!(env.token_balance(&owner.token_a) > a_before);
+
+    // Push the price up into the second range; both ranges earn on the way
+    env.swap(&pool, &trader, 200_000_000, 0, false).unwrap();
+    let state = env.pool_state(&pool);
+    assert!((1_000..2_000).contains(&state.tick_current), "tick {}", state.tick_current);
+    let (b_before, other_b_before) = (env.token_balance(&owner.token_b), env.token_balance(&other.token_b));
+    env.collect_fees(&pool, &inside, &owner).unwrap();
+    env.collect_fees(&pool, &above, &other).unwrap();
+    let inside_fees = env.token_balance(&owner.token_b) - b_before;
+    let above_fees = env.token_balance(&other.token_b) - other_b_before;
+    assert!(inside_fees > 0 && above_fees > 0);
+    // Together they never take more than the range fees paid in
+    assert!(env.pool_state(&pool).position_fees_b <= 2);
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn decrease_liquidity_keeps_fees_for_collect() {
+    let (env, pool, owner) = setup();
+    let position = open_funded(&env, &pool, &owner, -1_000, 1_000);
+    let trader = env.user(&pool, 10_000_000, 0);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+
+    let liquidity = env.fetch::<Position>(&position.address).liquidity;
+    env.decrease_liquidity(&pool, &position, &owner, liquidity, 0, 0).unwrap();
+    let owed = env.fetch::<Position>(&position.address).tokens_owed_a;
+    assert!(owed > 0);
+    assert!(env.fetch::<PoolTicks>(&pool.ticks).ticks.is_empty());
+
+    let a_before = env.token_balance(&owner.token_a);
+    env.collect_fees(&pool, &position, &owner).unwrap();
+    assert_eq!(env.token_balance(&owner.token_a), a_before + owed);
+    let state = env.pool_state(&pool);
+    // Only the withdrawal's rounding stays behind
+    assert!(state.position_reserve_a <= 2 && state.position_reserve_b <= 2);
+    assert!(state.position_fees_a <= 1);
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn filling_the_tick_account_takes_minimum_sized_positions() {
+    let (env, pool, owner) = setup();
+    let position = env.position();
+    env.open_position(&pool, &position, owner.key, 1_000, 1_010).unwrap();
+
+    // Dust can't claim ticks, nor can a withdrawal leave dust behind
+    assert_swap_error(env.increase_liquidity(&pool, &position, &owner, 100, 0), SwapError::LiquidityBelowMinimum);
+    env.increase_liquidity(&pool, &position, &owner, 1_000_000, 0).unwrap();
+    let liquidity = env.fetch::<Position>(&position.address).liquidity;
+    assert!(liquidity >= MIN_TICK_LIQUIDITY);
+    assert_swap_error(
+        env.decrease_liquidity(&pool, &position, &owner, liquidity - 1, 0, 0),
+        SwapError::LiquidityBelowMinimum,
+    );
+
+    // Every further range needs at least the minimum liquidity too
+    let mut positions = vec![position];
+    for range in 1..MAX_POOL_TICKS as i32 / 2 {
+        let tick_lower = 1_000 + 20 * range;
+        let position = env.position();
+        env.open_position(&pool, &position, owner.key, tick_lower, tick_lower + 10).unwrap();
+        env.increase_liquidity(&pool, &position, &owner, 1_000_000, 0).unwrap();
+        assert!(env.fetch::<Position>(&position.address).liquidity >= MIN_TICK_LIQUIDITY);
+        positions.push(position);
+    }
+    assert_eq!(env.fetch::<PoolTicks>(&pool.ticks).ticks.len(), MAX_POOL_TICKS);
+    let late = env.position();
+    env.open_position(&pool, &late, owner.key, -1_000, -990).unwrap();
+    assert_swap_error(env.increase_liquidity(&pool, &late, &owner, 0, 1_000_000), SwapError::TooManyTicks);
+
+    // Withdrawing a range frees its ticks
+    env.decrease_liquidity(&pool, &positions[0], &owner, liquidity, 0, 0).unwrap();
+    env.increase_liquidity(&pool, &late, &owner, 0, 1_000_000).unwrap();
+}
@@ -0,0 +1,264 @@
+mod common;
+
+use common::*;
+use anchor_lang::prelude::Pubkey;
+use solana_amm_educational_template::{Farm, StakePosition, SwapError};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+const RATE: u64 = 1_000; // Reward tokens per second
+const START: i64 = 1_000;
+const END: i64 = 2_000;
+const FUNDING: u64 = RATE * (END - START) as u64;
+
+// A funded pool with a farm paying RATE from START to END
+fn setup() -> (TestEnv, Pool, FarmAccounts) {
+    let env = TestEnv::new();
+    env.svm.warp_to_timestamp(START - 100);
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let farm = FarmAccounts::new(&pool, env.create_mint(6));
+    let funding = env.create_token_account(&farm.reward_mint, &env.admin, FUNDING);
+    env.create_farm(&farm, env.admin, funding, START, END, RATE).unwrap();
+    (env, pool, farm)
+}
+
+// A wallet holding `lp` LP tokens and an empty reward token account
+fn staker(env: &TestEnv, pool: &Pool, farm: &FarmAccounts, lp: u64) -> (User, Pubkey) {
+    let user = env.user(pool, 0, 0);
+    env.mint_to(&user.lp, lp);
+    let rewards = env.create_token_account(&farm.reward_mint, &user.key, 0);
+    (user, rewards)
+}
+
+#[test]
+fn create_farm_takes_the_full_funding() {
+    let (env, pool, farm) = setup();
+
+    let state: Farm = env.fetch(&farm.address);
+    assert_eq!((state.pool, state.lp_mint, state.reward_mint), (pool.state, pool.lp_mint, farm.reward_mint));
+    assert_eq!((state.start_time, state.end_time, state.emission_rate), (START, END, RATE));
+    assert_eq!(env.token_balance(&farm.reward_vault), FUNDING);
+    assert_eq!(env.token_balance(&farm.stake_vault), 0);
+}
+
+#[test]
+fn create_farm_checks_admin_and_schedule() {
+    let env = TestEnv::new();
+    env.svm.warp_to_timestamp(START);
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let farm = FarmAccounts::new(&pool, env.create_mint(6));
+    let funding = env.create_token_account(&farm.reward_mint, &env.admin, FUNDING);
+
+    let impostor = env.wallet();
+    let impostor_funding = env.create_token_account(&farm.reward_mint, &impostor, FUNDING);
+    assert_swap_error(
+        env.create_farm(&farm, impostor, impostor_funding, START, END, RATE),
+        SwapError::Unauthorized,
+    );
+    assert_swap_error(
+        env.create_farm(&farm, env.admin, funding, END, END, RATE),
+        SwapError::InvalidFarmSchedule,
+    );
+    assert_swap_error(
+        env.create_farm(&farm, env.admin, funding, START, END, 0),
+        SwapError::InvalidFarmSchedule,
+    );
+    // A start in the past counts from now, so it needs less funding
+    env.svm.warp_to_timestamp(START + 500);
+    env.create_farm(&farm, env.admin, funding, START, END, RATE).unwrap();
+    assert_eq!(env.token_balance(&farm.reward_vault), FUNDING / 2);
+}
+
+#[test]
+fn rewards_split_by_stake_and_time() {
+    let (env, pool, farm) = setup();
+    let (alice, alice_rewards) = staker(&env, &pool, &farm, 100);
+    let (bob, bob_rewards) = staker(&env, &pool, &farm, 300);
+
+    // Alice has the farm to herself for 400s, then shares it 1:3 with Bob
+    env.stake_lp(&farm, &alice, 100).unwrap();
+    env.svm.warp_to_timestamp(START + 400);
+    env.stake_lp(&farm, &bob, 300).unwrap();
+    env.svm.warp_to_timestamp(END + 50);
+    env.claim_rewards(&farm, &alice, alice_rewards).unwrap();
+    env.claim_rewards(&farm, &bob, bob_rewards).unwrap();
+
+    assert_eq!(env.token_balance(&alice_rewards), 400 * RATE + 600 * RATE / 4);
+    assert_eq!(env.token_balance(&bob_rewards), 600 * RATE * 3 / 4);
+    assert_eq!(env.token_balance(&farm.reward_vault), 0);
+
+    let state: Farm = env.fetch(&farm.address);
+    assert_eq!((state.total_staked, state.rewards_emitted, state.rewards_claimed), (400, FUNDING, FUNDING));
+    assert_eq!(env.token_balance(&farm.stake_vault), 400);
+}
+
+#[test]
+fn no_rewards_outside_the_schedule() {
+    let (env, pool, farm) = setup();
+    let (early, early_rewards) = staker(&env, &pool, &farm, 100);
+    env.stake_lp(&farm, &early, 100).unwrap();
+    env.svm.warp_to_timestamp(START);
+    env.claim_rewards(&farm, &early, early_rewards).unwrap();
+    assert_eq!(env.token_balance(&early_rewards), 0);
+
+    env.svm.warp_to_timestamp(END + 500);
+    let (late, late_rewards) = staker(&env, &pool, &farm, 100);
+    env.stake_lp(&farm, &late, 100).unwrap();
+    env.svm.warp_to_timestamp(END + 1_000);
+    env.claim_rewards(&farm, &late, late_rewards).unwrap();
+    assert_eq!(env.token_balance(&late_rewards), 0);
+
+    env.claim_rewards(&farm, &early, early_rewards).unwrap();
+    assert_eq!(env.token_balance(&early_rewards), FUNDING);
+}
+
+#[test]
+fn unstake_returns_lp_and_keeps_rewards() {
+    let (env, pool, farm) = setup();
+    let (user, rewards) = staker(&env, &pool, &farm, 500);
+    env.svm.warp_to_timestamp(START);
+    env.stake_lp(&farm, &user, 500).unwrap();
+    assert_eq!(env.token_balance(&user.lp), 0);
+
+    env.svm.warp_to_timestamp(START + 100);
+    assert_swap_error(env.unstake_lp(&farm, &user, 501), SwapError::InsufficientStake);
+    env.unstake_lp(&farm, &user, 500).unwrap();
+    assert_eq!(env.token_balance(&user.lp), 500);
+
+    // Earned while staked, nothing after
+    env.svm.warp_to_timestamp(START + 300);
+    env.claim_rewards(&farm, &user, rewards).unwrap();
+    assert_eq!(env.token_balance(&rewards), 100 * RATE);
+    let position: StakePosition = env.fetch(&farm.stake_position(&user.key));
+    assert_eq!((position.owner, position.amount, position.pending_rewards), (user.key, 0, 0));
+}
+
+#[test]
+fn only_the_owner_moves_a_stake() {
+    let (env, pool, farm) = setup();
+    let (user, _) = staker(&env, &pool, &farm, 500);
+    env.stake_lp(&farm, &user, 500).unwrap();
+
+    // Someone else's LP account can't receive the stake: the position is
+    // derived from the signer, and theirs doesn't exist
+    let (thief, _) = staker(&env, &pool, &farm, 0);
+    assert!(env.unstake_lp(&farm, &thief, 500).is_err());
+    assert_eq!(env.token_balance(&farm.stake_vault), 500);
+}
+
+#[test]
+fn extend_farm_requires_funding() {
+    let (env, pool, farm) = setup();
+    let (user, rewards) = staker(&env, &pool, &farm, 100);
+    env.stake_lp(&farm, &user, 100).unwrap();
+    env.svm.warp_to_timestamp(START + 500);
+
+    assert_swap_error(env.extend_farm(&farm, env.admin, END - 1, RATE), SwapError::InvalidFarmSchedule);
+    assert_swap_error(env.extend_farm(&farm, env.admin, END + 1_000, RATE), SwapError::FarmUnderfunded);
+    let impostor = env.wallet();
+    assert_swap_error(env.extend_farm(&farm, impostor, END + 1_000, RATE), SwapError::Unauthorized);
+
+    let topup = env.create_token_account(&farm.reward_mint, &env.admin, FUNDING);
+    env.fund_farm(&farm, env.admin, topup, FUNDING).unwrap();
+    // The top-up covers another 1000s at the same rate, but not doubling the
+    // rate for the rest of the run
+    assert_swap_error(env.extend_farm(&farm, env.admin, END + 500, 2 * RATE), SwapError::FarmUnderfunded);
+    env.extend_farm(&farm, env.admin, END + 1_000, RATE).unwrap();
+
+    env.svm.warp_to_timestamp(END + 1_000);
+    env.claim_rewards(&farm, &user, rewards).unwrap();
+    assert_eq!(env.token_balance(&rewards), 2 * FUNDING);
+    assert_eq!(env.token_balance(&farm.reward_vault), 0);
+}
+
+#[test]
+fn emissions_nobody_staked_for_can_be_reclaimed() {
+    let (env, pool, farm) = setup();
+    let (user, rewards) = staker(&env, &pool, &farm, 100);
+    let reclaimed = env.create_token_account(&farm.reward_mint, &env.admin, 0);
+
+    // Nobody staked for the first 200s; the rest of the run is still owed
+    env.svm.warp_to_timestamp(START + 200);
+    let impostor = env.wallet();
+    let impostor_rewards = env.create_token_account(&farm.reward_mint, &impostor, 0);
+    assert_swap_error(env.reclaim_farm_rewards(&farm, impostor, impostor_rewards), SwapError::Unauthorized);
+    env.reclaim_farm_rewards(&farm, env.admin, reclaimed).unwrap();
+    assert_eq!(env.token_balance(&reclaimed), 200 * RATE);
+
+    env.svm.warp_to_timestamp(START + 400);
+    env.stake_lp(&farm, &user, 100).unwrap();
+    env.svm.warp_to_timestamp(END + 50);
+    env.reclaim_farm_rewards(&farm, env.admin, reclaimed).unwrap();
+    assert_eq!(env.token_balance(&reclaimed), 400 * RATE);
+
+    // The staker's share was never touched
+    env.claim_rewards(&farm, &user, rewards).unwrap();
+    assert_eq!(env.token_balance(&rewards), 600 * RATE);
+    assert_eq!(env.token_balance(&farm.reward_vault), 0);
+}
+
+#[test]
+fn rounding_dust_can_be_reclaimed() {
+    let (env, pool, farm) = setup();
+    let stake = 7_000_000_000_000;
+    let (user, rewards) = staker(&env, &pool, &farm, stake);
+    env.stake_lp(&farm, &user, stake).unwrap();
+
+    // 100_000 a claim over 7e12 LP tokens raises reward_per_share by a
+    // rounded-down 14_285, which pays out 99_995 and leaves 5 behind
+    for claim in 1..=10 {
+        env.svm.warp_to_timestamp(START + claim * 100);
+        env.claim_rewards(&farm, &user, rewards).unwrap();
+    }
+    assert_eq!(env.token_balance(&rewards), FUNDING - 50);
+    let state: Farm = env.fetch(&farm.address);
+    assert_eq!(state.rewards_emitted, state.rewards_claimed);
+
+    let reclaimed = env.create_token_account(&farm.reward_mint, &env.admin, 0);
+    env.reclaim_farm_rewards(&farm, env.admin, reclaimed).unwrap();
+    assert_eq!(env.token_balance(&reclaimed), 50);
+    assert_eq!(env.token_balance(&farm.reward_vault), 0);
+}
+
+#[test]
+fn close_farm_waits_for_an_ended_empty_farm() {
+    let (env, pool, farm) = setup();
+    let (user, rewards) = staker(&env, &pool, &farm, 100);
+    let returned = env.create_token_account(&farm.reward_mint, &env.admin, 0);
+    let recipient = env.wallet();
+    env.svm.warp_to_timestamp(START + 500);
+    env.stake_lp(&farm, &user, 100).unwrap();
+
+    env.svm.warp_to_timestamp(END + 10);
+    assert_swap_error(env.close_farm(&farm, env.wallet(), returned, recipient), SwapError::Unauthorized);
+    // Still staked, then staked out but with rewards unclaimed
+    assert_swap_error(env.close_farm(&farm, env.admin, returned, recipient), SwapError::FarmNotFinished);
+    env.unstake_lp(&farm, &user, 100).unwrap();
+    assert_swap_error(env.close_farm(&farm, env.admin, returned, recipient), SwapError::FarmNotFinished);
+    env.claim_rewards(&farm, &user, rewards).unwrap();
+    assert_eq!(env.token_balance(&rewards), 500 * RATE);
+
+    // LP tokens sent to the stake vault without staking belong to nobody
+    env.mint_to(&farm.stake_vault, 7);
+    let lp_supply = env.mint(&pool.lp_mint).supply;
+    let rent: u64 = [farm.address, farm.stake_vault, farm.reward_vault].iter().map(|account| env.svm.lamports(account)).sum();
+    let recipient_before = env.svm.lamports(&recipient);
+    env.close_farm(&farm, env.admin, returned, recipient).unwrap();
+    assert_eq!(env.token_balance(&returned), 500 * RATE);
+    assert_eq!(env.mint(&pool.lp_mint).supply, lp_supply - 7);
+    assert_eq!(env.svm.lamports(&recipient), recipient_before + rent);
+    for account in [farm.address, farm.stake_vault, farm.reward_vault] {
+        assert!(env.svm.account(&account).is_none());
+    }
+}
+
+#[test]
+fn close_farm_waits_for_the_end() {
+    let (env, _, farm) = setup();
+    let returned = env.create_token_account(&farm.reward_mint, &env.admin, 0);
+
+    env.svm.warp_to_timestamp(END - 1);
+    assert_swap_error(env.close_farm(&farm, env.admin, returned, env.admin), SwapError::FarmNotFinished);
+    env.svm.warp_to_timestamp(END);
+    env.close_farm(&farm, env.admin, returned, env.admin).unwrap();
+    assert_eq!(env.token_balance(&returned), FUNDING);
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    entrypoint::ProgramResult, instruction::Instruction, program::invoke,
+};
+use common::*;
+use solana_amm_educational_template::SwapError;
+use solana_program_test::processor;
+
+const LIQUIDITY: u64 = 1_000_000_000;
+
+// Flash swap borrower used by the tests. The callback pays `amount` (the
+// instruction data) from its first account into its second, authorised by
+// the third.
+const BORROWER_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+
+fn borrower(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let amount = u64::from_le_bytes(data[..8].try_into().unwrap());
+    let ix = spl_token::instruction::transfer(
+        &spl_token::ID,
+        accounts[0].key,
+        accounts[1].key,
+        accounts[2].key,
+        &[],
+        amount,
+    )?;
+    invoke(&ix, &accounts[..3])
+}
+
+fn setup() -> (TestEnv, Pool, User) {
+    let env = TestEnv::with_programs(&[(BORROWER_ID, processor!(borrower))]);
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+    (env, pool, trader)
+}
+
+// A flash swap of token A for token B whose callback repays `repay` of token A
+fn flash_swap_ix(env: &TestEnv, pool: &Pool, trader: &User, amount_in: u64, repay: u64, program: Pubkey) -> Instruction {
+    let mut ix = env.swap_ix(pool, trader, amount_in, 0, true, Some(repay.to_le_bytes().to_vec()), None);
+    ix.accounts.extend([
+        AccountMeta::new_readonly(program, false),
+        AccountMeta::new(trader.token_a, false),
+        AccountMeta::new(pool.vault_a, false),
+        AccountMeta::new_readonly(trader.key, true),
+        AccountMeta::new_readonly(spl_token::ID, false),
+    ]);
+    ix
+}
+
+#[test]
+fn flash_swap_repaid_in_callback_matches_normal_swap() {
+    let (env, pool, trader) = setup();
+
+    let ix = flash_swap_ix(&env, &pool, &trader, 10_000_000, 10_000_000, BORROWER_ID);
+    env.svm.process_instruction(ix, &[trader.key]).unwrap();
+    let flash_out = env.token_balance(&trader.token_b);
+    let flash_state = env.pool_state(&pool);
+
+    let (env, pool, trader) = setup();
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+
+    assert_eq!(flash_out, env.token_balance(&trader.token_b));
+    let state = env.pool_state(&pool);
+    assert_eq!((flash_state.reserve_a, flash_state.reserve_b), (state.reserve_a, state.reserve_b));
+}
+
+#[test]
+fn flash_swap_must_be_repaid() {
+    let (env, pool, trader) = setup();
+
+    let ix = flash_swap_ix(&env, &pool, &trader, 10_000_000, 9_000_000, BORROWER_ID);
+    assert_swap_error(env.svm.process_instruction(ix, &[trader.key]), SwapError::FlashSwapNotRepaid);
+    assert_eq!(env.token_balance(&trader.token_b), 0);
+}
+
+#[test]
+fn flash_swap_needs_callback_program() {
+    let (env, pool, trader) = setup();
+
+    let ix = env.swap_ix(&pool, &trader, 10_000_000, 0, true, Some(vec![]), None);
+    assert_swap_error(env.svm.process_instruction(ix, &[trader.key]), SwapError::MissingCallbackProgram);
+}
+
+#[test]
+fn flash_swap_rejects_invalid_callback_programs() {
+    let (env, pool, trader) = setup();
+
+    // Not a program
+    let ix = flash_swap_ix(&env, &pool, &trader, 10_000_000, 10_000_000, trader.key);
+    assert_swap_error(env.svm.process_instruction(ix, &[trader.key]), SwapError::InvalidCallbackProgram);
+
+    // The AMM itself, which would allow re-entrant swaps
+    let ix = flash_swap_ix(&env, &pool, &trader, 10_000_000, 10_000_000, PROGRAM_ID);
+    assert_swap_error(env.svm.process_instruction(ix, &[trader.key]), SwapError::InvalidCallbackProgram);
+}
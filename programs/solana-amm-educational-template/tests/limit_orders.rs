@@ -0,0 +1,158 @@
+mod common;
+
+use common::*;
+use solana_amm_educational_template::{LimitOrder, LimitOrdersFilled, PoolTicks, SwapError};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+const ORDER: u64 = 10_000_000;
+
+// A concentrated pool at price 1 (tick 0) and a trader with plenty of both tokens
+fn setup() -> (TestEnv, Pool, User) {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(2, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 100_000_000, 100_000_000);
+    (env, pool, trader)
+}
+
+// Vaults hold exactly the reserves, the ranged tokens and fees and the creator fees
+fn assert_vaults_balance(env: &TestEnv, pool: &Pool) {
+    let state = env.pool_state(pool);
+    assert_eq!(
+        env.token_balance(&pool.vault_a),
+        state.reserve_a + state.position_reserve_a + state.position_fees_a + state.creator_fees_a
+    );
+    assert_eq!(
+        env.token_balance(&pool.vault_b),
+        state.reserve_b + state.position_reserve_b + state.position_fees_b + state.creator_fees_b
+    );
+}
+
+#[test]
+fn sell_a_order_fills_when_the_price_rises_through_it() {
+    let (env, pool, trader) = setup();
+    let seller = env.user(&pool, ORDER, 0);
+    env.place_limit_order(&pool, &seller, 100, true, ORDER).unwrap();
+
+    let order: LimitOrder = env.fetch(&pool.limit_order(&seller.key, 100));
+    assert_eq!((order.pool, order.owner, order.tick, order.sells_a), (pool.state, seller.key, 100, true));
+    assert!(env.token_balance(&seller.token_a) <= 1);
+    assert_swap_error(env.claim_filled_order(&pool, &seller, 100), SwapError::OrderNotFilled);
+
+    // Buying token A lifts the price through the order
+    env.swap(&pool, &trader, 30_000_000, 0, false).unwrap();
+    assert!(env.pool_state(&pool).tick_current >= 110);
+    let filled = env.events::<LimitOrdersFilled>();
+    assert_eq!(filled.len(), 1);
+    assert_eq!((filled[0].pool, filled[0].tick, filled[0].sells_a), (pool.state, 100, true));
+    assert_eq!((filled[0].epoch, filled[0].liquidity), (order.epoch, order.liquidity));
+    // Sold at a price of about 1.0105
+    assert!(filled[0].amount_out > ORDER && filled[0].amount_out < ORDER * 1_011 / 1_000);
+
+    // Falling back doesn't undo the fill
+    env.swap(&pool, &trader, 60_000_000, 0, true).unwrap();
+    assert!(env.pool_state(&pool).tick_current < 100);
+    assert_vaults_balance(&env, &pool);
+
+    env.claim_filled_order(&pool, &seller, 100).unwrap();
+    assert_eq!(env.token_balance(&seller.token_b), filled[0].amount_out);
+    assert!(env.svm.account(&pool.limit_order(&seller.key, 100)).is_none());
+    assert!(env.fetch::<PoolTicks>(&pool.ticks).ticks.is_empty());
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn sell_b_order_fills_when_the_price_falls_through_it() {
+    let (env, pool, trader) = setup();
+    let seller = env.user(&pool, 0, ORDER);
+    env.place_limit_order(&pool, &seller, -110, false, ORDER).unwrap();
+
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+    assert!(env.events::<LimitOrdersFilled>().is_empty());
+    env.swap(&pool, &trader, 20_000_000, 0, true).unwrap();
+    assert!(env.pool_state(&pool).tick_current < -110);
+    let filled = env.events::<LimitOrdersFilled>();
+    assert_eq!((filled.len(), filled[0].tick, filled[0].sells_a), (1, -110, false));
+
+    env.claim_filled_order(&pool, &seller, -110).unwrap();
+    let proceeds = env.token_balance(&seller.token_a);
+    assert_eq!(proceeds, filled[0].amount_out);
+    assert!(proceeds > ORDER && proceeds < ORDER * 1_011 / 1_000);
+    assert_vaults_balance(&env, &pool);
+}
+
+#[test]
+fn orders_sit_beyond_the_price() {
+    let (env, pool, _) = setup();
+    let seller = env.user(&pool, ORDER, ORDER);
+    // Selling A needs the whole range above the price, selling B below it
+    for (tick, sells_a) in [(-10, true), (0, true), (0, false), (10, false)] {
+        assert_swap_error(env.place_limit_order(&pool, &seller, tick, sells_a, ORDER), SwapError::InvalidOrderPrice);
+    }
+    assert_swap_error(env.place_limit_order(&pool, &seller, 105, true, ORDER), SwapError::InvalidTickRange);
+    assert_swap_error(env.place_limit_order(&pool, &seller, 100, true, 0), SwapError::InvalidAmount);
+
+    let (standard, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let standard_seller = env.user(&standard, ORDER, 0);
+    assert_swap_error(
+        env.place_limit_order(&standard, &standard_seller, 100, true, ORDER),
+        SwapError::InvalidPoolType,
+    );
+}
+
+#[test]
+fn cancel_returns_an_unfilled_order() {
+    let (env, pool, trader) = setup();
+    let seller = env.user(&pool, 2 * ORDER, 0);
+    env.place_limit_order(&pool, &seller, 100, true, ORDER).unwrap();
+    // One order per wallet and tick
+    assert_swap_error(env.place_limit_order(&pool, &seller, 100, true, ORDER), SwapError::LimitOrderExists);
+
+    // Another wallet can't touch it
+    let other = env.user(&pool, 0, 0);
+    assert!(env.cancel_limit_order(&pool, &other, 100).is_err());
+
+    env.cancel_limit_order(&pool, &seller, 100).unwrap();
+    // Rounding only ever favours the pool
+    let returned = env.token_balance(&seller.token_a) - ORDER;
+    assert!(returned <= ORDER && returned + 2 >= ORDER);
+    assert!(env.svm.account(&pool.limit_order(&seller.key, 100)).is_none());
+    assert!(env.fetch::<PoolTicks>(&pool.ticks).ticks.is_empty());
+    assert_vaults_balance(&env, &pool);
+
+    // Once filled, an order can only be claimed
+    env.place_limit_order(&pool, &seller, 100, true, env.token_balance(&seller.token_a)).unwrap();
+    env.swap(&pool, &trader, 30_000_000, 0, false).unwrap();
+    assert_swap_error(env.cancel_limit_order(&pool, &seller, 100), SwapError::OrderAlreadyFilled);
+    env.claim_filled_order(&pool, &seller, 100).unwrap();
+}
+
+#[test]
+fn a_batch_fills_together_and_earns_no_fees() {
+    let (env, pool, trader) = setup();
+    let alice = env.user(&pool, ORDER, 0);
+    let bob = env.user(&pool, 3 * ORDER, 0);
+    env.place_limit_order(&pool, &alice, 100, true, ORDER).unwrap();
+    env.place_limit_order(&pool, &bob, 100, true, 3 * ORDER).unwrap();
+    let alice_order: LimitOrder = env.fetch(&pool.limit_order(&alice.key, 100));
+    let bob_order: LimitOrder = env.fetch(&pool.limit_order(&bob.key, 100));
+    assert_eq!(alice_order.epoch, bob_order.epoch);
+
+    env.swap(&pool, &trader, 60_000_000, 0, false).unwrap();
+    assert_eq!(env.events::<LimitOrdersFilled>().len(), 1);
+    // With no positions, the orders' share of the fee went to the reserves
+    let state = env.pool_state(&pool);
+    assert_eq!((state.position_fees_a, state.position_fees_b), (0, 0));
+
+    // A new order at the same price waits for the next crossing
+    env.swap(&pool, &trader, 90_000_000, 0, true).unwrap();
+    let carol = env.user(&pool, ORDER, 0);
+    env.place_limit_order(&pool, &carol, 100, true, ORDER).unwrap();
+    assert!(env.fetch::<LimitOrder>(&pool.limit_order(&carol.key, 100)).epoch > alice_order.epoch);
+    assert_swap_error(env.claim_filled_order(&pool, &carol, 100), SwapError::OrderNotFilled);
+
+    env.claim_filled_order(&pool, &alice, 100).unwrap();
+    env.claim_filled_order(&pool, &bob, 100).unwrap();
+    let (alice_out, bob_out) = (env.token_balance(&alice.token_b), env.token_balance(&bob.token_b));
+    assert!(bob_out / 3 <= alice_out + 1 && alice_out <= bob_out / 3 + 1);
+    assert_vaults_balance(&env, &pool);
+}
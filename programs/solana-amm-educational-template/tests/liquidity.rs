@@ -0,0 +1,224 @@
+mod common;
+
+use common::*;
+use solana_amm_educational_template::{LiquidityAdded, LiquidityRemoved, SwapError};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+const FIRST_DEPOSIT_LP: u64 = 1_000_000;
+
+#[test]
+fn first_deposit_mints_fixed_lp_amount() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, 2 * LIQUIDITY);
+
+    assert_eq!(env.token_balance(&provider.lp), FIRST_DEPOSIT_LP);
+    assert_eq!(env.mint(&pool.lp_mint).supply, FIRST_DEPOSIT_LP);
+    let state = env.pool_state(&pool);
+    assert_eq!((state.reserve_a, state.reserve_b), (LIQUIDITY, 2 * LIQUIDITY));
+    assert_eq!(env.token_balance(&pool.vault_a), LIQUIDITY);
+    assert_eq!(env.token_balance(&pool.vault_b), 2 * LIQUIDITY);
+}
+
+#[test]
+fn standard_deposit_mints_pro_rata() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, 2 * LIQUIDITY);
+
+    let user = env.user(&pool, LIQUIDITY, 2 * LIQUIDITY);
+    env.add_liquidity(&pool, &user, LIQUIDITY / 2, LIQUIDITY, None).unwrap();
+    assert_eq!(env.token_balance(&user.lp), FIRST_DEPOSIT_LP / 2);
+
+    // Unbalanced deposits are credited for the smaller side only: token A
+    // alone is a third of the reserves, so a third of the LP supply
+    let user = env.user(&pool, LIQUIDITY, 2 * LIQUIDITY);
+    env.add_liquidity(&pool, &user, LIQUIDITY / 2, 2 * LIQUIDITY, None).unwrap();
+    assert_eq!(env.token_balance(&user.lp), FIRST_DEPOSIT_LP / 2);
+}
+
+#[test]
+fn stable_deposit_charges_imbalance() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(1, LIQUIDITY, LIQUIDITY);
+
+    let balanced = env.user(&pool, LIQUIDITY, LIQUIDITY);
+    env.add_liquidity(&pool, &balanced, LIQUIDITY / 10, LIQUIDITY / 10, None).unwrap();
+    let balanced_lp = env.token_balance(&balanced.lp);
+    assert!(balanced_lp.abs_diff(FIRST_DEPOSIT_LP / 10) <= 1, "got {balanced_lp}");
+
+    // The same value deposited on one side earns fewer LP tokens
+    let one_sided = env.user(&pool, LIQUIDITY, LIQUIDITY);
+    env.add_liquidity(&pool, &one_sided, LIQUIDITY / 5, 1, None).unwrap();
+    assert!(env.token_balance(&one_sided.lp) < balanced_lp);
+}
+
+#[test]
+fn concentrated_deposit_mints_pro_rata() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(2, LIQUIDITY, LIQUIDITY);
+
+    let user = env.user(&pool, LIQUIDITY, LIQUIDITY);
+    env.add_liquidity(&pool, &user, LIQUIDITY / 10, LIQUIDITY / 10, None).unwrap();
+    assert_eq!(env.token_balance(&user.lp), FIRST_DEPOSIT_LP / 10);
+    let state = env.pool_state(&pool);
+    assert_eq!((state.reserve_a, state.reserve_b), (LIQUIDITY * 11 / 10, LIQUIDITY * 11 / 10));
+}
+
+#[test]
+fn add_liquidity_rejects_zero_amounts() {
+    let env = TestEnv::new();
+    let pool = env.create_pool(0, 6, 6);
+    let user = env.user(&pool, LIQUIDITY, LIQUIDITY);
+
+    assert_swap_error(env.add_liquidity(&pool, &user, 0, LIQUIDITY, None), SwapError::InvalidAmount);
+    assert_swap_error(env.add_liquidity(&pool, &user, LIQUIDITY, 0, None), SwapError::InvalidAmount);
+}
+
+#[test]
+fn add_liquidity_rejects_deposits_too_small_for_one_lp_token() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let user = env.user(&pool, LIQUIDITY, LIQUIDITY);
+
+    assert_swap_error(env.add_liquidity(&pool, &user, 1, 1, None), SwapError::InvalidAmount);
+}
+
+#[test]
+fn add_liquidity_rejects_wrong_lp_account() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let mut user = env.user(&pool, LIQUIDITY, LIQUIDITY);
+    user.lp = env.create_token_account(&pool.token_a_mint, &user.key, 0);
+
+    assert_swap_error(env.add_liquidity(&pool, &user, LIQUIDITY, LIQUIDITY, None), SwapError::InvalidTokenMint);
+}
+
+#[test]
+fn add_liquidity_respects_deadline() {
+    let env = TestEnv::new();
+    let pool = env.create_pool(0, 6, 6);
+    let user = env.user(&pool, LIQUIDITY, LIQUIDITY);
+    env.svm.warp_to_timestamp(500);
+
+    assert_swap_error(
+        env.add_liquidity(&pool, &user, LIQUIDITY, LIQUIDITY, Some(499)),
+        SwapError::DeadlineExceeded,
+    );
+    env.add_liquidity(&pool, &user, LIQUIDITY, LIQUIDITY, Some(500)).unwrap();
+}
+
+#[test]
+fn remove_liquidity_pays_pro_rata() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, 2 * LIQUIDITY);
+
+    env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP / 4, 0, 0, None).unwrap();
+    assert_eq!(env.token_balance(&provider.token_a), LIQUIDITY / 4);
+    assert_eq!(env.token_balance(&provider.token_b), LIQUIDITY / 2);
+    assert_eq!(env.token_balance(&provider.lp), FIRST_DEPOSIT_LP * 3 / 4);
+
+    // Removing the rest empties the pool
+    env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP * 3 / 4, 0, 0, None).unwrap();
+    assert_eq!(env.token_balance(&provider.token_a), LIQUIDITY);
+    assert_eq!(env.token_balance(&provider.token_b), 2 * LIQUIDITY);
+    let state = env.pool_state(&pool);
+    assert_eq!((state.reserve_a, state.reserve_b), (0, 0));
+    assert_eq!(env.mint(&pool.lp_mint).supply, 0);
+}
+
+#[test]
+fn liquidity_changes_emit_events() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, 2 * LIQUIDITY);
+    let [added] = env.events::<LiquidityAdded>().try_into().unwrap();
+    assert_eq!((added.pool, added.user), (pool.state, provider.key));
+    assert_eq!((added.amount_a, added.amount_b, added.lp_amount), (LIQUIDITY, 2 * LIQUIDITY, FIRST_DEPOSIT_LP));
+    assert_eq!((added.reserve_a, added.reserve_b, added.lp_supply), (LIQUIDITY, 2 * LIQUIDITY, FIRST_DEPOSIT_LP));
+
+    env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP / 4, 0, 0, None).unwrap();
+    let [removed] = env.events::<LiquidityRemoved>().try_into().unwrap();
+    assert_eq!((removed.amount_a, removed.amount_b, removed.lp_amount), (LIQUIDITY / 4, LIQUIDITY / 2, FIRST_DEPOSIT_LP / 4));
+    let state = env.pool_state(&pool);
+    assert_eq!((removed.reserve_a, removed.reserve_b), (state.reserve_a, state.reserve_b));
+    assert_eq!(removed.lp_supply, env.mint(&pool.lp_mint).supply);
+}
+
+#[test]
+fn remove_liquidity_includes_swap_fees() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 100_000_000, 0);
+    env.swap(&pool, &trader, 100_000_000, 0, true).unwrap();
+    let out = env.token_balance(&trader.token_b);
+    env.swap(&pool, &trader, out, 0, false).unwrap();
+
+    env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP, 0, 0, None).unwrap();
+    let withdrawn_a = env.token_balance(&provider.token_a);
+    let withdrawn_b = env.token_balance(&provider.token_b);
+    assert!(withdrawn_a > LIQUIDITY);
+    assert_eq!(withdrawn_b, LIQUIDITY);
+}
+
+#[test]
+fn remove_liquidity_rejects_zero_amount() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+
+    assert_swap_error(env.remove_liquidity(&pool, &provider, 0, 0, 0, None), SwapError::InvalidAmount);
+}
+
+#[test]
+fn remove_liquidity_rejects_more_than_balance() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+
+    assert_swap_error(
+        env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP + 1, 0, 0, None),
+        SwapError::InsufficientLpBalance,
+    );
+}
+
+#[test]
+fn remove_liquidity_rejects_output_below_minimum() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+
+    assert_swap_error(
+        env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP / 2, LIQUIDITY / 2 + 1, 0, None),
+        SwapError::SlippageExceeded,
+    );
+    assert_swap_error(
+        env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP / 2, 0, LIQUIDITY / 2 + 1, None),
+        SwapError::SlippageExceeded,
+    );
+    env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP / 2, LIQUIDITY / 2, LIQUIDITY / 2, None).unwrap();
+}
+
+#[test]
+fn remove_liquidity_respects_deadline() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    env.svm.warp_to_timestamp(500);
+
+    assert_swap_error(
+        env.remove_liquidity(&pool, &provider, FIRST_DEPOSIT_LP, 0, 0, Some(499)),
+        SwapError::DeadlineExceeded,
+    );
+}
+
+#[test]
+fn liquidity_round_trip_for_every_pool_type() {
+    for pool_type in 0..=2 {
+        let env = TestEnv::new();
+        let (pool, _) = env.create_funded_pool(pool_type, LIQUIDITY, LIQUIDITY);
+
+        let user = env.user(&pool, LIQUIDITY / 10, LIQUIDITY / 10);
+        env.add_liquidity(&pool, &user, LIQUIDITY / 10, LIQUIDITY / 10, None).unwrap();
+        let lp = env.token_balance(&user.lp);
+        env.remove_liquidity(&pool, &user, lp, 0, 0, None).unwrap();
+
+        let state = env.pool_state(&pool);
+        assert_eq!(env.token_balance(&pool.vault_a), state.reserve_a, "pool type {pool_type}");
+        assert_eq!(env.token_balance(&pool.vault_b), state.reserve_b, "pool type {pool_type}");
+        assert_eq!(env.token_balance(&user.lp), 0);
+    }
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use anchor_lang::Space;
+use common::*;
+use solana_amm_educational_template::{
+    PoolState, SwapError, MINT_FLAG_FREEZE_AUTHORITY, MIN_POOL_STATE_VERSION, POOL_STATE_VERSION,
+};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+
+// Size of a pool account written before PoolState was versioned
+const LEGACY_POOL_STATE_LEN: usize = 110;
+// Offset of the version byte, right after the legacy fields
+const VERSION_OFFSET: usize = LEGACY_POOL_STATE_LEN;
+
+// A funded standard pool whose state account is cut back to the legacy
+// layout, which shares its first bytes with the current one
+fn legacy_pool(env: &TestEnv) -> Pool {
+    let freezable = env.create_mint_with_freeze_authority(9, Some(env.wallet()));
+    let pool = Pool::new(env.create_mint(6), freezable);
+    env.initialize_pool(&pool, 0, env.admin).unwrap();
+    let provider = env.user(&pool, LIQUIDITY, LIQUIDITY);
+    env.add_liquidity(&pool, &provider, LIQUIDITY, LIQUIDITY, None).unwrap();
+
+    let mut account = env.svm.account(&pool.state).unwrap();
+    account.data.truncate(LEGACY_POOL_STATE_LEN);
+    account.lamports = env.svm.rent().minimum_balance(LEGACY_POOL_STATE_LEN);
+    env.svm.set_account(pool.state, account);
+    pool
+}
+
+#[test]
+fn migrates_legacy_pool() {
+    let env = TestEnv::new();
+    let pool = legacy_pool(&env);
+
+    // Unusable until migrated
+    let trader = env.user(&pool, 10_000_000, 0);
+    assert!(env.swap(&pool, &trader, 10_000_000, 0, true).is_err());
+
+    let payer = env.wallet();
+    let payer_lamports = env.svm.lamports(&payer);
+    env.migrate_pool(&pool, payer).unwrap();
+
+    let account = env.svm.account(&pool.state).unwrap();
+    assert_eq!(account.data.len(), 8 + PoolState::INIT_SPACE);
+    assert_eq!(account.lamports, env.svm.rent().minimum_balance(account.data.len()));
+    assert_eq!(env.svm.lamports(&payer), payer_lamports - (account.lamports - env.svm.rent().minimum_balance(LEGACY_POOL_STATE_LEN)));
+
+    let state = env.pool_state(&pool);
+    assert_eq!(state.version, POOL_STATE_VERSION);
+    assert_eq!((state.token_a_decimals, state.token_b_decimals), (6, 9));
+    assert_eq!((state.token_a_rate, state.token_b_rate), (1_000, 1));
+    assert_eq!((state.reserve_a, state.reserve_b), (LIQUIDITY, LIQUIDITY));
+    assert_eq!((state.token_a_flags, state.token_b_flags), (0, MINT_FLAG_FREEZE_AUTHORITY));
+    assert_eq!(state.creator, Default::default());
+    assert_eq!(state.creator_fee_share, 0);
+    assert_eq!(state.fee_rate, 300);
+
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+}
+
+#[test]
+fn migrating_current_pool_is_a_no_op() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let before = env.svm.account(&pool.state).unwrap();
+
+    env.migrate_pool(&pool, env.wallet()).unwrap();
+
+    assert_eq!(env.svm.account(&pool.state).unwrap(), before);
+}
+
+#[test]
+fn unmigrated_pools_are_rejected() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let mut account = env.svm.account(&pool.state).unwrap();
+    account.data[VERSION_OFFSET] = MIN_POOL_STATE_VERSION - 1;
+    env.svm.set_account(pool.state, account);
+
+    let trader = env.user(&pool, 10_000_000, 0);
+    assert_swap_error(env.swap(&pool, &trader, 10_000_000, 0, true), SwapError::PoolNotMigrated);
+    assert_swap_error(env.add_liquidity(&pool, &provider, 1, 1, None), SwapError::PoolNotMigrated);
+    assert_swap_error(env.remove_liquidity(&pool, &provider, 1, 0, 0, None), SwapError::PoolNotMigrated);
+
+    env.migrate_pool(&pool, env.wallet()).unwrap();
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+}
+
+#[test]
+fn pools_missing_only_zeroed_fields_work_unmigrated() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let mut account = env.svm.account(&pool.state).unwrap();
+    account.data[VERSION_OFFSET] = MIN_POOL_STATE_VERSION;
+    env.svm.set_account(pool.state, account);
+
+    let trader = env.user(&pool, 10_000_000, 0);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+    env.mint_to(&provider.token_a, 1_000_000);
+    env.mint_to(&provider.token_b, 1_000_000);
+    env.add_liquidity(&pool, &provider, 1_000_000, 1_000_000, None).unwrap();
+    assert_eq!(env.pool_state(&pool).version, MIN_POOL_STATE_VERSION);
+
+    env.migrate_pool(&pool, env.wallet()).unwrap();
+    assert_eq!(env.pool_state(&pool).version, POOL_STATE_VERSION);
+}
+
+#[test]
+fn migrate_rejects_other_program_accounts() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let not_a_pool = Pool { state: config_address(), ..pool };
+
+    assert_swap_error(env.migrate_pool(&not_a_pool, env.wallet()), SwapError::InvalidPoolAccount);
+}
+
+#[test]
+fn migrate_rejects_another_pools_mints() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let (other, _) = env.create_funded_pool(0, 3 * LIQUIDITY, LIQUIDITY);
+    let mut account = env.svm.account(&pool.state).unwrap();
+    account.data[VERSION_OFFSET] = 3;
+    env.svm.set_account(pool.state, account);
+
+    // The other pool's vaults would otherwise overwrite this pool's reserves
+    let foreign = Pool { state: pool.state, ..other };
+    assert_swap_error(env.migrate_pool(&foreign, env.wallet()), SwapError::InvalidTokenMint);
+    assert_eq!(env.pool_state(&pool).version, 3);
+
+    env.migrate_pool(&pool, env.wallet()).unwrap();
+    assert_eq!((env.pool_state(&pool).reserve_a, env.pool_state(&pool).reserve_b), (LIQUIDITY, LIQUIDITY));
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::*;
+use solana_amm_educational_template::{SwapError, SwapExecuted};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+
+// A standard pool of wrapped SOL against a 9-decimal token, funded in native
+// SOL by its returned LP
+fn setup() -> (TestEnv, Pool, User) {
+    let env = TestEnv::new();
+    let pool = Pool::new(env.create_native_mint(), env.create_mint(9));
+    env.initialize_pool(&pool, 0, env.admin).unwrap();
+    let provider = env.native_sol_user(&pool, LIQUIDITY);
+    env.add_liquidity(&pool, &provider, LIQUIDITY, LIQUIDITY, None).unwrap();
+    (env, pool, provider)
+}
+
+#[test]
+fn liquidity_moves_in_native_sol() {
+    let (env, pool, _) = setup();
+    let provider = env.native_sol_user(&pool, LIQUIDITY);
+    let lamports_before = env.svm.lamports(&provider.key);
+
+    // The temporary wSOL account's rent comes back with it
+    env.add_liquidity(&pool, &provider, LIQUIDITY / 2, LIQUIDITY / 2, None).unwrap();
+    assert_eq!(env.svm.lamports(&provider.key), lamports_before - LIQUIDITY / 2);
+    assert_eq!(env.token_balance(&pool.vault_a), LIQUIDITY + LIQUIDITY / 2);
+    assert!(env.svm.account(&wsol_address(&provider.key)).is_none());
+
+    env.remove_liquidity(&pool, &provider, env.token_balance(&provider.lp), 0, 0, None).unwrap();
+    assert_eq!(env.svm.lamports(&provider.key), lamports_before);
+    assert_eq!(env.token_balance(&provider.token_b), LIQUIDITY);
+    assert!(env.svm.account(&wsol_address(&provider.key)).is_none());
+}
+
+#[test]
+fn swaps_native_sol_both_ways() {
+    let (env, pool, _) = setup();
+    let trader = env.native_sol_user(&pool, 0);
+    let lamports_before = env.svm.lamports(&trader.key);
+
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+    assert_eq!(env.svm.lamports(&trader.key), lamports_before - 10_000_000);
+    let bought = env.token_balance(&trader.token_b);
+    assert!(bought > 0);
+
+    env.swap(&pool, &trader, bought, 0, false).unwrap();
+    let [executed] = &env.events::<SwapExecuted>()[..] else { panic!("expected one swap event") };
+    assert_eq!(env.svm.lamports(&trader.key), lamports_before - 10_000_000 + executed.amount_out);
+    assert_eq!(env.token_balance(&trader.token_b), 0);
+    assert!(env.svm.account(&wsol_address(&trader.key)).is_none());
+}
+
+#[test]
+fn native_sol_needs_a_wrapped_sol_side() {
+    let env = TestEnv::new();
+    env.create_native_mint();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 0, 10_000_000);
+    let trader = User { token_a: trader.key, ..trader };
+
+    assert_swap_error(env.swap(&pool, &trader, 10_000_000, 0, false), SwapError::InvalidNativeSol);
+}
+
+#[test]
+fn claims_a_wsol_address_someone_sent_lamports_to() {
+    let (env, pool, _) = setup();
+    let trader = env.native_sol_user(&pool, 0);
+    env.svm.airdrop(&wsol_address(&trader.key), 1_000);
+    let lamports_before = env.svm.lamports(&trader.key);
+
+    // What was sent ends up with the trader when the account is closed
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+    assert_eq!(env.svm.lamports(&trader.key), lamports_before - 10_000_000 + 1_000);
+    assert!(env.token_balance(&trader.token_b) > 0);
+}
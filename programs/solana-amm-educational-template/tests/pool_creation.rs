@@ -0,0 +1,324 @@
+mod common;
+
+use common::*;
+use solana_amm_educational_template::{
+    ConfigParams, PoolCreator, SwapError, MINT_FLAG_FREEZE_AUTHORITY, MINT_FLAG_PERMANENT_DELEGATE,
+    MINT_FLAG_TRANSFER_HOOK, POOL_STATE_VERSION,
+};
+
+// ---------- config ----------
+
+#[test]
+fn upgrade_authority_initializes_config() {
+    let env = TestEnv::without_config();
+    let params = ConfigParams {
+        rejected_mint_flags: MINT_FLAG_FREEZE_AUTHORITY,
+        permissionless_pool_creation: false,
+        pool_creation_fee: 1_000,
+        treasury: env.treasury,
+        creator_fee_share: 2_500,
+        max_referral_fee_bps: 1_000,
+        circuit_breaker_bps: 300,
+    };
+    env.initialize_config(env.admin, params).unwrap();
+
+    let config = env.config();
+    assert_eq!(config.admin, env.admin);
+    assert_eq!(config.rejected_mint_flags, MINT_FLAG_FREEZE_AUTHORITY);
+    assert!(!config.permissionless_pool_creation);
+    assert_eq!(config.pool_creation_fee, 1_000);
+    assert_eq!(config.treasury, env.treasury);
+    assert_eq!(config.creator_fee_share, 2_500);
+    assert_eq!(config.max_referral_fee_bps, 1_000);
+    assert_eq!(config.circuit_breaker_bps, 300);
+
+    // There is only one config
+    assert!(env.initialize_config(env.admin, params).is_err());
+}
+
+#[test]
+fn only_upgrade_authority_initializes_config() {
+    let env = TestEnv::without_config();
+    let impostor = env.wallet();
+
+    assert_swap_error(
+        env.initialize_config(impostor, default_config_params(env.treasury)),
+        SwapError::Unauthorized,
+    );
+}
+
+#[test]
+fn config_rejects_excessive_fee_shares() {
+    let env = TestEnv::without_config();
+    let params = ConfigParams { creator_fee_share: 5_001, ..default_config_params(env.treasury) };
+    assert_swap_error(env.initialize_config(env.admin, params), SwapError::InvalidConfig);
+
+    env.initialize_config(env.admin, default_config_params(env.treasury)).unwrap();
+    assert_swap_error(env.update_config(env.admin, params), SwapError::InvalidConfig);
+
+    let params = ConfigParams { max_referral_fee_bps: 5_001, ..default_config_params(env.treasury) };
+    assert_swap_error(env.update_config(env.admin, params), SwapError::InvalidConfig);
+}
+
+#[test]
+fn config_takes_only_assigned_mint_flags() {
+    let env = TestEnv::new();
+    let params = ConfigParams { rejected_mint_flags: 1 << 3, ..default_config_params(env.treasury) };
+    assert_swap_error(env.update_config(env.admin, params), SwapError::InvalidConfig);
+
+    // Token-2022 risks can be rejected ahead of any such mint being poolable
+    let params = ConfigParams {
+        rejected_mint_flags: MINT_FLAG_PERMANENT_DELEGATE | MINT_FLAG_TRANSFER_HOOK,
+        ..default_config_params(env.treasury)
+    };
+    env.update_config(env.admin, params).unwrap();
+    assert_eq!(env.config().rejected_mint_flags, MINT_FLAG_PERMANENT_DELEGATE | MINT_FLAG_TRANSFER_HOOK);
+    env.create_pool(0, 6, 6);
+}
+
+#[test]
+fn only_admin_updates_config() {
+    let env = TestEnv::new();
+    let params = ConfigParams { pool_creation_fee: 5_000, ..default_config_params(env.treasury) };
+
+    assert_swap_error(env.update_config(env.wallet(), params), SwapError::Unauthorized);
+    env.update_config(env.admin, params).unwrap();
+    assert_eq!(env.config().pool_creation_fee, 5_000);
+}
+
+#[test]
+fn admin_manages_pool_creators() {
+    let env = TestEnv::new();
+    let creator = env.wallet();
+
+    assert_swap_error(env.add_pool_creator(env.wallet(), creator), SwapError::Unauthorized);
+    env.add_pool_creator(env.admin, creator).unwrap();
+    let entry: PoolCreator = env.fetch(&pool_creator_address(&creator));
+    assert_eq!(entry.creator, creator);
+
+    assert_swap_error(env.remove_pool_creator(env.wallet(), creator), SwapError::Unauthorized);
+    let admin_lamports = env.svm.lamports(&env.admin);
+    env.remove_pool_creator(env.admin, creator).unwrap();
+    assert!(env.svm.account(&pool_creator_address(&creator)).is_none());
+    assert!(env.svm.lamports(&env.admin) > admin_lamports);
+}
+
+// ---------- initialize_pool ----------
+
+#[test]
+fn initializes_every_pool_type() {
+    for (pool_type, fee_rate) in [(0, 300), (1, 50), (2, 500)] {
+        let env = TestEnv::new();
+        let pool = env.create_pool(pool_type, 6, 9);
+
+        let state = env.pool_state(&pool);
+        assert_eq!(state.pool_type, pool_type);
+        assert_eq!(state.fee_rate, fee_rate);
+        assert_eq!(state.version, POOL_STATE_VERSION);
+        assert_eq!((state.token_a, state.token_b), (pool.token_a_mint, pool.token_b_mint));
+        assert_eq!(state.lp_mint, pool.lp_mint);
+        assert_eq!((state.token_a_decimals, state.token_b_decimals), (6, 9));
+        assert_eq!((state.token_a_rate, state.token_b_rate), (1_000, 1));
+        assert_eq!((state.reserve_a, state.reserve_b), (0, 0));
+        assert_eq!(state.creator, env.admin);
+
+        let lp_mint = env.mint(&pool.lp_mint);
+        assert_eq!(lp_mint.decimals, 9);
+        assert_eq!(lp_mint.mint_authority, Some(pool.authority).into());
+        assert_eq!(env.token_balance(&pool.vault_a), 0);
+    }
+}
+
+#[test]
+fn lp_decimals_are_capped() {
+    let env = TestEnv::new();
+    let pool = env.create_pool(0, 12, 18);
+
+    assert_eq!(env.mint(&pool.lp_mint).decimals, 9);
+}
+
+#[test]
+fn rejects_unknown_pool_type() {
+    let env = TestEnv::new();
+    let pool = Pool::new(env.create_mint(6), env.create_mint(6));
+
+    assert_swap_error(env.initialize_pool(&pool, 3, env.admin), SwapError::InvalidPoolType);
+}
+
+#[test]
+fn rejects_identical_mints() {
+    let env = TestEnv::new();
+    let mint = env.create_mint(6);
+
+    assert_swap_error(env.initialize_pool(&Pool::new(mint, mint), 0, env.admin), SwapError::IdenticalMints);
+}
+
+#[test]
+fn rejects_mints_with_decimals_too_far_apart() {
+    let env = TestEnv::new();
+    let pool = Pool::new(env.create_mint(0), env.create_mint(20));
+
+    assert_swap_error(env.initialize_pool(&pool, 1, env.admin), SwapError::UnsupportedMintDecimals);
+}
+
+#[test]
+fn rejects_existing_pool() {
+    let env = TestEnv::new();
+    let pool = env.create_pool(0, 6, 6);
+
+    assert!(env.initialize_pool(&pool, 0, env.admin).is_err());
+}
+
+#[test]
+fn records_or_rejects_freeze_authority() {
+    let env = TestEnv::new();
+    let freezable = env.create_mint_with_freeze_authority(6, Some(env.wallet()));
+    let pool = Pool::new(env.create_mint(6), freezable);
+
+    env.initialize_pool(&pool, 0, env.admin).unwrap();
+    let state = env.pool_state(&pool);
+    assert_eq!(state.token_a_flags, 0);
+    assert_eq!(state.token_b_flags, MINT_FLAG_FREEZE_AUTHORITY);
+
+    env.update_config(
+        env.admin,
+        ConfigParams { rejected_mint_flags: MINT_FLAG_FREEZE_AUTHORITY, ..default_config_params(env.treasury) },
+    )
+    .unwrap();
+    let pool = Pool::new(freezable, env.create_mint(6));
+    assert_swap_error(env.initialize_pool(&pool, 0, env.admin), SwapError::MintRejectedByPolicy);
+}
+
+#[test]
+fn permissioned_creation_requires_allowlist_entry() {
+    let env = TestEnv::new();
+    env.update_config(
+        env.admin,
+        ConfigParams { permissionless_pool_creation: false, ..default_config_params(env.treasury) },
+    )
+    .unwrap();
+    let creator = env.wallet();
+    let pool = Pool::new(env.create_mint(6), env.create_mint(6));
+
+    assert_swap_error(env.initialize_pool(&pool, 0, creator), SwapError::Unauthorized);
+
+    env.add_pool_creator(env.admin, creator).unwrap();
+    let ix = env.initialize_pool_ix(&pool, 0, creator, true);
+    env.svm.process_instruction(ix, &[creator]).unwrap();
+    assert_eq!(env.pool_state(&pool).creator, creator);
+
+    env.remove_pool_creator(env.admin, creator).unwrap();
+    let pool = Pool::new(env.create_mint(6), env.create_mint(6));
+    assert_swap_error(env.initialize_pool(&pool, 0, creator), SwapError::Unauthorized);
+}
+
+#[test]
+fn creation_fee_goes_to_treasury() {
+    let env = TestEnv::new();
+    env.update_config(
+        env.admin,
+        ConfigParams { pool_creation_fee: LAMPORTS_PER_SOL / 2, ..default_config_params(env.treasury) },
+    )
+    .unwrap();
+    let treasury_before = env.svm.lamports(&env.treasury);
+
+    env.create_pool(0, 6, 6);
+
+    assert_eq!(env.svm.lamports(&env.treasury), treasury_before + LAMPORTS_PER_SOL / 2);
+}
+
+#[test]
+fn rejects_wrong_treasury() {
+    let env = TestEnv::new();
+    let pool = Pool::new(env.create_mint(6), env.create_mint(6));
+    let mut ix = env.initialize_pool_ix(&pool, 0, env.admin, false);
+    let thief = env.wallet();
+    ix.accounts[2].pubkey = thief;
+
+    assert_swap_error(env.svm.process_instruction(ix, &[env.admin]), SwapError::InvalidTreasury);
+}
+
+// ---------- close_pool ----------
+
+#[test]
+fn admin_closes_an_emptied_pool() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, 1_000_000, 1_000_000);
+    let recipient = env.wallet();
+
+    assert_swap_error(env.close_pool(&pool, env.wallet(), recipient, false), SwapError::Unauthorized);
+    assert_swap_error(env.close_pool(&pool, env.admin, recipient, false), SwapError::PoolNotEmpty);
+
+    env.remove_liquidity(&pool, &provider, env.token_balance(&provider.lp), 0, 0, None).unwrap();
+    // Tokens sent straight to a vault keep it open until skimmed
+    env.mint_to(&pool.vault_a, 10);
+    assert_swap_error(env.close_pool(&pool, env.admin, recipient, false), SwapError::PoolNotEmpty);
+    env.skim(&pool, &provider).unwrap();
+
+    let rent: u64 = [pool.state, pool.vault_a, pool.vault_b].iter().map(|account| env.svm.lamports(account)).sum();
+    let recipient_before = env.svm.lamports(&recipient);
+    env.close_pool(&pool, env.admin, recipient, false).unwrap();
+    assert_eq!(env.svm.lamports(&recipient), recipient_before + rent);
+    for account in [pool.state, pool.vault_a, pool.vault_b] {
+        assert!(env.svm.account(&account).is_none());
+    }
+}
+
+#[test]
+fn closing_a_concentrated_pool_takes_its_ticks() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(2, 1_000_000, 1_000_000);
+    let position = env.position();
+    env.open_position(&pool, &position, provider.key, -500, 500).unwrap();
+    env.remove_liquidity(&pool, &provider, env.token_balance(&provider.lp), 0, 0, None).unwrap();
+    env.skim(&pool, &provider).unwrap();
+    let recipient = env.wallet();
+
+    assert_swap_error(env.close_pool(&pool, env.admin, recipient, false), SwapError::MissingPoolTicks);
+    let rent: u64 = [pool.state, pool.vault_a, pool.vault_b, pool.ticks].iter().map(|account| env.svm.lamports(account)).sum();
+    let recipient_before = env.svm.lamports(&recipient);
+    env.close_pool(&pool, env.admin, recipient, true).unwrap();
+    assert!(env.svm.account(&pool.ticks).is_none());
+    assert_eq!(env.svm.lamports(&recipient), recipient_before + rent);
+}
+
+#[test]
+fn closed_pool_can_be_created_again() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, 1_000_000, 1_000_000);
+    env.remove_liquidity(&pool, &provider, env.token_balance(&provider.lp), 0, 0, None).unwrap();
+    env.skim(&pool, &provider).unwrap();
+    env.close_pool(&pool, env.admin, env.admin, false).unwrap();
+    assert!(env.svm.account(&pool.lp_mint).is_some());
+
+    // The new pool takes over the LP mint the old one left behind
+    env.initialize_pool(&pool, 1, env.admin).unwrap();
+    assert_eq!((env.pool_state(&pool).pool_type, env.pool_state(&pool).lp_mint), (1, pool.lp_mint));
+    env.mint_to(&provider.token_a, 1_000_000);
+    env.mint_to(&provider.token_b, 1_000_000);
+    env.add_liquidity(&pool, &provider, 1_000_000, 1_000_000, None).unwrap();
+    assert!(env.token_balance(&provider.lp) > 0);
+    env.swap(&pool, &provider, 1_000, 0, true).unwrap();
+}
+
+#[test]
+fn pools_with_a_farm_close_after_it() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, 1_000_000, 1_000_000);
+    let farm = FarmAccounts::new(&pool, env.create_mint(6));
+    let funding = env.create_token_account(&farm.reward_mint, &env.admin, 1_000);
+    let now = env.svm.clock().unix_timestamp;
+    env.create_farm(&farm, env.admin, funding, now + 100, now + 200, 10).unwrap();
+    env.remove_liquidity(&pool, &provider, env.token_balance(&provider.lp), 0, 0, None).unwrap();
+    env.skim(&pool, &provider).unwrap();
+
+    assert_swap_error(env.close_pool(&pool, env.admin, env.admin, false), SwapError::PoolNotEmpty);
+    assert!(env.svm.account(&pool.state).is_some());
+
+    // Once the farm has run its course and is closed, so can the pool be
+    let returned = env.create_token_account(&farm.reward_mint, &env.admin, 0);
+    env.svm.warp_to_timestamp(now + 200);
+    env.close_farm(&farm, env.admin, returned, env.admin).unwrap();
+    env.close_pool(&pool, env.admin, env.admin, false).unwrap();
+    assert!(env.svm.account(&pool.state).is_none());
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::*;
+use solana_amm_educational_template::ConfigParams;
+
+const LIQUIDITY: u64 = 1_000_000_000;
+const DONATION: u64 = 250_000_000;
+
+#[test]
+fn donations_do_not_move_the_price() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+    let undisturbed_out = env.token_balance(&trader.token_b);
+
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    env.mint_to(&pool.vault_b, DONATION);
+    let trader = env.user(&pool, 10_000_000, 0);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+
+    assert_eq!(env.token_balance(&trader.token_b), undisturbed_out);
+}
+
+#[test]
+fn skim_sends_surplus_to_recipient() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    env.mint_to(&pool.vault_a, DONATION);
+    env.mint_to(&pool.vault_b, DONATION / 2);
+
+    let recipient = env.user(&pool, 0, 0);
+    env.skim(&pool, &recipient).unwrap();
+
+    assert_eq!(env.token_balance(&recipient.token_a), DONATION);
+    assert_eq!(env.token_balance(&recipient.token_b), DONATION / 2);
+    assert_eq!(env.token_balance(&pool.vault_a), LIQUIDITY);
+    assert_eq!(env.token_balance(&pool.vault_b), LIQUIDITY);
+
+    // Nothing left to skim
+    env.skim(&pool, &recipient).unwrap();
+    assert_eq!(env.token_balance(&recipient.token_a), DONATION);
+}
+
+#[test]
+fn sync_folds_donations_into_reserves() {
+    let env = TestEnv::new();
+    let (pool, provider) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    env.mint_to(&pool.vault_a, DONATION);
+
+    env.sync(&pool).unwrap();
+    let state = env.pool_state(&pool);
+    assert_eq!((state.reserve_a, state.reserve_b), (LIQUIDITY + DONATION, LIQUIDITY));
+
+    // The donation now belongs to the LPs
+    env.remove_liquidity(&pool, &provider, 1_000_000, 0, 0, None).unwrap();
+    assert_eq!(env.token_balance(&provider.token_a), LIQUIDITY + DONATION);
+}
+
+#[test]
+fn sync_and_skim_leave_creator_fees_alone() {
+    let env = TestEnv::new();
+    env.update_config(env.admin, ConfigParams { creator_fee_share: 5_000, ..default_config_params(env.treasury) })
+        .unwrap();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+    let before = env.pool_state(&pool);
+    assert!(before.creator_fees_a > 0);
+
+    let recipient = env.user(&pool, 0, 0);
+    env.skim(&pool, &recipient).unwrap();
+    assert_eq!(env.token_balance(&recipient.token_a), 0);
+
+    env.sync(&pool).unwrap();
+    let after = env.pool_state(&pool);
+    assert_eq!((after.reserve_a, after.reserve_b), (before.reserve_a, before.reserve_b));
+    assert_eq!(after.creator_fees_a, before.creator_fees_a);
+}
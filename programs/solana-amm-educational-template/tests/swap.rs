@@ -0,0 +1,375 @@
+mod common;
+
+use common::*;
+use solana_amm_educational_template::{sqrt_price_at_tick, CircuitBreakerTripped, ConfigParams, SwapError, SwapExecuted, U256};
+
+const LIQUIDITY: u64 = 1_000_000_000;
+
+// Output of a standard pool swap, fee taken out of the input first
+fn constant_product_out(reserve_in: u64, reserve_out: u64, amount_in: u64, fee_rate: u64) -> u64 {
+    let amount_in = amount_in - amount_in * fee_rate / 100_000;
+    (reserve_out as u128 * amount_in as u128 / (reserve_in as u128 + amount_in as u128)) as u64
+}
+
+#[test]
+fn standard_swap_follows_constant_product() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+
+    let expected = constant_product_out(LIQUIDITY, LIQUIDITY, 10_000_000, 300);
+    env.swap(&pool, &trader, 10_000_000, expected, true).unwrap();
+
+    assert_eq!(env.token_balance(&trader.token_a), 0);
+    assert_eq!(env.token_balance(&trader.token_b), expected);
+
+    let state = env.pool_state(&pool);
+    assert_eq!(state.reserve_a, LIQUIDITY + 10_000_000);
+    assert_eq!(state.reserve_b, LIQUIDITY - expected);
+    assert_eq!(env.token_balance(&pool.vault_a), state.reserve_a);
+    assert_eq!(env.token_balance(&pool.vault_b), state.reserve_b);
+}
+
+#[test]
+fn swap_emits_event_with_new_reserves() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+
+    let [event] = env.events::<SwapExecuted>().try_into().unwrap();
+    let state = env.pool_state(&pool);
+    assert_eq!((event.pool, event.user, event.a_to_b, event.flash), (pool.state, trader.key, true, false));
+    assert_eq!((event.amount_in, event.amount_out), (10_000_000, env.token_balance(&trader.token_b)));
+    assert_eq!((event.fee, event.creator_fee), (30_000, 0));
+    assert_eq!((event.reserve_a, event.reserve_b), (state.reserve_a, state.reserve_b));
+}
+
+#[test]
+fn standard_swap_b_to_a() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, 2 * LIQUIDITY);
+    let trader = env.user(&pool, 0, 10_000_000);
+
+    let expected = constant_product_out(2 * LIQUIDITY, LIQUIDITY, 10_000_000, 300);
+    env.swap(&pool, &trader, 10_000_000, 0, false).unwrap();
+
+    assert_eq!(env.token_balance(&trader.token_a), expected);
+    let state = env.pool_state(&pool);
+    assert_eq!(state.reserve_a, LIQUIDITY - expected);
+    assert_eq!(state.reserve_b, 2 * LIQUIDITY + 10_000_000);
+}
+
+#[test]
+fn stable_swap_stays_close_to_peg() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(1, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 1_000_000, 0);
+
+    env.swap(&pool, &trader, 1_000_000, 0, true).unwrap();
+
+    // 0.05% fee and almost no curve slippage this close to balance
+    let out = env.token_balance(&trader.token_b);
+    assert!((999_000..1_000_000).contains(&out), "got {out}");
+    // A constant product pool of the same size would pay noticeably less
+    assert!(out > constant_product_out(LIQUIDITY, LIQUIDITY, 1_000_000, 50));
+}
+
+#[test]
+fn stable_swap_normalizes_decimals() {
+    let env = TestEnv::new();
+    // 1000 of a 6-decimal token against 1000 of a 9-decimal token
+    let pool = env.create_pool(1, 6, 9);
+    let provider = env.user(&pool, 1_000_000_000, 1_000_000_000_000);
+    env.add_liquidity(&pool, &provider, 1_000_000_000, 1_000_000_000_000, None).unwrap();
+
+    let trader = env.user(&pool, 1_000_000, 1_000_000_000);
+    env.swap(&pool, &trader, 1_000_000, 0, true).unwrap();
+    let out_b = env.token_balance(&trader.token_b) - 1_000_000_000;
+    assert!((999_000_000..1_000_000_000).contains(&out_b), "got {out_b}");
+
+    env.swap(&pool, &trader, 1_000_000_000, 0, false).unwrap();
+    let out_a = env.token_balance(&trader.token_a);
+    assert!((999_000..1_000_000).contains(&out_a), "got {out_a}");
+}
+
+#[test]
+fn concentrated_swap_follows_full_range_curve() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(2, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+
+    env.swap(&pool, &trader, 10_000_000, 1, true).unwrap();
+
+    // Full-range concentrated liquidity prices like constant product
+    let out = env.token_balance(&trader.token_b);
+    assert_eq!(out, constant_product_out(LIQUIDITY, LIQUIDITY, 10_000_000, 500));
+    let state = env.pool_state(&pool);
+    assert_eq!(state.reserve_a, LIQUIDITY + 10_000_000);
+    assert_eq!(state.reserve_b, LIQUIDITY - out);
+}
+
+#[test]
+fn swap_rejects_zero_amount() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+
+    assert_swap_error(env.swap(&pool, &trader, 0, 0, true), SwapError::InvalidAmount);
+}
+
+#[test]
+fn swap_rejects_output_below_minimum() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+
+    let expected = constant_product_out(LIQUIDITY, LIQUIDITY, 10_000_000, 300);
+    assert_swap_error(
+        env.swap(&pool, &trader, 10_000_000, expected + 1, true),
+        SwapError::SlippageExceeded,
+    );
+
+    // The failed transaction leaves everything untouched
+    assert_eq!(env.token_balance(&trader.token_a), 10_000_000);
+    assert_eq!(env.pool_state(&pool).reserve_a, LIQUIDITY);
+}
+
+#[test]
+fn swap_on_empty_pool_fails() {
+    let env = TestEnv::new();
+    let pool = env.create_pool(0, 6, 6);
+    let trader = env.user(&pool, 10_000_000, 0);
+
+    assert_swap_error(env.swap(&pool, &trader, 10_000_000, 0, true), SwapError::InsufficientLiquidity);
+}
+
+#[test]
+fn swap_respects_deadline() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+    env.svm.warp_to_timestamp(1_000);
+
+    let late = env.swap_ix(&pool, &trader, 5_000_000, 0, true, None, Some(999));
+    assert_swap_error(env.svm.process_instruction(late, &[trader.key]), SwapError::DeadlineExceeded);
+
+    let in_time = env.swap_ix(&pool, &trader, 5_000_000, 0, true, None, Some(1_000));
+    env.svm.process_instruction(in_time, &[trader.key]).unwrap();
+}
+
+#[test]
+fn swap_rejects_token_account_of_another_mint() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let mut trader = env.user(&pool, 10_000_000, 0);
+    let other_mint = env.create_mint(6);
+    trader.token_b = env.create_token_account(&other_mint, &trader.key, 0);
+
+    assert_swap_error(env.swap(&pool, &trader, 10_000_000, 0, true), SwapError::InvalidTokenMint);
+}
+
+#[test]
+fn swap_rejects_substituted_vault() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+
+    let attacker = env.wallet();
+    let fake_vault = env.create_token_account(&pool.token_b_mint, &attacker, LIQUIDITY);
+    let fake_pool = Pool { vault_b: fake_vault, ..pool };
+
+    // The vault seeds pin the account before the InvalidVaultAuthority check runs
+    assert_eq!(
+        env.swap(&fake_pool, &trader, 10_000_000, 0, true),
+        Err(anchor_error(anchor_lang::error::ErrorCode::ConstraintSeeds))
+    );
+}
+
+#[test]
+fn stable_swap_overflow_is_reported() {
+    let env = TestEnv::new();
+    // Rates of 10^19 push the normalized balances past what the invariant can hold
+    let pool = env.create_pool(1, 0, 19);
+    let amount = 10_000_000_000_000_000_000;
+    let provider = env.user(&pool, amount, amount);
+    env.add_liquidity(&pool, &provider, amount, amount, None).unwrap();
+
+    let trader = env.user(&pool, 1_000, 0);
+    assert_swap_error(env.swap(&pool, &trader, 1_000, 0, true), SwapError::MathOverflow);
+}
+
+#[test]
+fn creator_earns_share_of_swap_fees() {
+    let env = TestEnv::new();
+    env.update_config(env.admin, ConfigParams { creator_fee_share: 5_000, ..default_config_params(env.treasury) })
+        .unwrap();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    assert_eq!(env.pool_state(&pool).creator, env.admin);
+    assert_eq!(env.pool_state(&pool).creator_fee_share, 5_000);
+
+    let trader = env.user(&pool, 10_000_000, 0);
+    env.swap(&pool, &trader, 10_000_000, 0, true).unwrap();
+
+    // Half of the 30_000 fee is set aside for the creator
+    let state = env.pool_state(&pool);
+    assert_eq!(state.creator_fees_a, 15_000);
+    assert_eq!(state.creator_fees_b, 0);
+    assert_eq!(state.reserve_a, LIQUIDITY + 10_000_000 - 15_000);
+    assert_eq!(env.token_balance(&pool.vault_a), LIQUIDITY + 10_000_000);
+
+    let stranger = env.user(&pool, 0, 0);
+    assert_swap_error(env.collect_creator_fees(&pool, &stranger), SwapError::Unauthorized);
+
+    let creator = User {
+        key: env.admin,
+        token_a: env.create_token_account(&pool.token_a_mint, &env.admin, 0),
+        token_b: env.create_token_account(&pool.token_b_mint, &env.admin, 0),
+        lp: env.create_token_account(&pool.lp_mint, &env.admin, 0),
+    };
+    env.collect_creator_fees(&pool, &creator).unwrap();
+
+    assert_eq!(env.token_balance(&creator.token_a), 15_000);
+    let state = env.pool_state(&pool);
+    assert_eq!(state.creator_fees_a, 0);
+    assert_eq!(env.token_balance(&pool.vault_a), state.reserve_a);
+}
+
+#[test]
+fn referrer_is_paid_a_share_of_swap_fees() {
+    let env = TestEnv::new();
+    let params = ConfigParams { creator_fee_share: 2_000, max_referral_fee_bps: 3_000, ..default_config_params(env.treasury) };
+    env.update_config(env.admin, params).unwrap();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 0);
+    let referrer = env.create_token_account(&pool.token_a_mint, &env.wallet(), 0);
+
+    let expected = constant_product_out(LIQUIDITY, LIQUIDITY, 10_000_000, 300);
+    env.swap_with_referral(&pool, &trader, 10_000_000, true, Some(referrer), 3_000).unwrap();
+
+    // Of the 30_000 fee, 9_000 goes to the referrer and 6_000 to the creator;
+    // the price is the same as without a referrer
+    assert_eq!(env.token_balance(&trader.token_b), expected);
+    assert_eq!(env.token_balance(&referrer), 9_000);
+    let state = env.pool_state(&pool);
+    assert_eq!(state.creator_fees_a, 6_000);
+    assert_eq!(state.reserve_a, LIQUIDITY + 10_000_000 - 6_000 - 9_000);
+    assert_eq!(env.token_balance(&pool.vault_a), state.reserve_a + state.creator_fees_a);
+
+    let event = env.events::<SwapExecuted>().pop().unwrap();
+    assert_eq!((event.referrer, event.referral_fee, event.creator_fee), (Some(referrer), 9_000, 6_000));
+}
+
+#[test]
+fn referral_fee_is_capped_by_config() {
+    let env = TestEnv::new();
+    env.update_config(env.admin, ConfigParams { max_referral_fee_bps: 1_000, ..default_config_params(env.treasury) })
+        .unwrap();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 10_000_000, 10_000_000);
+    let referrer_a = env.create_token_account(&pool.token_a_mint, &env.wallet(), 0);
+
+    assert_swap_error(
+        env.swap_with_referral(&pool, &trader, 1_000_000, true, Some(referrer_a), 1_001),
+        SwapError::ReferralFeeTooHigh,
+    );
+    assert_swap_error(env.swap_with_referral(&pool, &trader, 1_000_000, true, None, 1_000), SwapError::MissingReferrer);
+    // Referrers are paid in the input token
+    assert_swap_error(
+        env.swap_with_referral(&pool, &trader, 1_000_000, false, Some(referrer_a), 1_000),
+        SwapError::InvalidTokenMint,
+    );
+
+    // A referrer can be named without taking a cut
+    env.swap_with_referral(&pool, &trader, 1_000_000, true, Some(referrer_a), 0).unwrap();
+    assert_eq!(env.token_balance(&referrer_a), 0);
+    let event = env.events::<SwapExecuted>().pop().unwrap();
+    assert_eq!((event.referrer, event.referral_fee), (Some(referrer_a), 0));
+}
+
+#[test]
+fn price_impact_guard_rejects_large_moves() {
+    let env = TestEnv::new();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 20_000_000, 0);
+
+    // 10_000_000 into 1_000_000_000 moves the price about 2%
+    assert_swap_error(
+        env.swap_with_limits(&pool, &trader, 10_000_000, true, Some(150), None),
+        SwapError::PriceImpactExceeded,
+    );
+    env.swap_with_limits(&pool, &trader, 10_000_000, true, Some(250), None).unwrap();
+
+    // Stable pools barely move near balance
+    let (stable, _) = env.create_funded_pool(1, LIQUIDITY, LIQUIDITY);
+    let stable_trader = env.user(&stable, 10_000_000, 0);
+    env.swap_with_limits(&stable, &stable_trader, 10_000_000, true, Some(10), None).unwrap();
+}
+
+#[test]
+fn constant_product_swaps_fill_up_to_their_price_limit() {
+    let env = TestEnv::new();
+    // About 1% below the starting price of 1
+    let limit = sqrt_price_at_tick(-100).unwrap();
+    let limit_price = (U256::from(limit) * U256::from(limit)) >> 64;
+
+    // Standard pools and concentrated pools without ranged positions share the curve
+    for pool_type in [0, 2] {
+        let (pool, _) = env.create_funded_pool(pool_type, LIQUIDITY, LIQUIDITY);
+        let trader = env.user(&pool, 20_000_000, 20_000_000);
+
+        // A limit in the other direction can't be met by selling A
+        assert_swap_error(
+            env.swap_with_limits(&pool, &trader, 1_000_000, true, None, Some(sqrt_price_at_tick(100).unwrap())),
+            SwapError::InvalidPriceLimit,
+        );
+
+        // About 5_000_000 gets the price to the limit; the rest stays with the trader
+        env.swap_with_limits(&pool, &trader, 10_000_000, true, None, Some(limit)).unwrap();
+        let event = env.events::<SwapExecuted>().pop().unwrap();
+        let spent = 20_000_000 - env.token_balance(&trader.token_a);
+        assert_eq!(event.amount_in, spent);
+        assert!(spent > 4_900_000 && spent < 5_100_000, "spent {spent}");
+        let state = env.pool_state(&pool);
+        assert_eq!(event.amount_out, constant_product_out(LIQUIDITY, LIQUIDITY, spent, state.fee_rate as u64));
+        let price = state.spot_price_x64().unwrap();
+        assert!(price >= limit_price && price - limit_price < limit_price / 100_000);
+    }
+
+    // Stable pools can't stop part way, so the swap fails whole
+    let (stable, _) = env.create_funded_pool(1, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&stable, 20_000_000, 0);
+    let tight = (1 << 64) - (1 << 40);
+    assert_swap_error(env.swap_with_limits(&stable, &trader, 10_000_000, true, None, Some(tight)), SwapError::PriceLimitExceeded);
+    env.swap_with_limits(&stable, &trader, 10_000_000, true, None, Some(limit)).unwrap();
+}
+
+#[test]
+fn circuit_breaker_caps_price_moves_within_a_slot() {
+    let env = TestEnv::new();
+    env.update_config(env.admin, ConfigParams { circuit_breaker_bps: 100, ..default_config_params(env.treasury) })
+        .unwrap();
+    let (pool, _) = env.create_funded_pool(0, LIQUIDITY, LIQUIDITY);
+    let trader = env.user(&pool, 20_000_000, 20_000_000);
+    env.svm.warp_to_slot(10);
+
+    // Each swap moves the price about 0.6%, two of them in one slot 1.2%
+    env.swap(&pool, &trader, 3_000_000, 0, true).unwrap();
+    let state = env.pool_state(&pool);
+    assert_eq!((state.breaker_slot, state.breaker_price_x64), (10, 1 << 64));
+    assert_swap_error(env.swap(&pool, &trader, 3_000_000, 0, true), SwapError::CircuitBreakerTripped);
+    let tripped = env.events::<CircuitBreakerTripped>();
+    assert_eq!((tripped.len(), tripped[0].pool, tripped[0].slot), (1, pool.state, 10));
+    assert_eq!((tripped[0].slot_price_x64, tripped[0].circuit_breaker_bps), (1 << 64, 100));
+    assert!(tripped[0].price_x64 < (1 << 64) * 99 / 100);
+
+    // Swapping back within the slot is fine, as is the next slot
+    env.swap(&pool, &trader, 3_000_000, 0, false).unwrap();
+    env.svm.warp_to_slot(11);
+    env.swap(&pool, &trader, 3_000_000, 0, true).unwrap();
+    assert_eq!(env.pool_state(&pool).breaker_slot, 11);
+
+    // A single swap can trip it too
+    env.svm.warp_to_slot(12);
+    assert_swap_error(env.swap(&pool, &trader, 15_000_000, 0, true), SwapError::CircuitBreakerTripped);
+}
@@ -35,6 +35,7 @@ const IDL = {
       "accounts": [
         {
           "name": "pool_state",
+          "writable": true,
           "pda": {
             "seeds": [
               {
@@ -152,6 +153,47 @@ const IDL = {
         {
           "name": "amount_b",
           "type": "u64"
+        },
+        {
+          "name": "deadline",
+          "type": {
+            "option": "i64"
+          }
+        }
+      ]
+    },
+    {
+      "name": "initialize_config",
+      "discriminator": [208, 127, 21, 1, 194, 190, 196, 70],
+      "accounts": [
+        {
+          "name": "amm_config",
+          "writable": true
+        },
+        {
+          "name": "admin",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "program"
+        },
+        {
+          "name": "program_data"
+        },
+        {
+          "name": "system_program",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "params",
+          "type": {
+            "defined": {
+              "name": "ConfigParams"
+            }
+          }
         }
       ]
     },
@@ -159,6 +201,17 @@ const IDL = {
       "name": "initialize_pool",
       "discriminator": [95, 180, 10, 172, 84, 174, 232, 40],
       "accounts": [
+        {
+          "name": "amm_config"
+        },
+        {
+          "name": "pool_creator",
+          "optional": true
+        },
+        {
+          "name": "treasury",
+          "writable": true
+        },
         {
           "name": "pool_state",
           "writable": true,
@@ -297,6 +350,42 @@ const IDL = {
     }
   ],
   "types": [
+    {
+      "name": "ConfigParams",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "rejected_mint_flags",
+            "type": "u8"
+          },
+          {
+            "name": "permissionless_pool_creation",
+            "type": "bool"
+          },
+          {
+            "name": "pool_creation_fee",
+            "type": "u64"
+          },
+          {
+            "name": "treasury",
+            "type": "pubkey"
+          },
+          {
+            "name": "creator_fee_share",
+            "type": "u16"
+          },
+          {
+            "name": "max_referral_fee_bps",
+            "type": "u16"
+          },
+          {
+            "name": "circuit_breaker_bps",
+            "type": "u16"
+          }
+        ]
+      }
+    },
     {
       "name": "PoolState",
       "type": {
@@ -484,7 +573,43 @@ async function setupThreeCurrencyPools() {
     anchor.setProvider(provider);
     const program = new Program(IDL as any, provider);
 
-    // 5. Setup Each Pool
+    // 5. Initialize the AMM config (once per deployment)
+    const [ammConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("amm_config")],
+      programId
+    );
+    const existingConfig = await connection.getAccountInfo(ammConfig);
+    if (existingConfig) {
+      console.log(`\nAMM config already initialized: ${ammConfig.toString()}`);
+    } else {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [programId.toBuffer()],
+        new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+      );
+      console.log(`\nInitializing AMM config: ${ammConfig.toString()}`);
+      const configTx = await program.methods
+        .initializeConfig({
+          rejectedMintFlags: 0,
+          permissionlessPoolCreation: true,
+          poolCreationFee: new anchor.BN(0),
+          treasury: deployerKeypair.publicKey,
+          creatorFeeShare: 0,
+          maxReferralFeeBps: 0,
+          circuitBreakerBps: 0,
+        })
+        .accounts({
+          ammConfig,
+          admin: deployerKeypair.publicKey,
+          program: programId,
+          programData,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([deployerKeypair])
+        .rpc();
+      console.log(`AMM config initialized: ${configTx}`);
+    }
+
+    // 6. Setup Each Pool
     console.log("\nInitializing currency exchange pools...");
     
     const poolSummary: any[] = [];
@@ -538,6 +663,9 @@ async function setupThreeCurrencyPools() {
       const tx = await program.methods
         .initializePool(pool.poolType)
         .accounts({
+          ammConfig,
+          poolCreator: null,
+          treasury: deployerKeypair.publicKey,
           poolState,
           tokenAMint: pool.tokenA.mint!,
           tokenBMint: pool.tokenB.mint!,
@@ -607,7 +735,8 @@ async function setupThreeCurrencyPools() {
       const addLiquidityTx = await program.methods
         .addLiquidity(
           new anchor.BN(amountAWithDecimals),
-          new anchor.BN(amountBWithDecimals)
+          new anchor.BN(amountBWithDecimals),
+          null // deadline
         )
         .accounts({
           poolState,
@@ -641,7 +770,7 @@ async function setupThreeCurrencyPools() {
       });
     }
 
-    // 6. Print Final Summary
+    // 7. Print Final Summary
     console.log("\nTHREE CURRENCY EXCHANGE POOLS SETUP COMPLETE!");
     console.log("=====================================================");
     console.log("\nAvailable Currency Exchange Routes:");
@@ -0,0 +1,152 @@
+// amm-sim: backtests pool types and fee rates against a price series, to
+// help pick a pool_type and fee for a currency pair.
+//
+//   amm-sim prices.csv --noise-size 500 --fee-rates 50,300,500
+//
+// Every scenario starts from the same deposit, sees the same prices and the
+// same noise trades, and is reported in token B.
+
+mod series;
+mod sim;
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use solana_amm_educational_template::{pool_fee_rate, FEE_DENOMINATOR};
+
+use series::read_series;
+use sim::{simulate, Report, Scenario, Settings};
+
+#[derive(Parser)]
+#[command(name = "amm-sim", version, about = "Backtest AMM pool types and fees against a price series")]
+struct Cli {
+    /// CSV with `time,price[,trade]` rows; price is token A in token B
+    prices: PathBuf,
+
+    /// Pool types to simulate
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [PoolType::Standard, PoolType::Stable, PoolType::Concentrated])]
+    pool_types: Vec<PoolType>,
+
+    /// Fee rates out of 100000 to try with every pool type; defaults to each
+    /// type's on-chain fee
+    #[arg(long, value_delimiter = ',')]
+    fee_rates: Vec<u32>,
+
+    /// LP deposit of token A in whole tokens, matched with B at the first price
+    #[arg(long, default_value_t = 1_000_000.0)]
+    liquidity: f64,
+
+    #[arg(long, default_value_t = 6)]
+    decimals_a: u8,
+
+    #[arg(long, default_value_t = 6)]
+    decimals_b: u8,
+
+    /// Average size of a generated noise trade per step, in whole token A
+    #[arg(long, default_value_t = 0.0)]
+    noise_size: f64,
+
+    /// Smallest profit, in whole token B, an arbitrageur acts on
+    #[arg(long, default_value_t = 0.0)]
+    arb_min_profit: f64,
+
+    /// Seed for generated noise trades
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// Print JSON instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PoolType {
+    Standard,
+    Stable,
+    Concentrated,
+}
+
+impl PoolType {
+    fn id(self) -> u8 {
+        match self {
+            PoolType::Standard => 0,
+            PoolType::Stable => 1,
+            PoolType::Concentrated => 2,
+        }
+    }
+}
+
+fn pool_type_name(pool_type: u8) -> &'static str {
+    match pool_type {
+        0 => "standard",
+        1 => "stable",
+        2 => "concentrated",
+        _ => "unknown",
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if cli.fee_rates.iter().any(|&fee_rate| fee_rate as u128 > FEE_DENOMINATOR) {
+        bail!("fee rates are out of {FEE_DENOMINATOR}");
+    }
+    let file = File::open(&cli.prices).with_context(|| format!("opening {}", cli.prices.display()))?;
+    let series = read_series(file).with_context(|| format!("reading {}", cli.prices.display()))?;
+
+    let settings = Settings {
+        decimals_a: cli.decimals_a,
+        decimals_b: cli.decimals_b,
+        liquidity_a: cli.liquidity,
+        noise_size: cli.noise_size,
+        arb_min_profit: cli.arb_min_profit,
+        seed: cli.seed,
+    };
+
+    let mut reports = Vec::new();
+    for pool_type in &cli.pool_types {
+        let pool_type = pool_type.id();
+        let fee_rates = if cli.fee_rates.is_empty() {
+            pool_fee_rate(pool_type).into_iter().collect()
+        } else {
+            cli.fee_rates.clone()
+        };
+        for fee_rate in fee_rates {
+            reports.push(simulate(Scenario { pool_type, fee_rate }, &settings, &series)?);
+        }
+    }
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        print_table(&reports, series.len());
+    }
+    Ok(())
+}
+
+fn print_table(reports: &[Report], steps: usize) {
+    println!("{steps} price steps, values in token B\n");
+    println!(
+        "{:<13} {:>7} {:>16} {:>16} {:>14} {:>9} {:>14} {:>14} {:>7}",
+        "pool", "fee", "volume", "fees earned", "LP PnL", "IL", "vs HODL", "arb volume", "swaps"
+    );
+    for report in reports {
+        println!(
+            "{:<13} {:>6.3}% {:>16.2} {:>16.2} {:>14.2} {:>8.3}% {:>14.2} {:>14.2} {:>7}",
+            pool_type_name(report.pool_type),
+            report.fee_rate as f64 / FEE_DENOMINATOR as f64 * 100.0,
+            report.volume,
+            report.fees_earned,
+            report.pnl,
+            report.impermanent_loss * 100.0,
+            report.end_value - report.hodl_value,
+            report.arbitrage_volume,
+            report.swaps,
+        );
+    }
+    let unfilled: u64 = reports.iter().map(|report| report.unfilled_trades).sum();
+    if unfilled > 0 {
+        println!("\n{unfilled} noise trades could not be filled");
+    }
+}
@@ -0,0 +1,59 @@
+// Price series input: a CSV with a `time` and `price` column and an optional
+// `trade` column.
+//
+//   time,price,trade
+//   2024-01-01T00:00,149.20,
+//   2024-01-01T01:00,149.35,-2500
+//
+// `price` is the external value of one token A in token B. `trade` is an
+// order to replay at that step, in whole token A: positive sells A to the
+// pool, negative buys that much A's worth with token B.
+
+use std::io::Read;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct PricePoint {
+    pub time: String,
+    pub price: f64,
+    #[serde(default)]
+    pub trade: Option<f64>,
+}
+
+pub fn read_series(reader: impl Read) -> Result<Vec<PricePoint>> {
+    let mut points = Vec::new();
+    for (index, row) in csv::Reader::from_reader(reader).deserialize().enumerate() {
+        let point: PricePoint = row.with_context(|| format!("row {}", index + 1))?;
+        if !(point.price.is_finite() && point.price > 0.0) {
+            bail!("row {}: price must be positive, got {}", index + 1, point.price);
+        }
+        points.push(point);
+    }
+    if points.is_empty() {
+        bail!("price series is empty");
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_prices_and_optional_trades() {
+        let csv = "time,price,trade\nt0,1.0,\nt1,1.5,-20\n";
+        let points = read_series(csv.as_bytes()).unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].trade, None);
+        assert_eq!((points[1].price, points[1].trade), (1.5, Some(-20.0)));
+
+        // The trade column can be left out entirely
+        let points = read_series("time,price\nt0,2\n".as_bytes()).unwrap();
+        assert_eq!(points[0].price, 2.0);
+
+        assert!(read_series("time,price\nt0,0\n".as_bytes()).is_err());
+        assert!(read_series("time,price\n".as_bytes()).is_err());
+    }
+}
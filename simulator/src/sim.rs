@@ -0,0 +1,347 @@
+// Market simulation for one pool: a single liquidity provider, an arbitrageur
+// who trades the pool back towards the external price whenever that pays,
+// and noise traders. Swaps are priced by `PoolState::quote_swap`, the same
+// math the program runs, and settle the way the swap instruction does.
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use solana_amm_educational_template::{PoolState, POOL_STATE_VERSION};
+
+use crate::series::PricePoint;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Scenario {
+    pub pool_type: u8,
+    pub fee_rate: u32, // Out of FEE_DENOMINATOR, as on chain
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub decimals_a: u8,
+    pub decimals_b: u8,
+    pub liquidity_a: f64,    // LP deposit of token A in whole tokens, matched with B at the first price
+    pub noise_size: f64,     // Average generated noise trade in whole token A; 0 turns them off
+    pub arb_min_profit: f64, // Smallest profit, in whole token B, an arbitrageur acts on
+    pub seed: u64,
+}
+
+// Values are in whole token B
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub pool_type: u8,
+    pub fee_rate: u32,
+    pub start_value: f64,      // LP deposit at the first price
+    pub end_value: f64,        // LP position at the last price
+    pub hodl_value: f64,       // The deposit kept out of the pool, at the last price
+    pub pnl: f64,              // end_value - start_value
+    pub fees_earned: f64,      // LP share of swap fees, at the last price
+    pub impermanent_loss: f64, // How far the position without its fees trails holding, as a fraction
+    pub volume: f64,           // Swap inputs, valued when traded
+    pub arbitrage_volume: f64,
+    pub noise_volume: f64,
+    pub swaps: u64,
+    pub unfilled_trades: u64, // Noise trades the pool couldn't fill
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Trader {
+    Arbitrageur,
+    Noise,
+}
+
+struct Market {
+    pool: PoolState,
+    scale_a: f64,
+    scale_b: f64,
+    fees_a: u64,
+    fees_b: u64,
+    arbitrage_volume: f64,
+    noise_volume: f64,
+    swaps: u64,
+}
+
+impl Market {
+    // Value of the output minus the input, in whole token B
+    fn profit(&self, amount_in: u64, a_to_b: bool, price: f64) -> f64 {
+        match self.pool.quote_swap(amount_in, a_to_b) {
+            Ok(quote) if a_to_b => quote.amount_out as f64 / self.scale_b - amount_in as f64 / self.scale_a * price,
+            Ok(quote) => quote.amount_out as f64 / self.scale_a * price - amount_in as f64 / self.scale_b,
+            Err(_) => f64::NEG_INFINITY,
+        }
+    }
+
+    // Settles a swap like the swap instruction: the input minus the
+    // creator's cut joins the reserves and the output leaves them
+    fn swap(&mut self, amount_in: u64, a_to_b: bool, price: f64, trader: Trader) -> bool {
+        let Ok(quote) = self.pool.quote_swap(amount_in, a_to_b) else {
+            return false;
+        };
+        let (input_reserve, output_reserve) = self.pool.reserves(a_to_b);
+        let Some(input_after) = input_reserve.checked_add(amount_in - quote.creator_fee) else {
+            return false;
+        };
+        let output_after = output_reserve - quote.amount_out;
+        let lp_fee = quote.fee - quote.creator_fee;
+        let value_in = if a_to_b {
+            (self.pool.reserve_a, self.pool.reserve_b) = (input_after, output_after);
+            self.fees_a += lp_fee;
+            amount_in as f64 / self.scale_a * price
+        } else {
+            (self.pool.reserve_b, self.pool.reserve_a) = (input_after, output_after);
+            self.fees_b += lp_fee;
+            amount_in as f64 / self.scale_b
+        };
+
+        match trader {
+            Trader::Arbitrageur => self.arbitrage_volume += value_in,
+            Trader::Noise => self.noise_volume += value_in,
+        }
+        self.swaps += 1;
+        true
+    }
+
+    // Most profitable input for a swap in one direction, if any trade pays.
+    // Profit is concave in the input, so it's found by doubling past the
+    // peak and then narrowing in on it.
+    fn best_trade(&self, a_to_b: bool, price: f64) -> Option<(u64, f64)> {
+        let profit = |amount| self.profit(amount, a_to_b, price);
+        let (input_reserve, _) = self.pool.reserves(a_to_b);
+
+        let mut high = (input_reserve / 1_000_000).max(1);
+        if profit(high) <= 0.0 {
+            return None;
+        }
+        while high < u64::MAX / 4 && profit(high * 2) > profit(high) {
+            high *= 2;
+        }
+
+        let (mut low, mut high) = (high / 2, high * 2);
+        while high - low > 2 {
+            let left = low + (high - low) / 3;
+            let right = high - (high - low) / 3;
+            if profit(left) < profit(right) {
+                low = left;
+            } else {
+                high = right;
+            }
+        }
+        (low..=high)
+            .map(|amount| (amount, profit(amount)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, profit)| *profit > 0.0)
+    }
+
+    fn arbitrage(&mut self, price: f64, min_profit: f64) {
+        let best = [true, false]
+            .into_iter()
+            .filter_map(|a_to_b| self.best_trade(a_to_b, price).map(|(amount, profit)| (a_to_b, amount, profit)))
+            .max_by(|a, b| a.2.total_cmp(&b.2));
+        if let Some((a_to_b, amount, profit)) = best {
+            if profit > min_profit {
+                self.swap(amount, a_to_b, price, Trader::Arbitrageur);
+            }
+        }
+    }
+
+    // Trade of `size` whole token A: positive sells A, negative buys A with
+    // that much A's worth of B
+    fn noise_trade(&mut self, size: f64, price: f64) -> Option<bool> {
+        let (amount, a_to_b) = if size > 0.0 {
+            (size * self.scale_a, true)
+        } else {
+            (-size * price * self.scale_b, false)
+        };
+        let amount = amount.round();
+        if amount < 1.0 || amount >= u64::MAX as f64 {
+            return None;
+        }
+        Some(self.swap(amount as u64, a_to_b, price, Trader::Noise))
+    }
+
+    fn value(&self, price: f64) -> f64 {
+        self.pool.reserve_a as f64 / self.scale_a * price + self.pool.reserve_b as f64 / self.scale_b
+    }
+}
+
+// Deterministic generator for noise trades (SplitMix64)
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn new_pool(scenario: Scenario, settings: &Settings, reserve_a: u64, reserve_b: u64) -> Result<PoolState> {
+    // Rates bring both tokens to the larger decimals, as initialize_pool sets them
+    let common_decimals = settings.decimals_a.max(settings.decimals_b);
+    let rate = |decimals: u8| 10u64.checked_pow(u32::from(common_decimals - decimals));
+    let (Some(token_a_rate), Some(token_b_rate)) = (rate(settings.decimals_a), rate(settings.decimals_b)) else {
+        bail!("token decimals {} and {} are too far apart", settings.decimals_a, settings.decimals_b);
+    };
+
+    Ok(PoolState {
+        token_a: Pubkey::default(),
+        token_b: Pubkey::default(),
+        lp_mint: Pubkey::default(),
+        fee_rate: scenario.fee_rate,
+        pool_type: scenario.pool_type,
+        bump: 0,
+        version: POOL_STATE_VERSION,
+        token_a_decimals: settings.decimals_a,
+        token_b_decimals: settings.decimals_b,
+        token_a_rate,
+        token_b_rate,
+        reserve_a,
+        reserve_b,
+        token_a_flags: 0,
+        token_b_flags: 0,
+        creator: Pubkey::default(),
+        creator_fee_share: 0,
+        creator_fees_a: 0,
+        creator_fees_b: 0,
+        // Full-range liquidity only: no ranged positions to price through ticks
+        sqrt_price_x64: 0,
+        tick_current: 0,
+        liquidity: 0,
+        position_reserve_a: 0,
+        position_reserve_b: 0,
+        fee_growth_global_a_x64: 0,
+        fee_growth_global_b_x64: 0,
+        position_fees_a: 0,
+        position_fees_b: 0,
+        order_liquidity: 0,
+        breaker_slot: 0,
+        breaker_price_x64: 0,
+        reserved: [0; 30],
+    })
+}
+
+pub fn simulate(scenario: Scenario, settings: &Settings, series: &[PricePoint]) -> Result<Report> {
+    let Some(first) = series.first() else {
+        bail!("price series is empty");
+    };
+    let scale_a = 10f64.powi(settings.decimals_a as i32);
+    let scale_b = 10f64.powi(settings.decimals_b as i32);
+    let deposit_a = (settings.liquidity_a * scale_a).round();
+    let deposit_b = (settings.liquidity_a * first.price * scale_b).round();
+    if !(deposit_a >= 1.0 && deposit_b >= 1.0 && deposit_a < u64::MAX as f64 && deposit_b < u64::MAX as f64) {
+        bail!("liquidity of {} token A doesn't fit the pool at price {}", settings.liquidity_a, first.price);
+    }
+    let (deposit_a, deposit_b) = (deposit_a as u64, deposit_b as u64);
+
+    let mut market = Market {
+        pool: new_pool(scenario, settings, deposit_a, deposit_b)?,
+        scale_a,
+        scale_b,
+        fees_a: 0,
+        fees_b: 0,
+        arbitrage_volume: 0.0,
+        noise_volume: 0.0,
+        swaps: 0,
+    };
+    let start_value = market.value(first.price);
+    let mut rng = Rng(settings.seed);
+    let mut unfilled_trades = 0;
+
+    for point in series {
+        market.arbitrage(point.price, settings.arb_min_profit);
+
+        let generated = (settings.noise_size > 0.0).then(|| {
+            let size = rng.next_f64() * 2.0 * settings.noise_size;
+            if rng.next_u64() & 1 == 0 { size } else { -size }
+        });
+        for size in point.trade.into_iter().chain(generated) {
+            if market.noise_trade(size, point.price) == Some(false) {
+                unfilled_trades += 1;
+            }
+        }
+    }
+
+    let last_price = series.last().map_or(first.price, |point| point.price);
+    let end_value = market.value(last_price);
+    let hodl_value = deposit_a as f64 / scale_a * last_price + deposit_b as f64 / scale_b;
+    let fees_earned = market.fees_a as f64 / scale_a * last_price + market.fees_b as f64 / scale_b;
+
+    Ok(Report {
+        pool_type: scenario.pool_type,
+        fee_rate: scenario.fee_rate,
+        start_value,
+        end_value,
+        hodl_value,
+        pnl: end_value - start_value,
+        fees_earned,
+        impermanent_loss: (end_value - fees_earned) / hodl_value - 1.0,
+        volume: market.arbitrage_volume + market.noise_volume,
+        arbitrage_volume: market.arbitrage_volume,
+        noise_volume: market.noise_volume,
+        swaps: market.swaps,
+        unfilled_trades,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(noise_size: f64) -> Settings {
+        Settings { decimals_a: 6, decimals_b: 6, liquidity_a: 1_000_000.0, noise_size, arb_min_profit: 0.0, seed: 7 }
+    }
+
+    fn series(prices: &[f64]) -> Vec<PricePoint> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(index, &price)| PricePoint { time: index.to_string(), price, trade: None })
+            .collect()
+    }
+
+    #[test]
+    fn quiet_market_changes_nothing() {
+        let scenario = Scenario { pool_type: 0, fee_rate: 300 };
+        let report = simulate(scenario, &settings(0.0), &series(&[2.0, 2.0, 2.0])).unwrap();
+        assert_eq!(report.swaps, 0);
+        assert_eq!(report.pnl, 0.0);
+        assert_eq!(report.impermanent_loss, 0.0);
+    }
+
+    #[test]
+    fn fee_free_constant_product_loses_the_textbook_amount() {
+        // Price x4: a constant product LP keeps 2*sqrt(4)/(1+4) = 80% of holding
+        for pool_type in [0, 2] {
+            let report = simulate(Scenario { pool_type, fee_rate: 0 }, &settings(0.0), &series(&[1.0, 4.0])).unwrap();
+            assert!((report.impermanent_loss + 0.2).abs() < 1e-4, "{report:?}");
+            assert_eq!(report.fees_earned, 0.0);
+        }
+    }
+
+    #[test]
+    fn arbitrage_stops_inside_the_fee_band() {
+        let scenario = Scenario { pool_type: 0, fee_rate: 300 };
+        let report = simulate(scenario, &settings(0.0), &series(&[1.0, 1.002, 1.1])).unwrap();
+        // The 0.2% move is inside the 0.3% fee, the 10% one isn't
+        assert_eq!(report.swaps, 1);
+        assert!(report.impermanent_loss < 0.0);
+        assert!(report.fees_earned > 0.0);
+    }
+
+    #[test]
+    fn noise_trading_pays_the_lp() {
+        let prices = vec![1.0; 200];
+        for pool_type in [0, 1, 2] {
+            let report = simulate(Scenario { pool_type, fee_rate: 300 }, &settings(1_000.0), &series(&prices)).unwrap();
+            assert!(report.noise_volume > 0.0 && report.fees_earned > 0.0, "{report:?}");
+            assert!(report.pnl > 0.0, "{report:?}");
+        }
+    }
+}
@@ -31,6 +31,7 @@ const IDL = {
       "accounts": [
         {
           "name": "pool_state",
+          "writable": true,
           "pda": {
             "seeds": [
               {
@@ -148,6 +149,12 @@ const IDL = {
         {
           "name": "amount_b",
           "type": "u64"
+        },
+        {
+          "name": "deadline",
+          "type": {
+            "option": "i64"
+          }
         }
       ]
     }
@@ -342,7 +349,8 @@ async function testAddLiquidity() {
     const addLiquidityTx = await program.methods
       .addLiquidity(
         new anchor.BN(amountNtd),
-        new anchor.BN(amountUsd)
+        new anchor.BN(amountUsd),
+        null // deadline
       )
       .accounts({
         poolState,
@@ -30,6 +30,7 @@ const IDL = {
       "accounts": [
         {
           "name": "pool_state",
+          "writable": true,
           "pda": {
             "seeds": [
               {
@@ -151,6 +152,12 @@ const IDL = {
         {
           "name": "minimum_b_out",
           "type": "u64"
+        },
+        {
+          "name": "deadline",
+          "type": {
+            "option": "i64"
+          }
         }
       ]
     }
@@ -330,7 +337,8 @@ async function testRemoveLiquidity() {
       .removeLiquidity(
         new anchor.BN(lpToRemove.toString()),
         new anchor.BN(minimumNtdOut),
-        new anchor.BN(minimumUsdOut)
+        new anchor.BN(minimumUsdOut),
+        null // deadline
       )
       .accounts({
         poolState,
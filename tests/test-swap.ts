@@ -31,6 +31,7 @@ const IDL = {
       "accounts": [
         {
           "name": "pool_state",
+          "writable": true,
           "pda": {
             "seeds": [
               {
@@ -144,6 +145,18 @@ const IDL = {
         {
           "name": "a_to_b",
           "type": "bool"
+        },
+        {
+          "name": "callback_data",
+          "type": {
+            "option": "bytes"
+          }
+        },
+        {
+          "name": "deadline",
+          "type": {
+            "option": "i64"
+          }
         }
       ]
     }
@@ -312,7 +325,12 @@ async function testSwap() {
       .swap(
         new anchor.BN(swapAmount),
         new anchor.BN(minimumOut),
-        true // a_to_b: NTD -> USD
+        true, // a_to_b: NTD -> USD
+        null, // callback_data: plain swap, not a flash swap
+        null, // deadline
+        0, // referral_fee_bps: no referrer
+        null, // max_price_impact_bps
+        null // sqrt_price_limit_x64
       )
       .accounts({
         poolState,